@@ -0,0 +1,384 @@
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+use chrono::{DateTime, Utc};
+use globset::GlobSet;
+use serde::{Deserialize, Serialize};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+use crate::engine::create_engine;
+use crate::error::{AppError, Result};
+use crate::models::{EngineType, Project};
+use crate::replace::build_globs;
+use crate::site_config::{config_path, load_site_config};
+
+/// 压缩包根目录中的项目清单
+pub const MANIFEST_FILE: &str = "pineapple-project.json";
+const MANIFEST_VERSION: u32 = 1;
+
+/// 解压的上限，防止压缩炸弹占满磁盘
+struct ImportLimits {
+    entries: usize,
+    entry_size: u64,
+    total_size: u64,
+}
+
+const IMPORT_LIMITS: ImportLimits = ImportLimits {
+    entries: 100_000,
+    entry_size: 1 << 30,
+    total_size: 4 << 30,
+};
+
+/// 导出选项。排除规则是相对于站点根目录的 glob，匹配到目录时跳过整个目录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportOptions {
+    pub exclude: Vec<String>,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            exclude: [
+                "public",
+                "resources/_gen",
+                ".git",
+                // 编辑器在本机的缓存：部署仓库和快照、修订历史与搜索索引
+                ".pineapple/deploy",
+                ".pineapple/project.db*",
+            ]
+            .map(String::from)
+            .to_vec(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+    pub version: u32,
+    pub name: String,
+    pub engine: EngineType,
+    /// 站点配置中固定的引擎版本（Hugo 的 `module.hugoVersion.min`），没有时为导出时安装的版本
+    pub engine_version: Option<String>,
+    pub theme: Option<String>,
+    pub files: usize,
+    pub exported_at: DateTime<Utc>,
+}
+
+/// 把项目打包成 zip，先写入临时文件，完成后再替换目标文件
+pub fn export_project(
+    project_path: &Path,
+    engine: &EngineType,
+    archive_path: &Path,
+    options: &ExportOptions,
+) -> Result<ArchiveManifest> {
+    if !project_path.is_dir() {
+        return Err(AppError::PathNotFound(project_path.display().to_string()));
+    }
+    let exclude = build_globs(&options.exclude)?;
+    // 规范化后再比较，`./site.zip`、`sub/../site.zip` 之类的路径也能认出压缩包本身
+    let root = project_path.canonicalize()?;
+    let mut files = Vec::new();
+    collect_files(&root, &root, &exclude, &canonical_archive_path(archive_path)?, &mut files)?;
+
+    let site_config = load_site_config(project_path)?;
+    let manifest = ArchiveManifest {
+        version: MANIFEST_VERSION,
+        name: project_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| "site".to_string()),
+        engine: engine.clone(),
+        engine_version: pinned_version(&site_config)
+            .or_else(|| create_engine(engine.clone()).and_then(|engine| engine.version()).ok()),
+        theme: theme(&site_config),
+        files: files.len(),
+        exported_at: Utc::now(),
+    };
+
+    let temp_path = archive_path.with_extension("zip.tmp");
+    let result = write_archive(&temp_path, &manifest, &files);
+    if let Err(e) = result.and_then(|_| Ok(fs::rename(&temp_path, archive_path)?)) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e);
+    }
+    Ok(manifest)
+}
+
+pub fn read_manifest(archive_path: &Path) -> Result<ArchiveManifest> {
+    let mut archive = ZipArchive::new(File::open(archive_path)?)?;
+    manifest_from(&mut archive)
+}
+
+/// 把压缩包解压到 `destination`（不存在或为空目录），返回导入后的项目。
+/// 绝对路径、包含 `..` 的路径和符号链接都会被拒绝，不会写到目标目录之外；
+/// 文件数和解压后的大小超过上限时拒绝导入
+pub fn import_project(archive_path: &Path, destination: &Path) -> Result<Project> {
+    extract(archive_path, destination, &IMPORT_LIMITS)
+}
+
+fn extract(archive_path: &Path, destination: &Path, limits: &ImportLimits) -> Result<Project> {
+    let mut archive = ZipArchive::new(File::open(archive_path)?)?;
+    let manifest = manifest_from(&mut archive)?;
+    if archive.len() > limits.entries {
+        return Err(AppError::ArchiveError(format!(
+            "Archive has {} entries, more than {}",
+            archive.len(),
+            limits.entries
+        )));
+    }
+
+    let mut total_size = 0u64;
+    let entries = (0..archive.len())
+        .map(|index| {
+            let entry = archive.by_index(index)?;
+            if entry.is_symlink() {
+                return Err(AppError::InvalidPath(format!("Symbolic link in archive: {}", entry.name())));
+            }
+            let path = entry
+                .enclosed_name()
+                .filter(|path| path.components().all(|c| matches!(c, Component::Normal(_))))
+                .ok_or_else(|| AppError::InvalidPath(entry.name().to_string()))?;
+            if entry.size() > limits.entry_size {
+                return Err(AppError::ArchiveError(format!("{} is too large", entry.name())));
+            }
+            total_size += entry.size();
+            if total_size > limits.total_size {
+                return Err(AppError::ArchiveError("Archive is too large when extracted".to_string()));
+            }
+            Ok((index, path, entry.is_dir()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let created = !destination.exists();
+    if !created && fs::read_dir(destination)?.next().is_some() {
+        return Err(AppError::ProjectExists(destination.display().to_string()));
+    }
+    fs::create_dir_all(destination)?;
+
+    let extracted = entries
+        .into_iter()
+        .filter(|(_, path, _)| path != Path::new(MANIFEST_FILE))
+        .try_for_each(|(index, path, is_dir)| -> Result<()> {
+            let target = destination.join(&path);
+            if is_dir {
+                fs::create_dir_all(&target)?;
+                return Ok(());
+            }
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let mut entry = archive.by_index(index)?;
+            // 声明的大小已经检查过，解压出的内容不能比声明的多
+            let size = entry.size();
+            let copied = io::copy(&mut (&mut entry).take(size + 1), &mut File::create(&target)?)?;
+            if copied > size {
+                return Err(AppError::ArchiveError(format!("{} is larger than declared", entry.name())));
+            }
+            #[cfg(unix)]
+            if let Some(mode) = entry.unix_mode() {
+                use std::os::unix::fs::PermissionsExt;
+                fs::set_permissions(&target, fs::Permissions::from_mode((mode & 0o755) | 0o600))?;
+            }
+            Ok(())
+        });
+    if let Err(e) = extracted {
+        if created {
+            let _ = fs::remove_dir_all(destination);
+        }
+        return Err(e);
+    }
+
+    let mut project = Project::new(manifest.name, destination.to_path_buf(), manifest.engine);
+    project.theme = manifest.theme;
+    Ok(project)
+}
+
+fn manifest_from(archive: &mut ZipArchive<File>) -> Result<ArchiveManifest> {
+    let entry = archive
+        .by_name(MANIFEST_FILE)
+        .map_err(|_| AppError::ArchiveError(format!("Not a project archive: missing {}", MANIFEST_FILE)))?;
+    let manifest: ArchiveManifest = serde_json::from_reader(entry)?;
+    if manifest.version > MANIFEST_VERSION {
+        return Err(AppError::ArchiveError(format!(
+            "Archive version {} is newer than supported",
+            manifest.version
+        )));
+    }
+    Ok(manifest)
+}
+
+fn write_archive(path: &Path, manifest: &ArchiveManifest, files: &[(PathBuf, String)]) -> Result<()> {
+    let mut writer = ZipWriter::new(File::create(path)?);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    writer.start_file(MANIFEST_FILE, options)?;
+    serde_json::to_writer_pretty(&mut writer, manifest)?;
+    for (file, relative) in files {
+        let metadata = fs::metadata(file)?;
+        let mut file_options = options.large_file(metadata.len() >= u32::MAX as u64);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            file_options = file_options.unix_permissions(metadata.permissions().mode());
+        }
+        writer.start_file(relative.as_str(), file_options)?;
+        io::copy(&mut File::open(file)?, &mut writer)?;
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+/// 压缩包可能还不存在，只规范化所在的目录
+fn canonical_archive_path(archive_path: &Path) -> Result<PathBuf> {
+    let name = archive_path
+        .file_name()
+        .ok_or_else(|| AppError::InvalidPath(archive_path.display().to_string()))?;
+    let parent = match archive_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    Ok(parent.canonicalize()?.join(name))
+}
+
+/// 站点中的文件及其相对路径（使用 `/` 分隔），包括隐藏目录；跳过符号链接和导出的压缩包本身
+fn collect_files(
+    project_path: &Path,
+    dir: &Path,
+    exclude: &GlobSet,
+    archive_path: &Path,
+    files: &mut Vec<(PathBuf, String)>,
+) -> Result<()> {
+    let mut entries: Vec<_> = fs::read_dir(dir)?.flatten().collect();
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let path = entry.path();
+        let relative = path
+            .strip_prefix(project_path)
+            .unwrap_or(&path)
+            .to_string_lossy()
+            .replace('\\', "/");
+        let file_type = entry.file_type()?;
+        if file_type.is_symlink() || exclude.is_match(&relative) || path == archive_path {
+            continue;
+        }
+        if file_type.is_dir() {
+            collect_files(project_path, &path, exclude, archive_path, files)?;
+        } else if relative != MANIFEST_FILE {
+            files.push((path, relative));
+        }
+    }
+    Ok(())
+}
+
+fn pinned_version(site_config: &serde_json::Value) -> Option<String> {
+    config_path(site_config, &["module", "hugoVersion", "min"])
+        .and_then(|value| value.as_str())
+        .map(str::to_string)
+}
+
+/// Hugo 的 `theme` 可以是字符串或列表，列表时取第一个
+fn theme(site_config: &serde_json::Value) -> Option<String> {
+    match config_path(site_config, &["theme"])? {
+        serde_json::Value::String(theme) => Some(theme.clone()),
+        serde_json::Value::Array(themes) => themes.first()?.as_str().map(str::to_string),
+        _ => None,
+    }
+    .filter(|theme| !theme.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    /// 手工构造的压缩包，带有效的项目清单
+    fn archive(path: &Path, entries: &[(&str, &[u8])]) {
+        let manifest = ArchiveManifest {
+            version: MANIFEST_VERSION,
+            name: "blog".to_string(),
+            engine: EngineType::Hugo,
+            engine_version: None,
+            theme: None,
+            files: entries.len(),
+            exported_at: Utc::now(),
+        };
+        let mut writer = ZipWriter::new(File::create(path).unwrap());
+        let options = SimpleFileOptions::default();
+        writer.start_file(MANIFEST_FILE, options).unwrap();
+        serde_json::to_writer(&mut writer, &manifest).unwrap();
+        for (name, data) in entries {
+            writer.start_file(*name, options).unwrap();
+            writer.write_all(data).unwrap();
+        }
+        writer.finish().unwrap();
+    }
+
+    #[test]
+    fn rejects_entries_outside_the_destination() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["../evil.txt", "content/../../evil.txt", "/tmp/evil.txt"] {
+            let zip = dir.path().join("evil.zip");
+            archive(&zip, &[("content/post.md", b"ok"), (name, b"evil")]);
+            let destination = dir.path().join("site/imported");
+            let result = import_project(&zip, &destination);
+            assert!(matches!(result, Err(AppError::InvalidPath(_))), "{}", name);
+            assert!(!destination.exists(), "{}", name);
+            assert!(!dir.path().join("evil.txt").exists());
+            assert!(!dir.path().join("site/evil.txt").exists());
+        }
+    }
+
+    #[test]
+    fn enforces_size_and_entry_limits() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip = dir.path().join("site.zip");
+        archive(&zip, &[("a.md", &[b'a'; 600]), ("b.md", &[b'b'; 600])]);
+        let destination = dir.path().join("site");
+        let limits = |entries, entry_size, total_size| ImportLimits {
+            entries,
+            entry_size,
+            total_size,
+        };
+
+        for (limits, message) in [
+            (limits(2, 1000, 2000), "entries"),
+            (limits(10, 500, 2000), "too large"),
+            (limits(10, 1000, 1000), "too large when extracted"),
+        ] {
+            let error = extract(&zip, &destination, &limits).unwrap_err();
+            assert!(matches!(error, AppError::ArchiveError(_)));
+            assert!(error.to_string().contains(message), "{}", error);
+            assert!(!destination.exists());
+        }
+
+        extract(&zip, &destination, &limits(10, 1000, 2000)).unwrap();
+        assert_eq!(fs::read(destination.join("b.md")).unwrap(), [b'b'; 600]);
+    }
+
+    #[test]
+    fn export_skips_the_archive_itself() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("site");
+        fs::create_dir_all(project.join("content/posts")).unwrap();
+        fs::create_dir_all(project.join("public")).unwrap();
+        fs::write(project.join("hugo.toml"), "title = 'Blog'\ntheme = 'ananke'\n").unwrap();
+        fs::write(project.join("content/posts/hello.md"), "# Hello").unwrap();
+        fs::write(project.join("public/index.html"), "built").unwrap();
+
+        // 通过带 `..` 的路径导出到项目目录中，上一次导出的压缩包不能被打包进去
+        let zip = project.join("content/../site.zip");
+        fs::write(project.join("site.zip"), "previous export").unwrap();
+        let manifest = export_project(&project, &EngineType::Hugo, &zip, &ExportOptions::default()).unwrap();
+        assert_eq!(manifest.files, 2);
+        assert_eq!(manifest.theme.as_deref(), Some("ananke"));
+
+        let imported = dir.path().join("imported");
+        let project_info = import_project(&zip, &imported).unwrap();
+        assert_eq!(project_info.name, "site");
+        assert_eq!(fs::read_to_string(imported.join("content/posts/hello.md")).unwrap(), "# Hello");
+        assert!(!imported.join("site.zip").exists());
+        assert!(!imported.join("public").exists());
+        assert!(!imported.join(MANIFEST_FILE).exists());
+    }
+}
@@ -1,9 +1,40 @@
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
 use std::fs;
+use crate::archive::{self, ArchiveManifest, ExportOptions};
+use crate::db::open_project_db;
+use crate::deploy::{
+    self, DeployGateSettings, DeployOutcome, DeployProgress, DeployRecord, DeployResult, DeployStatus,
+    DeployTargetConfig, GateReport, Progress, DEPLOY_PROGRESS_EVENT,
+};
 use crate::engine::create_engine;
-use crate::models::{BuildOptions, BuildResult, EngineType, Project, Post};
-use crate::utils::{ensure_directory_exists, validate_path};
+use crate::git::{BranchInfo, CommitInfo, GitSettings, PostDiff, PullOutcome, RepoStatus, SiteRepo};
+use crate::hooks::{self, Hook, HookOutput, HookRun, HookStage, HOOK_OUTPUT_EVENT};
+use crate::links::{self, ExternalCheckOptions, ExternalLinkReport, LinkReport};
+use crate::markdown::{
+    block_at, block_for_line, document_blocks, render_document, MarkdownConfig, RenderedMarkdown,
+    SourceBlock,
+};
+use crate::models::{BuildOptions, BuildProfile, BuildResult, EngineType, Project, Post, ServeOptions};
+use crate::preview::lan::reachable_urls;
+use crate::preview::{
+    find_free_port, list_lan_interfaces, localhost, reserve_port, NetworkInterface, EngineServers, PreviewServers,
+    StaticPreviewServer,
+};
+use crate::move_post::{self, MovePlan, MoveRequest};
+use crate::replace::{self, FileMatches, FindOptions, ReplaceResult, ReplaceSelection, UndoRecord};
+use crate::revisions::{self, RevisionDiff, RevisionInfo, RevisionSettings};
+use crate::scheduler::{self, PublishRecord, PublishScheduler, PublishSettings, ScheduleCheck, ScheduledPost};
+use crate::search::{refresh_index, search, update_file, IndexStats, SearchHit};
+use crate::secrets::{self, Credentials};
+use crate::settings::{load_project_settings, save_project_settings};
+use crate::shortcodes::{discover_shortcodes, ShortcodeInfo};
+use crate::site_config::load_site_config;
+use crate::taxonomy::{self, TaxonomyChange, TaxonomyEdit, TaxonomyTerms};
+use crate::utils::{ensure_directory_exists, find_project_root, read_site_base_url, validate_path};
 use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, State};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PostInfo {
@@ -77,38 +108,351 @@ pub async fn open_project(path: String) -> Result<Project, String> {
     Ok(project)
 }
 
+/// 把项目打包成 zip，默认不包含构建输出、`resources/_gen` 和 `.git`
+#[tauri::command]
+pub async fn export_project(
+    project_path: String,
+    engine: EngineType,
+    archive_path: String,
+    options: Option<ExportOptions>,
+) -> Result<ArchiveManifest, String> {
+    archive::export_project(
+        &PathBuf::from(&project_path),
+        &engine,
+        &PathBuf::from(&archive_path),
+        &options.unwrap_or_default(),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// 读取压缩包中的项目清单，导入前显示给用户确认
+#[tauri::command]
+pub async fn read_project_archive(archive_path: String) -> Result<ArchiveManifest, String> {
+    archive::read_manifest(&PathBuf::from(&archive_path)).map_err(|e| e.to_string())
+}
+
+/// 把导出的压缩包解压到指定目录（不存在或为空），并作为项目打开
+#[tauri::command]
+pub async fn import_project(archive_path: String, destination: String) -> Result<Project, String> {
+    archive::import_project(&PathBuf::from(&archive_path), &PathBuf::from(&destination)).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn build_project(
+    app: AppHandle,
+    previews: State<'_, PreviewServers>,
     project_path: String,
     engine: EngineType,
     options: BuildOptions,
 ) -> Result<BuildResult, String> {
     let path = PathBuf::from(&project_path);
-    let engine_adapter = create_engine(engine).map_err(|e| e.to_string())?;
+    let settings = load_project_settings(&path).map_err(|e| e.to_string())?;
 
-    let result = engine_adapter
-        .build(&path, &options)
+    let emit = |output: HookOutput| {
+        let _ = app.emit(HOOK_OUTPUT_EVENT, output);
+    };
+    let result = hooks::build(&path, &engine, &options, &settings.hooks, &emit)
         .await
         .map_err(|e| e.to_string())?;
 
+    if result.success {
+        previews.notify_reload(&path);
+    }
+
     Ok(result)
 }
 
+/// 项目的构建和部署钩子
+#[tauri::command]
+pub async fn get_hooks(project_path: String) -> Result<Vec<Hook>, String> {
+    let settings = load_project_settings(&PathBuf::from(&project_path)).map_err(|e| e.to_string())?;
+    Ok(settings.hooks)
+}
+
+#[tauri::command]
+pub async fn save_hooks(project_path: String, hooks: Vec<Hook>) -> Result<(), String> {
+    let path = PathBuf::from(&project_path);
+    let mut settings = load_project_settings(&path).map_err(|e| e.to_string())?;
+    settings.hooks = hooks;
+    save_project_settings(&path, &settings).map_err(|e| e.to_string())
+}
+
+/// 单独运行一个阶段的钩子，用于在设置界面中试运行；输出通过 `hook-output` 事件通知前端
+#[tauri::command]
+pub async fn run_hook_stage(app: AppHandle, project_path: String, stage: HookStage) -> Result<Vec<HookRun>, String> {
+    let path = PathBuf::from(&project_path);
+    let settings = load_project_settings(&path).map_err(|e| e.to_string())?;
+
+    let emit = |output: HookOutput| {
+        let _ = app.emit(HOOK_OUTPUT_EVENT, output);
+    };
+    hooks::run_stage(&path, &settings.hooks, stage, &[], &emit)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 列出项目的构建配置，包括没有被覆盖的内置配置
+#[tauri::command]
+pub async fn list_build_profiles(project_path: String, engine: EngineType) -> Result<Vec<BuildProfile>, String> {
+    let settings = load_project_settings(&PathBuf::from(&project_path)).map_err(|e| e.to_string())?;
+    Ok(settings.profiles(&engine))
+}
+
+/// 新增或覆盖同名构建配置
+#[tauri::command]
+pub async fn save_build_profile(project_path: String, profile: BuildProfile) -> Result<(), String> {
+    let path = PathBuf::from(&project_path);
+    let mut settings = load_project_settings(&path).map_err(|e| e.to_string())?;
+
+    match settings.build_profiles.iter_mut().find(|p| p.name == profile.name) {
+        Some(existing) => *existing = profile,
+        None => settings.build_profiles.push(profile),
+    }
+
+    save_project_settings(&path, &settings).map_err(|e| e.to_string())
+}
+
+/// 删除保存的构建配置；同名的内置配置会重新生效
+#[tauri::command]
+pub async fn delete_build_profile(project_path: String, name: String) -> Result<(), String> {
+    let path = PathBuf::from(&project_path);
+    let mut settings = load_project_settings(&path).map_err(|e| e.to_string())?;
+    settings.build_profiles.retain(|p| p.name != name);
+    save_project_settings(&path, &settings).map_err(|e| e.to_string())
+}
+
+/// 使用保存的构建配置构建项目
+#[tauri::command]
+pub async fn build_with_profile(
+    app: AppHandle,
+    previews: State<'_, PreviewServers>,
+    project_path: String,
+    engine: EngineType,
+    profile: String,
+) -> Result<BuildResult, String> {
+    let path = PathBuf::from(&project_path);
+    let settings = load_project_settings(&path).map_err(|e| e.to_string())?;
+    let profile = settings.profile(&profile, &engine).map_err(|e| e.to_string())?;
+
+    let emit = |output: HookOutput| {
+        let _ = app.emit(HOOK_OUTPUT_EVENT, output);
+    };
+    let result = hooks::build(&path, &engine, &profile.options, &settings.hooks, &emit)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if result.success {
+        previews.notify_reload(&path);
+    }
+
+    Ok(result)
+}
+
+/// 列出项目的部署目标
+#[tauri::command]
+pub async fn list_deploy_targets(project_path: String) -> Result<Vec<DeployTargetConfig>, String> {
+    let settings = load_project_settings(&PathBuf::from(&project_path)).map_err(|e| e.to_string())?;
+    Ok(settings.deploy_targets)
+}
+
+/// 新增或覆盖同名部署目标
+#[tauri::command]
+pub async fn save_deploy_target(project_path: String, target: DeployTargetConfig) -> Result<(), String> {
+    let path = PathBuf::from(&project_path);
+    let mut settings = load_project_settings(&path).map_err(|e| e.to_string())?;
+
+    match settings.deploy_targets.iter_mut().find(|t| t.name == target.name) {
+        Some(existing) => *existing = target,
+        None => settings.deploy_targets.push(target),
+    }
+
+    save_project_settings(&path, &settings).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_deploy_target(project_path: String, name: String) -> Result<(), String> {
+    let path = PathBuf::from(&project_path);
+    let mut settings = load_project_settings(&path).map_err(|e| e.to_string())?;
+    settings.deploy_targets.retain(|t| t.name != name);
+    save_project_settings(&path, &settings).map_err(|e| e.to_string())?;
+    secrets::delete_credentials(&path, &name).await.map_err(|e| e.to_string())
+}
+
+/// 把部署目标的凭据保存到系统钥匙串，覆盖原有内容
+#[tauri::command]
+pub async fn save_deploy_credentials(project_path: String, target: String, credentials: Credentials) -> Result<(), String> {
+    secrets::save_credentials(&PathBuf::from(&project_path), &target, &credentials)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 已保存的凭据字段名，不返回内容
+#[tauri::command]
+pub async fn get_deploy_credential_keys(project_path: String, target: String) -> Result<Vec<String>, String> {
+    secrets::credential_keys(&PathBuf::from(&project_path), &target)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_deploy_credentials(project_path: String, target: String) -> Result<(), String> {
+    secrets::delete_credentials(&PathBuf::from(&project_path), &target)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 构建并部署到指定的部署目标，进度通过 `deploy-progress` 事件通知前端。
+/// 构建失败或部署前检查不通过时返回检查报告，不部署
+#[tauri::command]
+pub async fn deploy_site(
+    app: AppHandle,
+    project_path: String,
+    engine: EngineType,
+    target: String,
+) -> Result<DeployResult, String> {
+    let path = PathBuf::from(&project_path);
+    let settings = load_project_settings(&path).map_err(|e| e.to_string())?;
+    let target = settings.deploy_target(&target).map_err(|e| e.to_string())?;
+
+    let emit = |progress: DeployProgress| {
+        let _ = app.emit(DEPLOY_PROGRESS_EVENT, progress);
+    };
+    let emit_output = |output: HookOutput| {
+        let _ = app.emit(HOOK_OUTPUT_EVENT, output);
+    };
+    let progress = Progress::new(&target.name, &emit);
+    deploy::deploy(&path, &engine, target, &progress, &emit_output)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_deploy_gate_settings(project_path: String) -> Result<DeployGateSettings, String> {
+    let settings = load_project_settings(&PathBuf::from(&project_path)).map_err(|e| e.to_string())?;
+    Ok(settings.deploy_gate)
+}
+
+#[tauri::command]
+pub async fn save_deploy_gate_settings(project_path: String, gate: DeployGateSettings) -> Result<(), String> {
+    let path = PathBuf::from(&project_path);
+    let mut settings = load_project_settings(&path).map_err(|e| e.to_string())?;
+    settings.deploy_gate = gate;
+    save_project_settings(&path, &settings).map_err(|e| e.to_string())
+}
+
+/// 用部署目标的构建配置构建站点并运行部署前检查，返回完整的检查报告
+#[tauri::command]
+pub async fn check_deploy(
+    app: AppHandle,
+    project_path: String,
+    engine: EngineType,
+    target: String,
+) -> Result<GateReport, String> {
+    let path = PathBuf::from(&project_path);
+    let settings = load_project_settings(&path).map_err(|e| e.to_string())?;
+    let target = settings.deploy_target(&target).map_err(|e| e.to_string())?;
+
+    let emit = |output: HookOutput| {
+        let _ = app.emit(HOOK_OUTPUT_EVENT, output);
+    };
+    deploy::check(&path, &engine, target, &emit).await.map_err(|e| e.to_string())
+}
+
+/// 查询一次部署的状态，部署服务还在处理时由前端轮询
+#[tauri::command]
+pub async fn deploy_status(project_path: String, target: String, revision: String) -> Result<DeployStatus, String> {
+    let path = PathBuf::from(&project_path);
+    let settings = load_project_settings(&path).map_err(|e| e.to_string())?;
+    let target = settings.deploy_target(&target).map_err(|e| e.to_string())?;
+    deploy::status(&path, target, &revision).await.map_err(|e| e.to_string())
+}
+
+/// 部署记录，最近的在前；不指定部署目标时列出所有目标
+#[tauri::command]
+pub async fn list_deploy_history(project_path: String, target: Option<String>) -> Result<Vec<DeployRecord>, String> {
+    deploy::history::list(&PathBuf::from(&project_path), target.as_deref()).map_err(|e| e.to_string())
+}
+
+/// 一次部署的文件清单：相对路径 → SHA-256
+#[tauri::command]
+pub async fn get_deploy_manifest(project_path: String, id: i64) -> Result<BTreeMap<String, String>, String> {
+    deploy::history::manifest(&PathBuf::from(&project_path), id).map_err(|e| e.to_string())
+}
+
+/// 把之前一次成功部署的构建输出重新部署到同一个目标
+#[tauri::command]
+pub async fn rollback_deploy(
+    app: AppHandle,
+    project_path: String,
+    target: String,
+    id: i64,
+) -> Result<DeployOutcome, String> {
+    let path = PathBuf::from(&project_path);
+    let settings = load_project_settings(&path).map_err(|e| e.to_string())?;
+    let target = settings.deploy_target(&target).map_err(|e| e.to_string())?;
+
+    let emit = |progress: DeployProgress| {
+        let _ = app.emit(DEPLOY_PROGRESS_EVENT, progress);
+    };
+    let emit_output = |output: HookOutput| {
+        let _ = app.emit(HOOK_OUTPUT_EVENT, output);
+    };
+    let progress = Progress::new(&target.name, &emit);
+    deploy::rollback(&path, target, id, &progress, &emit_output)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[derive(Debug, Serialize)]
+pub struct ServeInfo {
+    pub url: String,
+    /// 所有可访问的地址；局域网预览时包含本机的局域网地址
+    pub urls: Vec<String>,
+    /// 是否为局域网预览（同一网络中的设备都能访问）
+    pub lan: bool,
+}
+
+/// 启动引擎自带的预览服务器，`port` 被占用时自动在配置的端口范围内另选，返回实际地址。
+/// 传入 `lan_address` 时开启局域网预览；绑定所有网卡时用 `advertise_address` 指定页面链接使用的地址
 #[tauri::command]
 pub async fn serve_project(
+    servers: State<'_, EngineServers>,
     project_path: String,
     engine: EngineType,
     port: u16,
-) -> Result<(), String> {
+    lan_address: Option<IpAddr>,
+    advertise_address: Option<IpAddr>,
+) -> Result<ServeInfo, String> {
     let path = PathBuf::from(&project_path);
+    let settings = load_project_settings(&path).map_err(|e| e.to_string())?;
+    let bind = lan_address.unwrap_or_else(localhost);
+    let port = find_free_port(bind, Some(port), settings.preview_ports)
+        .map_err(|e| e.to_string())?;
     let engine_adapter = create_engine(engine).map_err(|e| e.to_string())?;
 
-    engine_adapter
-        .serve(&path, port)
+    let options = ServeOptions {
+        port,
+        lan_address,
+        advertise_address,
+    };
+    let server = engine_adapter
+        .serve(&path, &options)
         .await
         .map_err(|e| e.to_string())?;
+    servers.insert(path, server);
 
-    Ok(())
+    let urls = reachable_urls(bind, port).map_err(|e| e.to_string())?;
+    Ok(ServeInfo {
+        url: urls.first().cloned().unwrap_or_default(),
+        urls,
+        lan: lan_address.is_some(),
+    })
+}
+
+/// 列出可用于局域网预览的网卡地址
+#[tauri::command]
+pub async fn list_network_interfaces() -> Result<Vec<NetworkInterface>, String> {
+    list_lan_interfaces().map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -124,6 +468,8 @@ pub async fn create_post(
         .new_post(&path, &title)
         .await
         .map_err(|e| e.to_string())?;
+    // Hugo 返回相对于站点根目录的路径，Zola 返回绝对路径
+    update_search_index(&path, &path.join(&post_path));
 
     Ok(post_path)
 }
@@ -141,10 +487,399 @@ pub async fn read_file(file_path: String) -> Result<String, String> {
         .map_err(|e| format!("Failed to read file: {}", e))
 }
 
+/// 保存文件并记录一个修订；没有传入 `project_path` 时从文件所在目录向上查找站点根目录，
+/// 找不到时只写入文件
+#[tauri::command]
+pub async fn save_file(file_path: String, content: String, project_path: Option<String>) -> Result<(), String> {
+    let file_path = PathBuf::from(&file_path);
+    match project_path.map(PathBuf::from).or_else(|| find_project_root(&file_path)) {
+        Some(project_path) => {
+            revisions::save_with_revision(&project_path, &file_path, &content)
+                .map_err(|e| format!("Failed to save file: {}", e))?;
+            update_search_index(&project_path, &file_path);
+            Ok(())
+        }
+        None => fs::write(&file_path, content).map_err(|e| format!("Failed to save file: {}", e)),
+    }
+}
+
+/// 编辑器定时调用，记录尚未保存的内容；返回新修订的 id，内容没有变化时为 None
+#[tauri::command]
+pub async fn autosave_post(project_path: String, file_path: String, content: String) -> Result<Option<i64>, String> {
+    revisions::autosave(&PathBuf::from(&project_path), &PathBuf::from(&file_path), &content).map_err(|e| e.to_string())
+}
+
+/// 文章的修订列表（`path` 相对于站点根目录），新的在前
+#[tauri::command]
+pub async fn list_revisions(project_path: String, path: String) -> Result<Vec<RevisionInfo>, String> {
+    revisions::list_revisions(&PathBuf::from(&project_path), &path).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn read_revision(project_path: String, id: i64) -> Result<String, String> {
+    revisions::read_revision(&PathBuf::from(&project_path), id).map_err(|e| e.to_string())
+}
+
+/// 比较两个修订；不传 `to` 时与当前文件比较
+#[tauri::command]
+pub async fn diff_revisions(project_path: String, from: i64, to: Option<i64>) -> Result<RevisionDiff, String> {
+    revisions::diff_revisions(&PathBuf::from(&project_path), from, to).map_err(|e| e.to_string())
+}
+
+/// 恢复到某个修订，返回恢复后的内容
 #[tauri::command]
-pub async fn save_file(file_path: String, content: String) -> Result<(), String> {
-    fs::write(&file_path, content)
-        .map_err(|e| format!("Failed to save file: {}", e))
+pub async fn restore_revision(project_path: String, id: i64) -> Result<String, String> {
+    revisions::restore_revision(&PathBuf::from(&project_path), id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_revision_settings(project_path: String) -> Result<RevisionSettings, String> {
+    let settings = load_project_settings(&PathBuf::from(&project_path)).map_err(|e| e.to_string())?;
+    Ok(settings.revisions)
+}
+
+#[tauri::command]
+pub async fn save_revision_settings(project_path: String, revisions: RevisionSettings) -> Result<(), String> {
+    let path = PathBuf::from(&project_path);
+    let mut settings = load_project_settings(&path).map_err(|e| e.to_string())?;
+    settings.revisions = revisions;
+    save_project_settings(&path, &settings).map_err(|e| e.to_string())
+}
+
+fn load_markdown_config(project_path: &str, engine: &EngineType) -> Result<MarkdownConfig, String> {
+    let site_config = load_site_config(&PathBuf::from(project_path)).map_err(|e| e.to_string())?;
+    Ok(MarkdownConfig::from_site_config(engine, &site_config))
+}
+
+/// 用站点的 Markdown 配置渲染文章，供编辑器预览使用。
+/// 每个顶层块带有 `data-source-line`，用于编辑器与预览的滚动同步
+#[tauri::command]
+pub async fn render_preview(
+    project_path: String,
+    engine: EngineType,
+    content: String,
+) -> Result<RenderedMarkdown, String> {
+    let config = load_markdown_config(&project_path, &engine)?;
+    Ok(render_document(&content, &config))
+}
+
+/// 编辑器行号（从 1 开始）→ 预览中对应的块
+#[tauri::command]
+pub async fn preview_block_for_line(
+    project_path: String,
+    engine: EngineType,
+    content: String,
+    line: usize,
+) -> Result<Option<SourceBlock>, String> {
+    let config = load_markdown_config(&project_path, &engine)?;
+    let blocks = document_blocks(&content, &config);
+    Ok(block_for_line(&blocks, line).cloned())
+}
+
+/// 预览中第 `index` 个块 → 编辑器中的行范围
+#[tauri::command]
+pub async fn source_lines_for_block(
+    project_path: String,
+    engine: EngineType,
+    content: String,
+    index: usize,
+) -> Result<Option<SourceBlock>, String> {
+    let config = load_markdown_config(&project_path, &engine)?;
+    let blocks = document_blocks(&content, &config);
+    Ok(block_at(&blocks, index).cloned())
+}
+
+/// 列出项目和主题中可用的短代码，供编辑器自动补全和插入对话框使用
+#[tauri::command]
+pub async fn list_shortcodes(
+    project_path: String,
+    engine: EngineType,
+) -> Result<Vec<ShortcodeInfo>, String> {
+    Ok(discover_shortcodes(&PathBuf::from(&project_path), &engine))
+}
+
+/// 增量更新全文搜索索引（`.pineapple/project.db`）
+#[tauri::command]
+pub async fn refresh_search_index(project_path: String) -> Result<IndexStats, String> {
+    let path = PathBuf::from(&project_path);
+    let mut conn = open_project_db(&path).map_err(|e| e.to_string())?;
+    refresh_index(&mut conn, &path).map_err(|e| e.to_string())
+}
+
+/// 文件变化后更新搜索索引；索引只是缓存，失败时留给下次搜索前的增量更新
+fn update_search_index(project_path: &Path, file: &Path) {
+    let _ = open_project_db(project_path).and_then(|conn| update_file(&conn, project_path, file));
+}
+
+/// 搜索文章标题、标签、Front Matter 和正文，搜索前先更新索引
+#[tauri::command]
+pub async fn search_content(
+    project_path: String,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<SearchHit>, String> {
+    let path = PathBuf::from(&project_path);
+    let mut conn = open_project_db(&path).map_err(|e| e.to_string())?;
+    refresh_index(&mut conn, &path).map_err(|e| e.to_string())?;
+    search(&conn, &query, limit.unwrap_or(50)).map_err(|e| e.to_string())
+}
+
+/// 查找替换的试运行：返回每个匹配及其上下文，不修改文件
+#[tauri::command]
+pub async fn find_in_project(project_path: String, options: FindOptions) -> Result<Vec<FileMatches>, String> {
+    replace::find_in_project(&PathBuf::from(&project_path), &options).map_err(|e| e.to_string())
+}
+
+/// 应用选中的替换，返回撤销记录的 ID
+#[tauri::command]
+pub async fn replace_in_project(
+    project_path: String,
+    options: FindOptions,
+    selections: Vec<ReplaceSelection>,
+) -> Result<ReplaceResult, String> {
+    replace::apply_replacements(&PathBuf::from(&project_path), &options, &selections)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn list_replace_history(project_path: String) -> Result<Vec<UndoRecord>, String> {
+    replace::list_undo_records(&PathBuf::from(&project_path)).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn undo_replace(project_path: String, undo_id: String) -> Result<UndoRecord, String> {
+    replace::undo_replacement(&PathBuf::from(&project_path), &undo_id).map_err(|e| e.to_string())
+}
+
+/// 移动或重命名文章的试运行：列出新地址、别名和所有需要改写链接的文件
+#[tauri::command]
+pub async fn plan_move_post(
+    project_path: String,
+    engine: EngineType,
+    request: MoveRequest,
+) -> Result<MovePlan, String> {
+    let config = load_markdown_config(&project_path, &engine)?;
+    move_post::plan_move(&PathBuf::from(&project_path), &config, &request).map_err(|e| e.to_string())
+}
+
+/// 移动或重命名文章（或页面包），把旧地址加入 `aliases` 并改写其他文章中的链接
+#[tauri::command]
+pub async fn move_post(
+    project_path: String,
+    engine: EngineType,
+    request: MoveRequest,
+) -> Result<MovePlan, String> {
+    let config = load_markdown_config(&project_path, &engine)?;
+    move_post::apply_move(&PathBuf::from(&project_path), &config, &request).map_err(|e| e.to_string())
+}
+
+/// 检查站内链接：文章中的链接、`ref`/`relref`、图片和锚点，以及构建输出中的 404
+#[tauri::command]
+pub async fn check_internal_links(
+    project_path: String,
+    engine: EngineType,
+    output_dir: Option<String>,
+) -> Result<LinkReport, String> {
+    let config = load_markdown_config(&project_path, &engine)?;
+    let path = PathBuf::from(&project_path);
+    let output_dir = path.join(output_dir.as_deref().unwrap_or("public"));
+    links::check_internal_links(&path, &config, Some(&output_dir)).map_err(|e| e.to_string())
+}
+
+/// 检查文章中的外部链接，报告失效、重定向和响应慢的链接
+#[tauri::command]
+pub async fn check_external_links(
+    project_path: String,
+    engine: EngineType,
+    options: Option<ExternalCheckOptions>,
+) -> Result<ExternalLinkReport, String> {
+    let config = load_markdown_config(&project_path, &engine)?;
+    links::check_external_links(&PathBuf::from(&project_path), &config, &options.unwrap_or_default())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 列出标签、分类及配置中自定义的分类法，附带每个词条的文章数
+#[tauri::command]
+pub async fn list_taxonomy_terms(project_path: String, engine: EngineType) -> Result<Vec<TaxonomyTerms>, String> {
+    taxonomy::list_terms(&PathBuf::from(&project_path), &engine).map_err(|e| e.to_string())
+}
+
+/// 重命名、合并或删除词条的试运行，返回受影响的文章
+#[tauri::command]
+pub async fn preview_taxonomy_edit(
+    project_path: String,
+    engine: EngineType,
+    edit: TaxonomyEdit,
+) -> Result<Vec<TaxonomyChange>, String> {
+    taxonomy::preview_edit(&PathBuf::from(&project_path), &engine, &edit).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn apply_taxonomy_edit(
+    project_path: String,
+    engine: EngineType,
+    edit: TaxonomyEdit,
+) -> Result<Vec<TaxonomyChange>, String> {
+    taxonomy::apply_edit(&PathBuf::from(&project_path), &engine, &edit).map_err(|e| e.to_string())
+}
+
+/// 读取定时发布设置
+#[tauri::command]
+pub async fn get_publish_settings(project_path: String) -> Result<PublishSettings, String> {
+    let settings = load_project_settings(&PathBuf::from(&project_path)).map_err(|e| e.to_string())?;
+    Ok(settings.publishing)
+}
+
+#[tauri::command]
+pub async fn save_publish_settings(project_path: String, publishing: PublishSettings) -> Result<(), String> {
+    let path = PathBuf::from(&project_path);
+    let mut settings = load_project_settings(&path).map_err(|e| e.to_string())?;
+    settings.publishing = publishing;
+    save_project_settings(&path, &settings).map_err(|e| e.to_string())
+}
+
+/// 把项目交给后台定时发布器，并立即检查一次；本次运行中第一次检查时错过的发布按项目设置处理
+#[tauri::command]
+pub async fn watch_scheduled_publishing(
+    app: AppHandle,
+    scheduler: State<'_, PublishScheduler>,
+    project_path: String,
+    engine: EngineType,
+) -> Result<ScheduleCheck, String> {
+    let path = PathBuf::from(&project_path);
+    scheduler.watch(path.clone(), engine.clone()).map_err(|e| e.to_string())?;
+    let check = scheduler.check(&path, &engine).await.map_err(|e| e.to_string())?;
+    scheduler::notify(&app, &check);
+    Ok(check)
+}
+
+#[tauri::command]
+pub async fn unwatch_scheduled_publishing(
+    scheduler: State<'_, PublishScheduler>,
+    project_path: String,
+) -> Result<bool, String> {
+    scheduler.unwatch(&PathBuf::from(&project_path)).map_err(|e| e.to_string())
+}
+
+/// 等待发布的草稿，包括错过的定时发布
+#[tauri::command]
+pub async fn list_scheduled_posts(project_path: String, engine: EngineType) -> Result<Vec<ScheduledPost>, String> {
+    scheduler::list_scheduled(&PathBuf::from(&project_path), &engine).map_err(|e| e.to_string())
+}
+
+/// 立即发布所有错过的定时发布
+#[tauri::command]
+pub async fn publish_missed_posts(
+    app: AppHandle,
+    scheduler: State<'_, PublishScheduler>,
+    project_path: String,
+    engine: EngineType,
+) -> Result<ScheduleCheck, String> {
+    let check = scheduler
+        .publish_missed(&PathBuf::from(&project_path), &engine)
+        .await
+        .map_err(|e| e.to_string())?;
+    scheduler::notify(&app, &check);
+    Ok(check)
+}
+
+#[tauri::command]
+pub async fn list_publish_history(project_path: String, limit: Option<usize>) -> Result<Vec<PublishRecord>, String> {
+    scheduler::list_history(&PathBuf::from(&project_path), limit.unwrap_or(100)).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_git_settings(project_path: String) -> Result<GitSettings, String> {
+    let settings = load_project_settings(&PathBuf::from(&project_path)).map_err(|e| e.to_string())?;
+    Ok(settings.git)
+}
+
+#[tauri::command]
+pub async fn save_git_settings(project_path: String, git: GitSettings) -> Result<(), String> {
+    let path = PathBuf::from(&project_path);
+    let mut settings = load_project_settings(&path).map_err(|e| e.to_string())?;
+    settings.git = git;
+    save_project_settings(&path, &settings).map_err(|e| e.to_string())
+}
+
+/// 仓库状态：当前分支、与上游的差距，以及站点目录下有变化的文件（标出文章）
+#[tauri::command]
+pub async fn git_status(project_path: String) -> Result<RepoStatus, String> {
+    let repo = SiteRepo::open(&PathBuf::from(&project_path)).map_err(|e| e.to_string())?;
+    repo.status().map_err(|e| e.to_string())
+}
+
+/// 暂存文件（相对于站点根目录）；不传文件时暂存站点目录下的所有变化
+#[tauri::command]
+pub async fn git_stage(project_path: String, paths: Vec<String>) -> Result<(), String> {
+    let repo = SiteRepo::open(&PathBuf::from(&project_path)).map_err(|e| e.to_string())?;
+    repo.stage(&paths).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn git_unstage(project_path: String, paths: Vec<String>) -> Result<(), String> {
+    let repo = SiteRepo::open(&PathBuf::from(&project_path)).map_err(|e| e.to_string())?;
+    repo.unstage(&paths).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn git_commit(project_path: String, message: String) -> Result<CommitInfo, String> {
+    let repo = SiteRepo::open(&PathBuf::from(&project_path)).map_err(|e| e.to_string())?;
+    repo.commit(&message).map_err(|e| e.to_string())
+}
+
+/// 修改过某篇文章的提交
+#[tauri::command]
+pub async fn git_post_history(project_path: String, path: String, limit: Option<usize>) -> Result<Vec<CommitInfo>, String> {
+    let repo = SiteRepo::open(&PathBuf::from(&project_path)).map_err(|e| e.to_string())?;
+    repo.file_history(&path, limit.unwrap_or(50)).map_err(|e| e.to_string())
+}
+
+/// 文章当前内容与 HEAD 的差异
+#[tauri::command]
+pub async fn git_diff_post(project_path: String, path: String) -> Result<PostDiff, String> {
+    let repo = SiteRepo::open(&PathBuf::from(&project_path)).map_err(|e| e.to_string())?;
+    repo.diff_against_head(&path).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn git_list_branches(project_path: String) -> Result<Vec<BranchInfo>, String> {
+    let repo = SiteRepo::open(&PathBuf::from(&project_path)).map_err(|e| e.to_string())?;
+    repo.branches().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn git_switch_branch(project_path: String, name: String, create: bool) -> Result<(), String> {
+    let path = PathBuf::from(&project_path);
+    let settings = load_project_settings(&path).map_err(|e| e.to_string())?;
+    let repo = SiteRepo::open(&path).map_err(|e| e.to_string())?;
+    repo.switch_branch(&name, &settings.git.remote, create).map_err(|e| e.to_string())
+}
+
+/// 从项目设置中的远程仓库拉取当前分支
+/// 网络操作在阻塞线程池中执行，避免占用异步运行时
+#[tauri::command]
+pub async fn git_pull(project_path: String) -> Result<PullOutcome, String> {
+    let path = PathBuf::from(&project_path);
+    let settings = load_project_settings(&path).map_err(|e| e.to_string())?;
+    let remote = settings.git.remote;
+    tokio::task::spawn_blocking(move || SiteRepo::open(&path)?.pull(&remote))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// 推送当前分支到项目设置中的远程仓库
+#[tauri::command]
+pub async fn git_push(project_path: String) -> Result<(), String> {
+    let path = PathBuf::from(&project_path);
+    let settings = load_project_settings(&path).map_err(|e| e.to_string())?;
+    let remote = settings.git.remote;
+    tokio::task::spawn_blocking(move || SiteRepo::open(&path)?.push(&remote))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -284,32 +1019,39 @@ pub struct ServeSiteResult {
 /// 构建网站（简化版本）
 #[tauri::command]
 pub async fn build_site(
+    app: AppHandle,
+    previews: State<'_, PreviewServers>,
     project_path: String,
-    engine_type: String,
+    engine_type: EngineType,
 ) -> Result<BuildSiteResult, String> {
     let path = PathBuf::from(&project_path);
-    
-    // 只支持 Hugo
-    let engine = EngineType::Hugo;
-    
-    let engine_adapter = create_engine(engine).map_err(|e| e.to_string())?;
+    let engine = engine_type;
+    let settings = load_project_settings(&path).map_err(|e| e.to_string())?;
     
     let options = BuildOptions {
-        minify: false,
         clean: true,
         draft: true,
+        ..BuildOptions::default()
     };
     
-    match engine_adapter.build(&path, &options).await {
-        Ok(result) => Ok(BuildSiteResult {
-            success: result.success,
-            output: result.output,
-            error: if result.errors.is_empty() {
-                None
-            } else {
-                Some(result.errors.join("\n"))
-            },
-        }),
+    let emit = |output: HookOutput| {
+        let _ = app.emit(HOOK_OUTPUT_EVENT, output);
+    };
+    match hooks::build(&path, &engine, &options, &settings.hooks, &emit).await {
+        Ok(result) => {
+            if result.success {
+                previews.notify_reload(&path);
+            }
+            Ok(BuildSiteResult {
+                success: result.success,
+                output: result.output,
+                error: if result.errors.is_empty() {
+                    None
+                } else {
+                    Some(result.errors.join("\n"))
+                },
+            })
+        }
         Err(e) => Ok(BuildSiteResult {
             success: false,
             output: String::new(),
@@ -321,23 +1063,38 @@ pub async fn build_site(
 /// 预览网站
 #[tauri::command]
 pub async fn serve_site(
+    servers: State<'_, EngineServers>,
     project_path: String,
-    engine_type: String,
+    engine_type: EngineType,
     port: u16,
 ) -> Result<ServeSiteResult, String> {
     let path = PathBuf::from(&project_path);
-    
-    // 只支持 Hugo
-    let engine = EngineType::Hugo;
-    
+    let engine = engine_type;
+
+    let port = match load_project_settings(&path)
+        .and_then(|settings| find_free_port(localhost(), Some(port), settings.preview_ports))
+    {
+        Ok(port) => port,
+        Err(e) => {
+            return Ok(ServeSiteResult {
+                success: false,
+                url: None,
+                error: Some(e.to_string()),
+            })
+        }
+    };
+
     let engine_adapter = create_engine(engine).map_err(|e| e.to_string())?;
     
-    match engine_adapter.serve(&path, port).await {
-        Ok(_) => Ok(ServeSiteResult {
-            success: true,
-            url: Some(format!("http://127.0.0.1:{}", port)),
-            error: None,
-        }),
+    match engine_adapter.serve(&path, &ServeOptions::local(port)).await {
+        Ok(server) => {
+            servers.insert(path, server);
+            Ok(ServeSiteResult {
+                success: true,
+                url: Some(format!("http://127.0.0.1:{}", port)),
+                error: None,
+            })
+        }
         Err(e) => Ok(ServeSiteResult {
             success: false,
             url: None,
@@ -346,23 +1103,59 @@ pub async fn serve_site(
     }
 }
 
-/// 停止预览服务器
+#[derive(Debug, Serialize)]
+pub struct StaticPreviewInfo {
+    pub url: String,
+    pub port: u16,
+    pub root: String,
+}
+
+/// 用内置服务器预览构建输出（默认 public/），构建成功后页面自动刷新
 #[tauri::command]
-pub async fn stop_serve(_project_path: String) -> Result<(), String> {
-    // 在Windows上，我们需要终止hugo进程
-    // 这是一个简化实现，实际中可能需要跟踪进程ID
-    #[cfg(target_os = "windows")]
-    {
-        let _ = std::process::Command::new("taskkill")
-            .args(&["/F", "/IM", "hugo.exe"])
-            .output();
-    }
-    #[cfg(not(target_os = "windows"))]
-    {
-        let _ = std::process::Command::new("pkill")
-            .args(&["-f", "hugo server"])
-            .output();
-    }
+pub async fn start_static_preview(
+    previews: State<'_, PreviewServers>,
+    project_path: String,
+    output_dir: Option<String>,
+    base_url: Option<String>,
+) -> Result<StaticPreviewInfo, String> {
+    let path = PathBuf::from(&project_path);
+    let root = output_dir
+        .map(PathBuf::from)
+        .unwrap_or_else(|| path.join("public"));
+    let base_url = base_url.or_else(|| read_site_base_url(&path));
+    let settings = load_project_settings(&path).map_err(|e| e.to_string())?;
+
+    let listener = reserve_port(localhost(), None, settings.preview_ports)
+        .and_then(|listener| {
+            listener.set_nonblocking(true)?;
+            Ok(tokio::net::TcpListener::from_std(listener)?)
+        })
+        .map_err(|e| format!("无法启动预览服务器: {}", e))?;
+    let server = StaticPreviewServer::start(listener, root, base_url).map_err(|e| e.to_string())?;
+
+    let info = StaticPreviewInfo {
+        url: server.url(),
+        port: server.port(),
+        root: server.root().to_string_lossy().to_string(),
+    };
+    previews.insert(path, server);
+
+    Ok(info)
+}
+
+/// 停止内置预览服务器
+#[tauri::command]
+pub async fn stop_static_preview(
+    previews: State<'_, PreviewServers>,
+    project_path: String,
+) -> Result<bool, String> {
+    Ok(previews.stop(&PathBuf::from(&project_path)))
+}
+
+/// 停止引擎自带的预览服务器
+#[tauri::command]
+pub async fn stop_serve(servers: State<'_, EngineServers>, project_path: String) -> Result<(), String> {
+    servers.stop(&PathBuf::from(&project_path));
     Ok(())
 }
 
@@ -0,0 +1,15 @@
+use std::path::Path;
+use rusqlite::Connection;
+use crate::error::Result;
+use crate::settings::ensure_project_data_dir;
+
+const DB_FILE: &str = "project.db";
+
+/// 打开项目数据库 `.pineapple/project.db`，不存在时自动创建
+pub fn open_project_db(project_path: &Path) -> Result<Connection> {
+    let dir = ensure_project_data_dir(project_path)?;
+
+    let conn = Connection::open(dir.join(DB_FILE))?;
+    conn.execute_batch("PRAGMA journal_mode = WAL; PRAGMA foreign_keys = ON;")?;
+    Ok(conn)
+}
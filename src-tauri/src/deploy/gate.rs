@@ -0,0 +1,494 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::error::Result;
+use crate::front_matter::{get_field, parse_front_matter, split_front_matter};
+use crate::links::{check_internal_links, BrokenReason};
+use crate::markdown::MarkdownConfig;
+use crate::models::{BuildOptions, BuildResult, EngineType};
+use crate::scheduler::parse_date;
+use crate::site_config::load_site_config;
+use crate::utils::walk_files;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckLevel {
+    /// 不运行
+    Off,
+    /// 报告问题，但允许继续部署
+    Warning,
+    /// 有问题时阻止部署
+    #[default]
+    Blocking,
+}
+
+/// 部署前检查的设置，保存在项目设置中
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeployGateSettings {
+    pub enabled: bool,
+    /// 构建失败时总是阻止部署；级别只决定构建成功但输出了错误时如何处理
+    pub build: CheckLevel,
+    pub links: CheckLevel,
+    pub front_matter: CheckLevel,
+    /// 草稿或未到发布时间的文章会被这次构建发布
+    pub unpublished: CheckLevel,
+    pub schema: FrontMatterSchema,
+}
+
+impl Default for DeployGateSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            build: CheckLevel::Blocking,
+            links: CheckLevel::Blocking,
+            front_matter: CheckLevel::Warning,
+            unpublished: CheckLevel::Blocking,
+            schema: FrontMatterSchema::default(),
+        }
+    }
+}
+
+impl DeployGateSettings {
+    /// 关闭所有检查；构建失败仍然会被报告并阻止部署
+    pub fn off() -> Self {
+        Self {
+            enabled: false,
+            build: CheckLevel::Off,
+            links: CheckLevel::Off,
+            front_matter: CheckLevel::Off,
+            unpublished: CheckLevel::Off,
+            schema: FrontMatterSchema::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    String,
+    Bool,
+    Number,
+    Date,
+    List,
+}
+
+impl FieldType {
+    fn name(self) -> &'static str {
+        match self {
+            FieldType::String => "string",
+            FieldType::Bool => "boolean",
+            FieldType::Number => "number",
+            FieldType::Date => "date",
+            FieldType::List => "list",
+        }
+    }
+}
+
+/// Front Matter 的字段要求。字段名可以用 `.` 指定嵌套字段，例如 `taxonomies.tags`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FrontMatterSchema {
+    /// 文章必须有的字段，分区页面（`_index.md`）不检查
+    pub required: Vec<String>,
+    /// 字段存在时必须是的类型
+    pub fields: BTreeMap<String, FieldType>,
+}
+
+impl Default for FrontMatterSchema {
+    fn default() -> Self {
+        Self {
+            required: vec!["title".to_string()],
+            fields: BTreeMap::from([
+                ("title".to_string(), FieldType::String),
+                ("date".to_string(), FieldType::Date),
+                ("draft".to_string(), FieldType::Bool),
+                ("tags".to_string(), FieldType::List),
+                ("categories".to_string(), FieldType::List),
+            ]),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CheckKind {
+    Build,
+    Links,
+    FrontMatter,
+    Unpublished,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckIssue {
+    /// 相对于站点根目录，使用 `/` 分隔
+    pub path: Option<String>,
+    /// 行号，从 1 开始
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckResult {
+    pub kind: CheckKind,
+    pub level: CheckLevel,
+    pub passed: bool,
+    pub issues: Vec<CheckIssue>,
+}
+
+/// 所有检查的结果；`blocked` 为 true 时不允许部署
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GateReport {
+    pub checks: Vec<CheckResult>,
+    pub blocked: bool,
+}
+
+/// 运行已启用的检查。`output_dir` 为构建输出，构建失败时为 None
+pub fn run(
+    project_path: &Path,
+    engine: &EngineType,
+    settings: &DeployGateSettings,
+    options: &BuildOptions,
+    build: &BuildResult,
+    output_dir: Option<&Path>,
+) -> Result<GateReport> {
+    let mut checks = Vec::new();
+    if !build.success {
+        checks.push(result(CheckKind::Build, CheckLevel::Blocking, check_build(build)));
+    } else if settings.build != CheckLevel::Off {
+        checks.push(result(CheckKind::Build, settings.build, check_build(build)));
+    }
+    if settings.links != CheckLevel::Off {
+        let config = MarkdownConfig::from_site_config(engine, &load_site_config(project_path)?);
+        checks.push(result(CheckKind::Links, settings.links, check_links(project_path, &config, output_dir)?));
+    }
+    if settings.front_matter != CheckLevel::Off {
+        let issues = check_front_matter(project_path, &settings.schema);
+        checks.push(result(CheckKind::FrontMatter, settings.front_matter, issues));
+    }
+    if settings.unpublished != CheckLevel::Off {
+        let issues = check_unpublished(project_path, engine, options);
+        checks.push(result(CheckKind::Unpublished, settings.unpublished, issues));
+    }
+
+    let blocked = checks
+        .iter()
+        .any(|check| !check.passed && check.level == CheckLevel::Blocking);
+    Ok(GateReport { checks, blocked })
+}
+
+fn result(kind: CheckKind, level: CheckLevel, issues: Vec<CheckIssue>) -> CheckResult {
+    CheckResult {
+        kind,
+        level,
+        passed: issues.is_empty(),
+        issues,
+    }
+}
+
+/// 构建失败，或者构建成功但输出中有错误日志（Hugo 的 `ERROR`、Zola 的 `Error:`）
+fn check_build(build: &BuildResult) -> Vec<CheckIssue> {
+    let issue = |message: &str| CheckIssue {
+        path: None,
+        line: None,
+        message: message.trim().to_string(),
+    };
+    if !build.success {
+        return build.errors.iter().map(|error| issue(error)).collect();
+    }
+    build
+        .output
+        .lines()
+        .filter(|line| line.starts_with("ERROR") || line.starts_with("Error:"))
+        .map(issue)
+        .collect()
+}
+
+fn check_links(project_path: &Path, config: &MarkdownConfig, output_dir: Option<&Path>) -> Result<Vec<CheckIssue>> {
+    let report = check_internal_links(project_path, config, output_dir)?;
+    Ok(report
+        .broken
+        .into_iter()
+        .map(|link| {
+            let reason = match link.reason {
+                BrokenReason::MissingTarget => "missing target",
+                BrokenReason::MissingAnchor => "missing anchor",
+                BrokenReason::AmbiguousRef => "ambiguous ref",
+            };
+            CheckIssue {
+                path: Some(link.source),
+                line: Some(link.line),
+                message: format!("{}: {}", link.target, reason),
+            }
+        })
+        .collect())
+}
+
+fn check_front_matter(project_path: &Path, schema: &FrontMatterSchema) -> Vec<CheckIssue> {
+    let mut issues = Vec::new();
+    for (file, relative) in content_files(project_path) {
+        let Ok(content) = fs::read_to_string(&file) else {
+            continue;
+        };
+        let mut issue = |message: String| {
+            issues.push(CheckIssue {
+                path: Some(relative.clone()),
+                line: None,
+                message,
+            })
+        };
+        let front_matter = match split_front_matter(&content).front_matter.map(|block| parse_front_matter(&block)) {
+            Some(Ok(front_matter)) => front_matter,
+            Some(Err(e)) => {
+                issue(format!("Invalid front matter: {}", e));
+                continue;
+            }
+            None => {
+                issue("Missing front matter".to_string());
+                continue;
+            }
+        };
+
+        let section = file.file_name().is_some_and(|name| name == "_index.md");
+        for field in schema.required.iter().filter(|_| !section) {
+            if field_value(&front_matter, field).is_none_or(Value::is_null) {
+                issue(format!("Missing required field '{}'", field));
+            }
+        }
+        for (field, field_type) in &schema.fields {
+            let Some(value) = field_value(&front_matter, field).filter(|value| !value.is_null()) else {
+                continue;
+            };
+            let valid = match field_type {
+                FieldType::String => value.is_string(),
+                FieldType::Bool => value.is_boolean(),
+                FieldType::Number => value.is_number(),
+                FieldType::Date => value.as_str().and_then(parse_date).is_some(),
+                FieldType::List => value.is_array(),
+            };
+            if !valid {
+                issue(format!("Field '{}' should be a {}", field, field_type.name()));
+            }
+        }
+    }
+    issues
+}
+
+/// 这次构建会发布的草稿和未到发布时间的文章
+fn check_unpublished(project_path: &Path, engine: &EngineType, options: &BuildOptions) -> Vec<CheckIssue> {
+    let (date_fields, build_future): (&[&str], bool) = match engine {
+        EngineType::Hugo => (&["publishDate", "date"], options.build_future),
+        // Zola 没有对应 `--buildFuture` 的选项，日期在未来的文章总是会被发布，
+        // 所以不看构建配置中的 build_future
+        EngineType::Zola => (&["date"], true),
+    };
+    let now = Utc::now();
+
+    let mut issues = Vec::new();
+    for (file, relative) in content_files(project_path) {
+        let Some(front_matter) = fs::read_to_string(&file).ok().and_then(|content| {
+            split_front_matter(&content)
+                .front_matter
+                .and_then(|block| parse_front_matter(&block).ok())
+        }) else {
+            continue;
+        };
+
+        let draft = match get_field(&front_matter, "draft") {
+            Some(Value::Bool(draft)) => *draft,
+            Some(Value::String(draft)) => draft.eq_ignore_ascii_case("true"),
+            _ => false,
+        };
+        let publish_at = date_fields
+            .iter()
+            .find_map(|field| get_field(&front_matter, field).and_then(Value::as_str).and_then(parse_date));
+
+        let message = if draft && options.draft {
+            "Draft will be published".to_string()
+        } else if let Some(publish_at) = publish_at.filter(|date| *date > now && build_future && !draft) {
+            format!("Scheduled for {} but will be published now", publish_at.to_rfc3339())
+        } else {
+            continue;
+        };
+        issues.push(CheckIssue {
+            path: Some(relative),
+            line: None,
+            message,
+        });
+    }
+    issues
+}
+
+/// 内容目录中的 Markdown 文件及其相对于站点根目录的路径
+fn content_files(project_path: &Path) -> Vec<(std::path::PathBuf, String)> {
+    walk_files(&project_path.join("content"))
+        .into_iter()
+        .filter(|file| matches!(file.extension().and_then(|e| e.to_str()), Some("md") | Some("markdown")))
+        .map(|file| {
+            let relative = file
+                .strip_prefix(project_path)
+                .unwrap_or(&file)
+                .to_string_lossy()
+                .replace('\\', "/");
+            (file, relative)
+        })
+        .collect()
+}
+
+fn field_value<'a>(front_matter: &'a Value, field: &str) -> Option<&'a Value> {
+    field
+        .split('.')
+        .try_fold(front_matter, |value, key| get_field(value, key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let write = |path: &str, content: &str| {
+            let file = root.join(path);
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+            fs::write(file, content).unwrap();
+        };
+        write("hugo.toml", "baseURL = 'https://example.com/'\n");
+        write("public/index.html", "<h1>Home</h1>");
+        write("content/_index.md", "---\n---\nHome\n");
+        write("content/posts/ok.md", "---\ntitle: OK\ndate: 2020-01-01\ntags: [a]\n---\n[gone](/nope/)\n");
+        write("content/posts/draft.md", "+++\ntitle = 'Draft'\ndraft = true\n+++\n");
+        write("content/posts/future.md", "---\ntitle: Later\ndate: 2999-01-01\ntags: a\n---\n");
+        write("content/posts/bad.md", "---\ntitle: [\n---\n");
+        write("content/posts/none.md", "No front matter\n");
+        dir
+    }
+
+    fn build(success: bool, output: &str) -> BuildResult {
+        BuildResult {
+            success,
+            duration: 0.0,
+            output: output.to_string(),
+            errors: if success { vec![] } else { vec!["boom\n".to_string()] },
+        }
+    }
+
+    fn issues(report: &GateReport, kind: CheckKind) -> Vec<(String, String)> {
+        report
+            .checks
+            .iter()
+            .find(|check| check.kind == kind)
+            .unwrap()
+            .issues
+            .iter()
+            .map(|issue| (issue.path.clone().unwrap_or_default(), issue.message.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn failed_build_blocks_even_when_checks_are_off() {
+        let dir = project();
+        let report = run(
+            dir.path(),
+            &EngineType::Hugo,
+            &DeployGateSettings::off(),
+            &BuildOptions::default(),
+            &build(false, ""),
+            None,
+        )
+        .unwrap();
+        assert!(report.blocked);
+        assert_eq!(report.checks.len(), 1);
+        assert_eq!(report.checks[0].level, CheckLevel::Blocking);
+        assert_eq!(issues(&report, CheckKind::Build), [(String::new(), "boom".to_string())]);
+    }
+
+    #[test]
+    fn warnings_are_reported_without_blocking() {
+        let dir = project();
+        let settings = DeployGateSettings {
+            build: CheckLevel::Warning,
+            links: CheckLevel::Warning,
+            front_matter: CheckLevel::Warning,
+            unpublished: CheckLevel::Off,
+            ..Default::default()
+        };
+        let output = dir.path().join("public");
+        let report = run(
+            dir.path(),
+            &EngineType::Hugo,
+            &settings,
+            &BuildOptions::default(),
+            &build(true, "Start building\nERROR render failed\n"),
+            Some(&output),
+        )
+        .unwrap();
+        assert!(!report.blocked);
+        assert_eq!(report.checks.len(), 3);
+        assert!(report.checks.iter().all(|check| !check.passed));
+        assert_eq!(issues(&report, CheckKind::Build), [(String::new(), "ERROR render failed".to_string())]);
+
+        let links = issues(&report, CheckKind::Links);
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].0, "content/posts/ok.md");
+        assert!(links[0].1.starts_with("/nope/"));
+
+        // 分区页面不要求 title
+        let mut front_matter = issues(&report, CheckKind::FrontMatter);
+        front_matter.sort();
+        let paths: Vec<&str> = front_matter.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(paths, ["content/posts/bad.md", "content/posts/future.md", "content/posts/none.md"]);
+        assert!(front_matter[0].1.starts_with("Invalid front matter"));
+        assert_eq!(front_matter[1].1, "Field 'tags' should be a list");
+        assert_eq!(front_matter[2].1, "Missing front matter");
+    }
+
+    #[test]
+    fn blocking_check_blocks() {
+        let dir = project();
+        let settings = DeployGateSettings {
+            links: CheckLevel::Off,
+            front_matter: CheckLevel::Off,
+            ..Default::default()
+        };
+        let mut options = BuildOptions::default();
+        let clean = run(dir.path(), &EngineType::Hugo, &settings, &options, &build(true, ""), None).unwrap();
+        assert!(!clean.blocked);
+        assert!(clean.checks.iter().all(|check| check.passed));
+
+        options.draft = true;
+        let report = run(dir.path(), &EngineType::Hugo, &settings, &options, &build(true, ""), None).unwrap();
+        assert!(report.blocked);
+        assert_eq!(
+            issues(&report, CheckKind::Unpublished),
+            [("content/posts/draft.md".to_string(), "Draft will be published".to_string())]
+        );
+    }
+
+    #[test]
+    fn future_posts_follow_build_future_on_hugo_only() {
+        let dir = project();
+        let future = |engine: &EngineType, build_future: bool| {
+            let options = BuildOptions {
+                build_future,
+                ..Default::default()
+            };
+            let settings = DeployGateSettings {
+                links: CheckLevel::Off,
+                front_matter: CheckLevel::Off,
+                ..Default::default()
+            };
+            let report = run(dir.path(), engine, &settings, &options, &build(true, ""), None).unwrap();
+            issues(&report, CheckKind::Unpublished)
+                .into_iter()
+                .map(|(path, _)| path)
+                .collect::<Vec<_>>()
+        };
+        assert!(future(&EngineType::Hugo, false).is_empty());
+        assert_eq!(future(&EngineType::Hugo, true), ["content/posts/future.md"]);
+        assert_eq!(future(&EngineType::Zola, false), ["content/posts/future.md"]);
+    }
+}
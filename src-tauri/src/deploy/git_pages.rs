@@ -0,0 +1,299 @@
+use async_trait::async_trait;
+use std::fs;
+use std::path::{Path, PathBuf};
+use chrono::Utc;
+use git2::{ErrorCode, FetchOptions, Oid, Repository};
+use serde::{Deserialize, Serialize};
+use crate::deploy::{DeployOutcome, DeployState, DeployStatus, DeployTarget, Progress};
+use crate::error::{AppError, Result};
+use crate::git::{push_refspec, remote_callbacks, resolve_remote_url, signature, SiteRepo};
+use crate::settings::{ensure_project_data_dir, load_project_settings};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GitPagesOptions {
+    /// 目标仓库地址；为空时推送到站点仓库的远程（Git 设置中的 `remote`）
+    pub repository: Option<String>,
+    pub branch: String,
+    /// 写入 `CNAME` 文件的自定义域名
+    pub cname: Option<String>,
+    /// 添加 `.nojekyll`，避免 GitHub Pages 用 Jekyll 处理构建输出
+    pub nojekyll: bool,
+    pub commit_message: Option<String>,
+}
+
+impl Default for GitPagesOptions {
+    fn default() -> Self {
+        Self {
+            repository: None,
+            branch: "gh-pages".to_string(),
+            cname: None,
+            nojekyll: true,
+            commit_message: None,
+        }
+    }
+}
+
+pub struct GitPagesTarget {
+    project_path: PathBuf,
+    name: String,
+    options: GitPagesOptions,
+}
+
+impl GitPagesTarget {
+    pub fn new(project_path: &Path, name: String, options: GitPagesOptions) -> Self {
+        Self {
+            project_path: project_path.to_path_buf(),
+            name,
+            options,
+        }
+    }
+}
+
+#[async_trait]
+impl DeployTarget for GitPagesTarget {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// 写入对象和推送都是阻塞操作，放到阻塞线程池中执行
+    async fn deploy(&self, output_dir: &Path, _progress: &Progress<'_>) -> Result<DeployOutcome> {
+        let project_path = self.project_path.clone();
+        let name = self.name.clone();
+        let options = self.options.clone();
+        let output_dir = output_dir.to_path_buf();
+        tokio::task::spawn_blocking(move || deploy(&project_path, &name, &output_dir, &options))
+            .await
+            .map_err(|e| AppError::DeployFailed(e.to_string()))?
+    }
+
+    /// 推送完成即部署完成，Pages 服务的发布过程无法查询
+    async fn status(&self, revision: &str) -> Result<DeployStatus> {
+        Ok(DeployStatus {
+            revision: revision.to_string(),
+            state: DeployState::Ready,
+            url: site_url(&self.options),
+            error: None,
+        })
+    }
+}
+
+/// 把构建输出提交到目标分支并推送。提交在 `.pineapple/deploy/` 下的缓存仓库中完成，
+/// 数据目录被自身的 `.gitignore` 忽略，不会改动站点仓库的工作区、暂存区和分支
+pub fn deploy(
+    project_path: &Path,
+    target_name: &str,
+    output_dir: &Path,
+    options: &GitPagesOptions,
+) -> Result<DeployOutcome> {
+    if !output_dir.is_dir() {
+        return Err(AppError::PathNotFound(output_dir.display().to_string()));
+    }
+    let url = match options.repository.as_deref().map(str::trim) {
+        Some(url) if !url.is_empty() => resolve_remote_url(url, project_path),
+        _ => {
+            let settings = load_project_settings(project_path)?;
+            SiteRepo::open(project_path)?.remote_url(&settings.git.remote)?
+        }
+    };
+
+    let cache = ensure_project_data_dir(project_path)?
+        .join("deploy")
+        .join(format!("{}.git", cache_name(target_name)));
+    let repo = match Repository::open_bare(&cache) {
+        Ok(repo) => repo,
+        Err(_) => Repository::init_bare(&cache)?,
+    };
+    let branch_ref = format!("refs/heads/{}", options.branch);
+    let parent = fetch_branch(&repo, &url, &branch_ref)?;
+
+    let mut files = 0;
+    let tree_id = write_tree(&repo, output_dir, &mut files)?
+        .ok_or_else(|| AppError::BuildFailed(format!("{} is empty", output_dir.display())))?;
+    let mut root = repo.treebuilder(Some(&repo.find_tree(tree_id)?))?;
+    if let Some(cname) = options.cname.as_deref().map(str::trim).filter(|c| !c.is_empty()) {
+        root.insert("CNAME", repo.blob(format!("{}\n", cname).as_bytes())?, 0o100644)?;
+        files += 1;
+    }
+    if options.nojekyll {
+        root.insert(".nojekyll", repo.blob(b"")?, 0o100644)?;
+        files += 1;
+    }
+    let tree = repo.find_tree(root.write()?)?;
+
+    if let Some(parent) = parent.filter(|p| p.tree_id() == tree.id()) {
+        return Ok(DeployOutcome {
+            target: target_name.to_string(),
+            files,
+            changed: false,
+            revision: Some(parent.id().to_string()),
+            state: DeployState::Ready,
+            url: site_url(options),
+            finished_at: Utc::now(),
+            checks: None,
+        });
+    }
+
+    let message = match options.commit_message.as_deref().filter(|m| !m.trim().is_empty()) {
+        Some(message) => message.to_string(),
+        None => default_message(project_path),
+    };
+    let signature = signature(&repo)?;
+    let parent = repo.find_reference(&branch_ref).ok().and_then(|r| r.peel_to_commit().ok());
+    let parents: Vec<&git2::Commit> = parent.iter().collect();
+    let commit = repo.commit(Some(&branch_ref), &signature, &signature, &message, &tree, &parents)?;
+
+    let mut remote = repo.remote_anonymous(&url)?;
+    push_refspec(&mut remote, &format!("{0}:{0}", branch_ref))?;
+
+    Ok(DeployOutcome {
+        target: target_name.to_string(),
+        files,
+        changed: true,
+        revision: Some(commit.to_string()),
+        state: DeployState::Ready,
+        url: site_url(options),
+        finished_at: Utc::now(),
+        checks: None,
+    })
+}
+
+/// 取回目标分支的最新提交，远程还没有这个分支时返回 None。
+/// 先删除缓存中的旧分支，这样远程分支被删除后会从头开始
+fn fetch_branch<'r>(repo: &'r Repository, url: &str, branch_ref: &str) -> Result<Option<git2::Commit<'r>>> {
+    if let Ok(mut cached) = repo.find_reference(branch_ref) {
+        cached.delete()?;
+    }
+
+    let mut remote = repo.remote_anonymous(url)?;
+    let mut options = FetchOptions::new();
+    options.remote_callbacks(remote_callbacks());
+    remote.fetch(&[format!("+{0}:{0}", branch_ref)], Some(&mut options), None)?;
+
+    match repo.find_reference(branch_ref) {
+        Ok(reference) => Ok(Some(reference.peel_to_commit()?)),
+        Err(e) if e.code() == ErrorCode::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// 把目录写入仓库的对象库，返回树的 id；空目录返回 None（Git 不保存空目录）
+fn write_tree(repo: &Repository, dir: &Path, files: &mut usize) -> Result<Option<Oid>> {
+    let mut entries = fs::read_dir(dir)?.collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    let mut builder = repo.treebuilder(None)?;
+    for entry in entries {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name == ".git" {
+            continue;
+        }
+        let path = entry.path();
+        if path.is_dir() {
+            if let Some(id) = write_tree(repo, &path, files)? {
+                builder.insert(&name, id, 0o040000)?;
+            }
+        } else if path.is_file() {
+            builder.insert(&name, repo.blob_path(&path)?, 0o100644)?;
+            *files += 1;
+        }
+    }
+
+    if builder.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(builder.write()?))
+}
+
+/// 站点是 Git 仓库时在提交说明中注明源提交
+fn default_message(project_path: &Path) -> String {
+    let source = SiteRepo::open(project_path)
+        .and_then(|repo| repo.head_id())
+        .ok()
+        .flatten();
+    match source {
+        Some(id) => format!("Deploy {}", &id[..7]),
+        None => format!("Deploy {}", Utc::now().format("%Y-%m-%d %H:%M:%S UTC")),
+    }
+}
+
+fn site_url(options: &GitPagesOptions) -> Option<String> {
+    let cname = options.cname.as_deref().map(str::trim).filter(|c| !c.is_empty())?;
+    Some(format!("https://{}/", cname))
+}
+
+fn cache_name(target_name: &str) -> String {
+    target_name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn deploys_to_branch_without_touching_the_site_repo() {
+        let dir = tempfile::tempdir().unwrap();
+        let remote_path = dir.path().join("remote.git");
+        let remote = Repository::init_bare(&remote_path).unwrap();
+        let site = dir.path().join("site");
+        let site_repo = Repository::init(&site).unwrap();
+        site_repo.remote("origin", &remote_path.to_string_lossy()).unwrap();
+        fs::write(site.join("config.toml"), "title = \"Blog\"\n").unwrap();
+        let source = SiteRepo::open(&site).unwrap();
+        source.stage(&[]).unwrap();
+        let source_commit = source.commit("Initial").unwrap();
+
+        // 构建输出放在站点仓库之外，只检查部署本身有没有改动站点仓库
+        let output = dir.path().join("public");
+        fs::create_dir_all(output.join("posts/hello")).unwrap();
+        fs::create_dir_all(output.join("empty")).unwrap();
+        fs::write(output.join("index.html"), "<h1>Home</h1>").unwrap();
+        fs::write(output.join("posts/hello/index.html"), "<h1>Hello</h1>").unwrap();
+
+        let options = GitPagesOptions {
+            cname: Some("blog.example.com".to_string()),
+            ..Default::default()
+        };
+        let target = GitPagesTarget::new(&site, "pages".to_string(), options);
+        let callback = |_| {};
+        let progress = Progress::new("pages", &callback);
+        let first = target.deploy(&output, &progress).await.unwrap();
+        assert!(first.changed);
+        assert_eq!(first.files, 4);
+        assert_eq!(first.url.as_deref(), Some("https://blog.example.com/"));
+
+        let deployed = remote.find_reference("refs/heads/gh-pages").unwrap().peel_to_commit().unwrap();
+        assert_eq!(first.revision, Some(deployed.id().to_string()));
+        assert_eq!(deployed.message(), Some(format!("Deploy {}", &source_commit.id[..7]).as_str()));
+        let tree = deployed.tree().unwrap();
+        let blob = |path: &str| {
+            let entry = tree.get_path(Path::new(path)).unwrap();
+            remote.find_blob(entry.id()).unwrap().content().to_vec()
+        };
+        assert_eq!(blob("CNAME"), b"blog.example.com\n");
+        assert_eq!(blob(".nojekyll"), b"");
+        assert_eq!(blob("posts/hello/index.html"), b"<h1>Hello</h1>");
+        assert!(tree.get_path(Path::new("empty")).is_err());
+
+        // 内容没有变化时不创建新提交
+        let second = target.deploy(&output, &progress).await.unwrap();
+        assert!(!second.changed);
+        assert_eq!(second.revision, first.revision);
+
+        // 站点仓库的 HEAD、暂存区和工作区都没有变化
+        let head = site_repo.head().unwrap();
+        assert_eq!(head.target().unwrap().to_string(), source_commit.id);
+        assert!(site_repo.find_branch("gh-pages", git2::BranchType::Local).is_err());
+        assert!(source.status().unwrap().files.is_empty());
+        let index = site_repo.index().unwrap();
+        let mut staged: Vec<_> = index
+            .iter()
+            .map(|entry| String::from_utf8_lossy(&entry.path).into_owned())
+            .collect();
+        staged.sort();
+        assert_eq!(staged, ["config.toml"]);
+    }
+}
@@ -0,0 +1,447 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use crate::db::open_project_db;
+use crate::deploy::gate::GateReport;
+use crate::deploy::{output_files, DeployOutcome};
+use crate::error::{AppError, Result};
+use crate::git::SiteRepo;
+use crate::settings::{ensure_project_data_dir, project_data_dir};
+
+/// 每个部署目标保留构建输出的成功部署数，更早的部署只保留记录和文件清单
+const KEEP_SNAPSHOTS: usize = 20;
+
+/// 一次部署的记录
+#[derive(Debug, Clone, Serialize)]
+pub struct DeployRecord {
+    pub id: i64,
+    pub target: String,
+    pub deployed_at: DateTime<Utc>,
+    /// 部署时站点仓库的 HEAD
+    pub source_commit: Option<String>,
+    /// 由文件清单计算，构建输出相同时相同
+    pub content_hash: String,
+    pub files: usize,
+    pub success: bool,
+    pub error: Option<String>,
+    pub revision: Option<String>,
+    pub url: Option<String>,
+    /// 还保留着构建输出，可以回滚到这次部署
+    pub snapshot: bool,
+    /// 回滚产生的部署记录指向被回滚到的记录
+    pub rollback_of: Option<i64>,
+    /// 部署前检查的报告；被检查拦下的部署也有记录
+    pub checks: Option<GateReport>,
+}
+
+/// 构建输出的文件清单：相对路径 → SHA-256
+pub struct Snapshot {
+    pub manifest: BTreeMap<String, String>,
+    pub content_hash: String,
+}
+
+impl Snapshot {
+    /// 没有构建输出时使用，例如构建失败
+    pub fn empty() -> Self {
+        Self::from_manifest(BTreeMap::new())
+    }
+
+    fn from_manifest(manifest: BTreeMap<String, String>) -> Self {
+        let mut hasher = Sha256::new();
+        for (path, hash) in &manifest {
+            hasher.update(path.as_bytes());
+            hasher.update([0]);
+            hasher.update(hash.as_bytes());
+            hasher.update([b'\n']);
+        }
+        let content_hash = format!("{:x}", hasher.finalize());
+        Self { manifest, content_hash }
+    }
+}
+
+/// 把构建输出存进按内容寻址的对象目录，多次部署中相同的文件只保存一份
+pub fn take_snapshot(project_path: &Path, output_dir: &Path) -> Result<Snapshot> {
+    ensure_project_data_dir(project_path)?;
+    let objects = objects_dir(project_path);
+    let mut manifest = BTreeMap::new();
+    for (file, relative) in output_files(output_dir) {
+        let data = fs::read(&file)?;
+        let hash = format!("{:x}", Sha256::digest(&data));
+        let object = object_path(&objects, &hash);
+        if !object.exists() {
+            if let Some(parent) = object.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let temp = object.with_extension("tmp");
+            fs::write(&temp, &data)?;
+            fs::rename(&temp, &object)?;
+        }
+        manifest.insert(relative, hash);
+    }
+    Ok(Snapshot::from_manifest(manifest))
+}
+
+/// 记录部署结果；失败的部署不保留快照
+pub fn record(
+    project_path: &Path,
+    target: &str,
+    snapshot: &Snapshot,
+    result: std::result::Result<&DeployOutcome, &AppError>,
+    rollback_of: Option<i64>,
+) -> Result<i64> {
+    let entry = match result {
+        Ok(outcome) => Entry {
+            success: true,
+            error: None,
+            revision: outcome.revision.clone(),
+            url: outcome.url.clone(),
+            checks: outcome.checks.as_ref(),
+            rollback_of,
+        },
+        Err(e) => Entry {
+            success: false,
+            error: Some(e.to_string()),
+            revision: None,
+            url: None,
+            checks: None,
+            rollback_of,
+        },
+    };
+    insert(project_path, target, snapshot, entry)
+}
+
+/// 记录被部署前检查拦下的部署，连同检查报告
+pub fn record_blocked(project_path: &Path, target: &str, snapshot: &Snapshot, report: &GateReport) -> Result<i64> {
+    let entry = Entry {
+        success: false,
+        error: Some("Blocked by deploy checks".to_string()),
+        revision: None,
+        url: None,
+        checks: Some(report),
+        rollback_of: None,
+    };
+    insert(project_path, target, snapshot, entry)
+}
+
+struct Entry<'a> {
+    success: bool,
+    error: Option<String>,
+    revision: Option<String>,
+    url: Option<String>,
+    checks: Option<&'a GateReport>,
+    rollback_of: Option<i64>,
+}
+
+fn insert(project_path: &Path, target: &str, snapshot: &Snapshot, entry: Entry<'_>) -> Result<i64> {
+    let source_commit = SiteRepo::open(project_path)
+        .and_then(|repo| repo.head_id())
+        .ok()
+        .flatten();
+    let checks = entry.checks.map(serde_json::to_string).transpose()?;
+
+    let conn = history_db(project_path)?;
+    conn.execute(
+        "INSERT INTO deploy_history
+            (target, deployed_at, source_commit, content_hash, manifest, files,
+             success, error, revision, url, snapshot, rollback_of, checks)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?7, ?11, ?12)",
+        params![
+            target,
+            Utc::now().timestamp_millis(),
+            source_commit,
+            snapshot.content_hash,
+            serde_json::to_string(&snapshot.manifest)?,
+            snapshot.manifest.len() as i64,
+            entry.success,
+            entry.error,
+            entry.revision,
+            entry.url,
+            entry.rollback_of,
+            checks,
+        ],
+    )?;
+    let id = conn.last_insert_rowid();
+    prune_snapshots(&conn, project_path, target)?;
+    Ok(id)
+}
+
+/// 最近的部署在前；`target` 为 None 时列出所有部署目标
+pub fn list(project_path: &Path, target: Option<&str>) -> Result<Vec<DeployRecord>> {
+    let conn = history_db(project_path)?;
+    let records = conn
+        .prepare(
+            "SELECT id, target, deployed_at, source_commit, content_hash, files,
+                    success, error, revision, url, snapshot, rollback_of, checks
+             FROM deploy_history
+             WHERE ?1 IS NULL OR target = ?1
+             ORDER BY id DESC",
+        )?
+        .query_map(params![target], read_record)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(records)
+}
+
+pub fn get(project_path: &Path, id: i64) -> Result<DeployRecord> {
+    let conn = history_db(project_path)?;
+    conn.query_row(
+        "SELECT id, target, deployed_at, source_commit, content_hash, files,
+                success, error, revision, url, snapshot, rollback_of, checks
+         FROM deploy_history WHERE id = ?1",
+        params![id],
+        read_record,
+    )
+    .optional()?
+    .ok_or_else(|| AppError::NotFound(format!("Deploy {}", id)))
+}
+
+pub fn manifest(project_path: &Path, id: i64) -> Result<BTreeMap<String, String>> {
+    let conn = history_db(project_path)?;
+    let manifest: String = conn
+        .query_row("SELECT manifest FROM deploy_history WHERE id = ?1", params![id], |row| row.get(0))
+        .optional()?
+        .ok_or_else(|| AppError::NotFound(format!("Deploy {}", id)))?;
+    Ok(serde_json::from_str(&manifest)?)
+}
+
+/// 把一次成功部署的构建输出还原到 `.pineapple/deploy/rollback/`，返回目录和文件清单
+pub fn restore(project_path: &Path, id: i64) -> Result<(PathBuf, Snapshot)> {
+    let record = get(project_path, id)?;
+    if !record.success || !record.snapshot {
+        return Err(AppError::DeployFailed(format!("Deploy {} has no snapshot to roll back to", id)));
+    }
+    let snapshot = Snapshot::from_manifest(manifest(project_path, id)?);
+
+    let dir = project_data_dir(project_path).join("deploy").join("rollback");
+    if dir.exists() {
+        fs::remove_dir_all(&dir)?;
+    }
+    let objects = objects_dir(project_path);
+    for (relative, hash) in &snapshot.manifest {
+        let file = dir.join(relative);
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(object_path(&objects, hash), &file)
+            .map_err(|e| AppError::FileOperationFailed(format!("{}: {}", relative, e)))?;
+    }
+    Ok((dir, snapshot))
+}
+
+fn read_record(row: &rusqlite::Row) -> rusqlite::Result<DeployRecord> {
+    Ok(DeployRecord {
+        id: row.get(0)?,
+        target: row.get(1)?,
+        deployed_at: DateTime::from_timestamp_millis(row.get(2)?).unwrap_or_default(),
+        source_commit: row.get(3)?,
+        content_hash: row.get(4)?,
+        files: row.get::<_, i64>(5)? as usize,
+        success: row.get(6)?,
+        error: row.get(7)?,
+        revision: row.get(8)?,
+        url: row.get(9)?,
+        snapshot: row.get(10)?,
+        rollback_of: row.get(11)?,
+        checks: row
+            .get::<_, Option<String>>(12)?
+            .and_then(|checks| serde_json::from_str(&checks).ok()),
+    })
+}
+
+/// 只保留每个目标最近的快照，然后删除不再被任何快照引用的对象
+fn prune_snapshots(conn: &Connection, project_path: &Path, target: &str) -> Result<()> {
+    conn.execute(
+        "UPDATE deploy_history SET snapshot = 0
+         WHERE target = ?1 AND snapshot = 1 AND id NOT IN (
+             SELECT id FROM deploy_history WHERE target = ?1 AND snapshot = 1
+             ORDER BY id DESC LIMIT ?2
+         )",
+        params![target, KEEP_SNAPSHOTS as i64],
+    )?;
+
+    let mut referenced = HashSet::new();
+    let mut statement = conn.prepare("SELECT manifest FROM deploy_history WHERE snapshot = 1")?;
+    for manifest in statement.query_map([], |row| row.get::<_, String>(0))? {
+        let manifest: BTreeMap<String, String> = serde_json::from_str(&manifest?)?;
+        referenced.extend(manifest.into_values());
+    }
+
+    let Ok(prefixes) = fs::read_dir(objects_dir(project_path)) else {
+        return Ok(());
+    };
+    for prefix in prefixes.flatten() {
+        let Ok(objects) = fs::read_dir(prefix.path()) else {
+            continue;
+        };
+        for object in objects.flatten() {
+            let hash = format!(
+                "{}{}",
+                prefix.file_name().to_string_lossy(),
+                object.file_name().to_string_lossy()
+            );
+            if !referenced.contains(&hash) {
+                let _ = fs::remove_file(object.path());
+            }
+        }
+        let _ = fs::remove_dir(prefix.path());
+    }
+    Ok(())
+}
+
+fn history_db(project_path: &Path) -> Result<Connection> {
+    let conn = open_project_db(project_path)?;
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS deploy_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            target TEXT NOT NULL,
+            deployed_at INTEGER NOT NULL,
+            source_commit TEXT,
+            content_hash TEXT NOT NULL,
+            manifest TEXT NOT NULL,
+            files INTEGER NOT NULL,
+            success INTEGER NOT NULL,
+            error TEXT,
+            revision TEXT,
+            url TEXT,
+            snapshot INTEGER NOT NULL,
+            rollback_of INTEGER,
+            checks TEXT
+        );
+        CREATE INDEX IF NOT EXISTS deploy_history_target ON deploy_history (target, id);",
+    )?;
+    Ok(conn)
+}
+
+fn objects_dir(project_path: &Path) -> PathBuf {
+    project_data_dir(project_path).join("deploy").join("objects")
+}
+
+/// 按哈希的前两位分目录，避免单个目录下文件过多
+fn object_path(objects: &Path, hash: &str) -> PathBuf {
+    objects.join(&hash[..2]).join(&hash[2..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deploy::DeployState;
+
+    fn outcome(target: &str) -> DeployOutcome {
+        DeployOutcome {
+            target: target.to_string(),
+            files: 0,
+            changed: true,
+            revision: None,
+            state: DeployState::Ready,
+            url: Some("https://blog.example.com/".to_string()),
+            finished_at: Utc::now(),
+            checks: None,
+        }
+    }
+
+    fn deploy(project: &Path, target: &str, files: &[(&str, &str)], success: bool) -> (i64, Snapshot) {
+        let output = project.join("public");
+        let _ = fs::remove_dir_all(&output);
+        for (path, content) in files {
+            let file = output.join(path);
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+            fs::write(file, content).unwrap();
+        }
+        let snapshot = take_snapshot(project, &output).unwrap();
+        let outcome = outcome(target);
+        let error = AppError::DeployFailed("upload failed".to_string());
+        let result = if success { Ok(&outcome) } else { Err(&error) };
+        let id = record(project, target, &snapshot, result, None).unwrap();
+        (id, snapshot)
+    }
+
+    fn has_object(project: &Path, content: &str) -> bool {
+        let hash = format!("{:x}", Sha256::digest(content.as_bytes()));
+        object_path(&objects_dir(project), &hash).exists()
+    }
+
+    #[test]
+    fn prunes_old_snapshots_but_keeps_shared_objects() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path();
+        let (other, _) = deploy(project, "staging", &[("index.html", "staging")], true);
+        let (first, _) = deploy(project, "live", &[("index.html", "v0"), ("css/site.css", "shared")], true);
+        let (failed, _) = deploy(project, "live", &[("index.html", "broken"), ("css/site.css", "shared")], false);
+        assert!(!has_object(project, "broken"));
+        assert!(!get(project, failed).unwrap().snapshot);
+
+        for version in 1..=KEEP_SNAPSHOTS {
+            let index = format!("v{}", version);
+            deploy(project, "live", &[("index.html", &index), ("css/site.css", "shared")], true);
+        }
+
+        // 最早的快照超出保留数，只剩记录和清单
+        let record = get(project, first).unwrap();
+        assert!(record.success && !record.snapshot);
+        assert_eq!(manifest(project, first).unwrap().len(), 2);
+        assert!(!has_object(project, "v0"));
+        assert!(matches!(restore(project, first), Err(AppError::DeployFailed(_))));
+        assert!(matches!(restore(project, failed), Err(AppError::DeployFailed(_))));
+
+        // 仍被保留的快照引用的对象和其他目标的快照不受影响
+        assert!(has_object(project, "shared"));
+        assert!(has_object(project, "v1"));
+        assert!(get(project, other).unwrap().snapshot);
+        assert!(has_object(project, "staging"));
+        let live = list(project, Some("live")).unwrap();
+        assert_eq!(live.len(), KEEP_SNAPSHOTS + 2);
+        assert_eq!(live.iter().filter(|r| r.snapshot).count(), KEEP_SNAPSHOTS);
+        assert_eq!(list(project, None).unwrap().len(), KEEP_SNAPSHOTS + 3);
+    }
+
+    #[test]
+    fn restores_snapshot_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path();
+        let (id, snapshot) = deploy(project, "live", &[("index.html", "home"), ("posts/a/index.html", "a")], true);
+        deploy(project, "live", &[("index.html", "changed")], true);
+
+        let (restored, restored_snapshot) = restore(project, id).unwrap();
+        assert_eq!(restored_snapshot.content_hash, snapshot.content_hash);
+        assert_eq!(fs::read_to_string(restored.join("index.html")).unwrap(), "home");
+        assert_eq!(fs::read_to_string(restored.join("posts/a/index.html")).unwrap(), "a");
+        let record = get(project, id).unwrap();
+        assert_eq!(record.url.as_deref(), Some("https://blog.example.com/"));
+
+        assert!(matches!(get(project, 99), Err(AppError::NotFound(_))));
+        assert!(matches!(manifest(project, 99), Err(AppError::NotFound(_))));
+        assert!(matches!(restore(project, 99), Err(AppError::NotFound(_))));
+    }
+
+    #[test]
+    fn records_blocked_deploys_with_report() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path();
+        let report = GateReport {
+            checks: vec![crate::deploy::gate::CheckResult {
+                kind: crate::deploy::gate::CheckKind::Links,
+                level: crate::deploy::gate::CheckLevel::Blocking,
+                passed: false,
+                issues: vec![crate::deploy::gate::CheckIssue {
+                    path: Some("content/a.md".to_string()),
+                    line: Some(3),
+                    message: "Broken link /missing/".to_string(),
+                }],
+            }],
+            blocked: true,
+        };
+        let id = record_blocked(project, "live", &Snapshot::empty(), &report).unwrap();
+
+        let record = get(project, id).unwrap();
+        assert!(!record.success && !record.snapshot);
+        assert_eq!(record.error.as_deref(), Some("Blocked by deploy checks"));
+        let checks = record.checks.unwrap();
+        assert!(checks.blocked);
+        assert_eq!(checks.checks[0].issues[0].line, Some(3));
+        assert!(matches!(restore(project, id), Err(AppError::DeployFailed(_))));
+    }
+}
+
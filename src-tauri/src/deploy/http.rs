@@ -0,0 +1,70 @@
+use reqwest::{Client, Method, RequestBuilder, StatusCode, Url};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use crate::error::{AppError, Result};
+
+/// 部署服务的 REST API。地址可以在部署目标中修改，测试时指向本地的模拟服务
+pub struct ApiClient {
+    http: Client,
+    base: String,
+    token: String,
+}
+
+impl ApiClient {
+    pub fn new(base: &str, token: &str) -> Result<Self> {
+        Url::parse(base).map_err(|e| AppError::ParseError(format!("{}: {}", base, e)))?;
+        let http = Client::builder()
+            .user_agent(format!("Pineapple-Deploy/{}", env!("CARGO_PKG_VERSION")))
+            .build()
+            .map_err(|e| AppError::DeployFailed(e.to_string()))?;
+        Ok(Self {
+            http,
+            base: base.trim_end_matches('/').to_string(),
+            token: token.to_string(),
+        })
+    }
+
+    pub fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.http
+            .request(method, format!("{}{}", self.base, path))
+            .bearer_auth(&self.token)
+    }
+
+    pub async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        self.send(self.request(Method::GET, path)).await
+    }
+
+    pub async fn post<B: Serialize + ?Sized, T: DeserializeOwned>(&self, path: &str, body: &B) -> Result<T> {
+        self.send(self.request(Method::POST, path).json(body)).await
+    }
+
+    /// 发送请求并解析 JSON；非 2xx 响应的内容放进错误信息
+    pub async fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T> {
+        let (status, text) = self.send_raw(request).await?;
+        if !status.is_success() {
+            return Err(AppError::DeployFailed(format!("{}: {}", status, text.trim())));
+        }
+        serde_json::from_str(&text).map_err(|e| AppError::ParseError(format!("{}: {}", e, text.trim())))
+    }
+
+    /// 返回状态码和响应内容，由调用方处理错误响应
+    pub async fn send_raw(&self, request: RequestBuilder) -> Result<(StatusCode, String)> {
+        let response = request.send().await.map_err(|e| AppError::DeployFailed(e.to_string()))?;
+        let status = response.status();
+        let text = response.text().await.map_err(|e| AppError::DeployFailed(e.to_string()))?;
+        Ok((status, text))
+    }
+}
+
+/// 只保留非保留字符；`encode_slash` 为 false 时路径中的 `/` 不编码
+pub fn uri_encode(input: &str, encode_slash: bool) -> String {
+    let mut out = String::with_capacity(input.len());
+    for byte in input.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => out.push(byte as char),
+            b'/' if !encode_slash => out.push('/'),
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
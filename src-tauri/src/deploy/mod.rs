@@ -0,0 +1,395 @@
+pub mod traits;
+pub mod gate;
+pub mod git_pages;
+pub mod history;
+pub mod http;
+pub mod netlify;
+pub mod s3;
+pub mod ssh;
+pub mod vercel;
+
+pub use traits::DeployTarget;
+pub use gate::{DeployGateSettings, GateReport};
+pub use git_pages::{GitPagesOptions, GitPagesTarget};
+pub use history::DeployRecord;
+pub use netlify::{NetlifyOptions, NetlifyTarget};
+pub use s3::{S3Options, S3Target};
+pub use ssh::{SshOptions, SshTarget};
+pub use vercel::{VercelOptions, VercelTarget};
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::error::{AppError, Result};
+use crate::hooks::{self, Hook, HookStage, OutputCallback};
+use crate::models::{BuildOptions, BuildResult, EngineType};
+use crate::secrets::{load_credentials, required};
+use crate::settings::load_project_settings;
+use crate::site_config::output_dir;
+
+/// 保存在项目设置中的部署目标
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeployTargetConfig {
+    pub name: String,
+    /// 部署前使用的构建配置
+    #[serde(default = "default_build_profile")]
+    pub build_profile: String,
+    /// 站点的公开地址，部署服务不提供地址时使用
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(flatten)]
+    pub kind: DeployKind,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum DeployKind {
+    /// 把构建输出提交到 Git 仓库的分支，例如 GitHub Pages 的 `gh-pages`
+    GitPages(GitPagesOptions),
+    /// 上传到 S3 兼容的对象存储，凭据保存在系统钥匙串中
+    S3(S3Options),
+    /// 通过 SFTP 或本地的 `rsync` 同步到服务器上的目录
+    Ssh(SshOptions),
+    /// Netlify 的文件摘要部署 API
+    Netlify(NetlifyOptions),
+    /// Vercel 的部署 API
+    Vercel(VercelOptions),
+}
+
+fn default_build_profile() -> String {
+    "production".to_string()
+}
+
+/// 一次部署的结果
+#[derive(Debug, Clone, Serialize)]
+pub struct DeployOutcome {
+    pub target: String,
+    /// 部署的文件数
+    pub files: usize,
+    /// 与上次部署的内容相同时为 false
+    pub changed: bool,
+    /// 部署后的版本，例如分支上的提交、服务返回的部署 id
+    pub revision: Option<String>,
+    /// 部署服务还在处理时可以用 `status` 轮询
+    pub state: DeployState,
+    pub url: Option<String>,
+    pub finished_at: DateTime<Utc>,
+    /// 部署前检查的结果，包括只是警告的问题
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checks: Option<GateReport>,
+}
+
+/// `deploy` 的结果：部署完成，或者被部署前检查阻止（包括构建失败）
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum DeployResult {
+    Deployed(DeployOutcome),
+    Blocked(GateReport),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeployState {
+    /// 等待上传或排队中
+    Pending,
+    Processing,
+    Ready,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DeployStatus {
+    pub revision: String,
+    pub state: DeployState,
+    pub url: Option<String>,
+    pub error: Option<String>,
+}
+
+/// 部署服务在后台处理时，部署完成前等待的时间和查询间隔。超时后返回处理中的状态，
+/// 之后由前端用 `deploy_status` 继续查询
+const READY_TIMEOUT: Duration = Duration::from_secs(120);
+const READY_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// 部署进度事件
+pub const DEPLOY_PROGRESS_EVENT: &str = "deploy-progress";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeployPhase {
+    Build,
+    /// 部署前检查
+    Check,
+    Upload,
+    Delete,
+    /// 原子部署时切换到新版本
+    Activate,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DeployProgress {
+    pub target: String,
+    pub phase: DeployPhase,
+    pub done: usize,
+    pub total: usize,
+    /// 正在处理的文件，相对于构建输出目录
+    pub file: Option<String>,
+}
+
+/// 把部署进度交给调用方，命令中转发为 Tauri 事件
+pub struct Progress<'a> {
+    target: &'a str,
+    callback: &'a (dyn Fn(DeployProgress) + Send + Sync),
+}
+
+impl<'a> Progress<'a> {
+    pub fn new(target: &'a str, callback: &'a (dyn Fn(DeployProgress) + Send + Sync)) -> Self {
+        Self { target, callback }
+    }
+
+    pub(crate) fn report(&self, phase: DeployPhase, done: usize, total: usize, file: Option<&str>) {
+        (self.callback)(DeployProgress {
+            target: self.target.to_string(),
+            phase,
+            done,
+            total,
+            file: file.map(str::to_string),
+        });
+    }
+
+    /// 转发在其他线程中产生的进度
+    pub(crate) fn forward(&self, progress: DeployProgress) {
+        (self.callback)(progress);
+    }
+}
+
+/// 根据项目设置中的部署目标创建实现，需要的凭据从系统钥匙串读取
+pub async fn create_target(project_path: &Path, config: &DeployTargetConfig) -> Result<Arc<dyn DeployTarget>> {
+    let name = config.name.clone();
+    let target: Arc<dyn DeployTarget> = match &config.kind {
+        DeployKind::GitPages(options) => Arc::new(GitPagesTarget::new(project_path, name, options.clone())),
+        DeployKind::S3(options) => {
+            let credentials = load_credentials(project_path, &config.name).await?;
+            let credentials = s3::S3Credentials::from_store(&credentials)?;
+            Arc::new(S3Target::new(name, options.clone(), credentials))
+        }
+        DeployKind::Ssh(options) => {
+            let credentials = load_credentials(project_path, &config.name).await?;
+            let passphrase = credentials.get("passphrase").cloned();
+            Arc::new(SshTarget::new(name, options.clone(), passphrase))
+        }
+        DeployKind::Netlify(options) => {
+            let credentials = load_credentials(project_path, &config.name).await?;
+            Arc::new(NetlifyTarget::new(name, options.clone(), required(&credentials, "token")?)?)
+        }
+        DeployKind::Vercel(options) => {
+            let credentials = load_credentials(project_path, &config.name).await?;
+            Arc::new(VercelTarget::new(name, options.clone(), required(&credentials, "token")?)?)
+        }
+    };
+    Ok(target)
+}
+
+/// 先用目标的构建配置构建站点并运行部署前检查，检查通过后再部署构建输出。
+/// 构建失败时即使关闭了部署前检查也返回检查报告，其中包含构建错误。
+/// 构建和部署前后运行项目设置中的钩子，钩子的输出通过 `on_output` 回调
+pub async fn deploy(
+    project_path: &Path,
+    engine: &EngineType,
+    config: &DeployTargetConfig,
+    progress: &Progress<'_>,
+    on_output: OutputCallback<'_>,
+) -> Result<DeployResult> {
+    let target = create_target(project_path, config).await?;
+    let settings = load_project_settings(project_path)?;
+    let options = &settings.profile(&config.build_profile, engine)?.options;
+    progress.report(DeployPhase::Build, 0, 1, None);
+    let (build, output_dir) = build_for_deploy(project_path, engine, options, &settings.hooks, on_output).await?;
+    progress.report(DeployPhase::Build, 1, 1, None);
+
+    let checks = if settings.deploy_gate.enabled || !build.success {
+        progress.report(DeployPhase::Check, 0, 1, None);
+        let gate_settings = if settings.deploy_gate.enabled {
+            settings.deploy_gate.clone()
+        } else {
+            DeployGateSettings::off()
+        };
+        let built = Some(output_dir.as_path()).filter(|_| build.success);
+        let report = gate::run(project_path, engine, &gate_settings, options, &build, built)?;
+        if report.blocked {
+            // 构建失败时输出目录里可能是上次构建的文件，不计入清单
+            let snapshot = match built {
+                Some(dir) => history::take_snapshot(project_path, dir)?,
+                None => history::Snapshot::empty(),
+            };
+            history::record_blocked(project_path, &config.name, &snapshot, &report)?;
+            return Ok(DeployResult::Blocked(report));
+        }
+        progress.report(DeployPhase::Check, 1, 1, None);
+        Some(report)
+    } else {
+        None
+    };
+
+    let env = hook_env(config, &output_dir, None);
+    hooks::run_stage(project_path, &settings.hooks, HookStage::PreDeploy, &env, on_output).await?;
+    let snapshot = history::take_snapshot(project_path, &output_dir)?;
+    let result = deploy_output(target.as_ref(), config, &output_dir, progress)
+        .await
+        .map(|outcome| DeployOutcome { checks, ..outcome });
+    let recorded = history::record(project_path, &config.name, &snapshot, result.as_ref(), None);
+    let outcome = result?;
+    recorded?;
+
+    let env = hook_env(config, &output_dir, Some(&outcome));
+    hooks::run_stage(project_path, &settings.hooks, HookStage::PostDeploy, &env, on_output).await?;
+    Ok(DeployResult::Deployed(outcome))
+}
+
+/// 只构建和运行部署前检查，不部署；检查设置为关闭时也会运行
+pub async fn check(
+    project_path: &Path,
+    engine: &EngineType,
+    config: &DeployTargetConfig,
+    on_output: OutputCallback<'_>,
+) -> Result<GateReport> {
+    let settings = load_project_settings(project_path)?;
+    let options = &settings.profile(&config.build_profile, engine)?.options;
+    let (build, output_dir) = build_for_deploy(project_path, engine, options, &settings.hooks, on_output).await?;
+    let output_dir = Some(output_dir.as_path()).filter(|_| build.success);
+    gate::run(project_path, engine, &settings.deploy_gate, options, &build, output_dir)
+}
+
+/// 把一次成功部署的快照重新部署到同一个目标，不重新构建；仍然运行 pre-deploy / post-deploy 钩子
+pub async fn rollback(
+    project_path: &Path,
+    config: &DeployTargetConfig,
+    record_id: i64,
+    progress: &Progress<'_>,
+    on_output: OutputCallback<'_>,
+) -> Result<DeployOutcome> {
+    let record = history::get(project_path, record_id)?;
+    if record.target != config.name {
+        return Err(AppError::DeployFailed(format!(
+            "Deploy {} was made to '{}', not '{}'",
+            record_id, record.target, config.name
+        )));
+    }
+    let target = create_target(project_path, config).await?;
+    let settings = load_project_settings(project_path)?;
+    let (output_dir, snapshot) = history::restore(project_path, record_id)?;
+
+    let env = hook_env(config, &output_dir, None);
+    hooks::run_stage(project_path, &settings.hooks, HookStage::PreDeploy, &env, on_output).await?;
+    let result = deploy_output(target.as_ref(), config, &output_dir, progress).await;
+    let recorded = history::record(project_path, &config.name, &snapshot, result.as_ref(), Some(record_id));
+    let post_deploy = match &result {
+        Ok(outcome) => {
+            let env = hook_env(config, &output_dir, Some(outcome));
+            hooks::run_stage(project_path, &settings.hooks, HookStage::PostDeploy, &env, on_output)
+                .await
+                .map(drop)
+        }
+        Err(_) => Ok(()),
+    };
+    let _ = std::fs::remove_dir_all(&output_dir);
+    let outcome = result?;
+    recorded?;
+    post_deploy?;
+    Ok(outcome)
+}
+
+async fn deploy_output(
+    target: &dyn DeployTarget,
+    config: &DeployTargetConfig,
+    output_dir: &Path,
+    progress: &Progress<'_>,
+) -> Result<DeployOutcome> {
+    let mut outcome = target.deploy(output_dir, progress).await?;
+    if let (DeployState::Pending | DeployState::Processing, Some(revision)) = (outcome.state, &outcome.revision) {
+        let status = target
+            .wait_until_ready(revision, READY_POLL_INTERVAL, READY_TIMEOUT)
+            .await?;
+        if status.state == DeployState::Failed {
+            return Err(AppError::DeployFailed(
+                status.error.unwrap_or_else(|| format!("Deploy {} failed", status.revision)),
+            ));
+        }
+        outcome.state = status.state;
+        outcome.url = status.url.or(outcome.url);
+    }
+    if outcome.url.is_none() {
+        outcome.url = config.url.clone();
+    }
+    Ok(outcome)
+}
+
+/// 部署钩子的环境变量；post-deploy 钩子另外可以读到部署的地址和版本
+fn hook_env(
+    config: &DeployTargetConfig,
+    output_dir: &Path,
+    outcome: Option<&DeployOutcome>,
+) -> Vec<(&'static str, String)> {
+    let mut env = vec![
+        ("PINEAPPLE_DEPLOY_TARGET", config.name.clone()),
+        ("PINEAPPLE_OUTPUT_DIR", output_dir.to_string_lossy().to_string()),
+    ];
+    if let Some(outcome) = outcome {
+        env.push(("PINEAPPLE_DEPLOY_URL", outcome.url.clone().unwrap_or_default()));
+        env.push(("PINEAPPLE_DEPLOY_REVISION", outcome.revision.clone().unwrap_or_default()));
+    }
+    env
+}
+
+pub async fn status(project_path: &Path, config: &DeployTargetConfig, revision: &str) -> Result<DeployStatus> {
+    let mut status = create_target(project_path, config).await?.status(revision).await?;
+    if status.url.is_none() {
+        status.url = config.url.clone();
+    }
+    Ok(status)
+}
+
+/// 返回构建结果和构建输出目录
+async fn build_for_deploy(
+    project_path: &Path,
+    engine: &EngineType,
+    options: &BuildOptions,
+    hooks: &[Hook],
+    on_output: OutputCallback<'_>,
+) -> Result<(BuildResult, PathBuf)> {
+    let result = hooks::build(project_path, engine, options, hooks, on_output).await?;
+    Ok((result, output_dir(project_path, engine, options.destination.as_deref())))
+}
+
+/// 构建输出中的所有文件及其相对路径（使用 `/` 分隔），包括 `.well-known` 等隐藏目录
+pub(crate) fn output_files(output_dir: &Path) -> Vec<(PathBuf, String)> {
+    fn walk(dir: &Path, files: &mut Vec<PathBuf>) {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                walk(&path, files);
+            } else if path.is_file() {
+                files.push(path);
+            }
+        }
+    }
+
+    let mut files = Vec::new();
+    walk(output_dir, &mut files);
+    files.sort();
+    files
+        .into_iter()
+        .map(|file| {
+            let relative = file
+                .strip_prefix(output_dir)
+                .unwrap_or(&file)
+                .to_string_lossy()
+                .replace('\\', "/");
+            (file, relative)
+        })
+        .collect()
+}
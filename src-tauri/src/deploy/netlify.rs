@@ -0,0 +1,286 @@
+use async_trait::async_trait;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+use chrono::Utc;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha1::{Digest, Sha1};
+use crate::deploy::http::{uri_encode, ApiClient};
+use crate::deploy::{output_files, DeployOutcome, DeployPhase, DeployState, DeployStatus, DeployTarget, Progress};
+use crate::error::{AppError, Result};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetlifyOptions {
+    pub site_id: String,
+    /// 草稿部署不发布到正式地址，只返回预览地址
+    pub draft: bool,
+    pub api_url: String,
+}
+
+impl Default for NetlifyOptions {
+    fn default() -> Self {
+        Self {
+            site_id: String::new(),
+            draft: false,
+            api_url: "https://api.netlify.com/api/v1".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct NetlifyDeploy {
+    id: String,
+    state: String,
+    /// 服务端还没有的文件摘要
+    #[serde(default)]
+    required: Vec<String>,
+    ssl_url: Option<String>,
+    url: Option<String>,
+    deploy_ssl_url: Option<String>,
+    error_message: Option<String>,
+}
+
+/// 先提交所有文件的 SHA-1，再只上传服务端要求的文件
+pub struct NetlifyTarget {
+    name: String,
+    options: NetlifyOptions,
+    api: ApiClient,
+}
+
+impl NetlifyTarget {
+    pub fn new(name: String, options: NetlifyOptions, token: &str) -> Result<Self> {
+        let api = ApiClient::new(&options.api_url, token)?;
+        Ok(Self { name, options, api })
+    }
+
+    fn status_of(&self, deploy: NetlifyDeploy) -> DeployStatus {
+        let state = match deploy.state.as_str() {
+            "ready" => DeployState::Ready,
+            "error" | "rejected" => DeployState::Failed,
+            "processing" | "processed" | "preparing" | "prepared" => DeployState::Processing,
+            _ => DeployState::Pending,
+        };
+        let url = if self.options.draft {
+            deploy.deploy_ssl_url
+        } else {
+            deploy.ssl_url.or(deploy.url)
+        };
+        DeployStatus {
+            revision: deploy.id,
+            state,
+            url,
+            error: deploy.error_message,
+        }
+    }
+}
+
+#[async_trait]
+impl DeployTarget for NetlifyTarget {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn deploy(&self, output_dir: &Path, progress: &Progress<'_>) -> Result<DeployOutcome> {
+        if self.options.site_id.trim().is_empty() {
+            return Err(AppError::DeployFailed("Site id is empty".to_string()));
+        }
+        let files = output_files(output_dir);
+        let mut digests = BTreeMap::new();
+        for (file, relative) in &files {
+            digests.insert(format!("/{}", relative), format!("{:x}", Sha1::digest(fs::read(file)?)));
+        }
+
+        let deploy: NetlifyDeploy = self
+            .api
+            .post(
+                &format!("/sites/{}/deploys", uri_encode(self.options.site_id.trim(), true)),
+                &json!({ "files": digests, "draft": self.options.draft }),
+            )
+            .await?;
+
+        // 内容相同的文件只需上传一次
+        let required: HashSet<&str> = deploy.required.iter().map(String::as_str).collect();
+        let mut uploaded = HashSet::new();
+        let uploads: Vec<_> = files
+            .iter()
+            .filter(|(_, relative)| {
+                let digest = digests[&format!("/{}", relative)].as_str();
+                required.contains(digest) && uploaded.insert(digest)
+            })
+            .collect();
+        for (index, (file, relative)) in uploads.iter().enumerate() {
+            progress.report(DeployPhase::Upload, index, uploads.len(), Some(relative));
+            let request = self
+                .api
+                .request(
+                    Method::PUT,
+                    &format!("/deploys/{}/files/{}", deploy.id, uri_encode(relative, false)),
+                )
+                .header(CONTENT_TYPE, "application/octet-stream")
+                .body(fs::read(file)?);
+            self.api.send::<Value>(request).await?;
+        }
+        progress.report(DeployPhase::Upload, uploads.len(), uploads.len(), None);
+
+        let status = self.status(&deploy.id).await?;
+        Ok(DeployOutcome {
+            target: self.name.clone(),
+            files: files.len(),
+            changed: !uploads.is_empty(),
+            revision: Some(status.revision),
+            state: status.state,
+            url: status.url,
+            finished_at: Utc::now(),
+            checks: None,
+        })
+    }
+
+    async fn status(&self, revision: &str) -> Result<DeployStatus> {
+        let deploy: NetlifyDeploy = self
+            .api
+            .get(&format!("/deploys/{}", uri_encode(revision, true)))
+            .await?;
+        Ok(self.status_of(deploy))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{MockRequest, MockResponse, MockServer};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    fn sha1(data: &str) -> String {
+        format!("{:x}", Sha1::digest(data.as_bytes()))
+    }
+
+    fn target(server: &MockServer, draft: bool) -> NetlifyTarget {
+        let options = NetlifyOptions {
+            site_id: "site-1".to_string(),
+            draft,
+            api_url: server.url().to_string(),
+        };
+        NetlifyTarget::new("netlify".to_string(), options, "secret").unwrap()
+    }
+
+    #[tokio::test]
+    async fn uploads_only_required_digests_and_waits_until_ready() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path();
+        fs::create_dir_all(output.join("posts")).unwrap();
+        fs::write(output.join("index.html"), "home").unwrap();
+        fs::write(output.join("copy.html"), "home").unwrap();
+        fs::write(output.join("posts/hello.html"), "hello").unwrap();
+        fs::write(output.join("style.css"), "body {}").unwrap();
+
+        let polls = AtomicUsize::new(0);
+        let server = MockServer::start(move |request: &MockRequest| {
+            assert_eq!(request.header("authorization"), Some("Bearer secret"));
+            match (request.method.as_str(), request.path.as_str()) {
+                ("POST", "/sites/site-1/deploys") => MockResponse::status(200).body(
+                    json!({ "id": "d1", "state": "uploading", "required": [sha1("home"), sha1("hello")] })
+                        .to_string(),
+                ),
+                ("PUT", _) => MockResponse::status(200).body("{}"),
+                ("GET", "/deploys/d1") => {
+                    let state = match polls.fetch_add(1, Ordering::SeqCst) {
+                        0 => "uploaded",
+                        1 => "processing",
+                        _ => "ready",
+                    };
+                    MockResponse::status(200).body(
+                        json!({ "id": "d1", "state": state, "ssl_url": "https://blog.netlify.app" }).to_string(),
+                    )
+                }
+                _ => MockResponse::status(404),
+            }
+        })
+        .await;
+
+        let target = target(&server, false);
+        let callback = |_| {};
+        let outcome = target
+            .deploy(output, &Progress::new("netlify", &callback))
+            .await
+            .unwrap();
+        assert!(outcome.changed);
+        assert_eq!(outcome.files, 4);
+        assert_eq!(outcome.revision.as_deref(), Some("d1"));
+        assert_eq!(outcome.state, DeployState::Pending);
+
+        let requests = server.requests();
+        let created: Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(created["files"].as_object().unwrap().len(), 4);
+        assert_eq!(created["files"]["/posts/hello.html"], sha1("hello"));
+        assert_eq!(created["draft"], false);
+        // 相同内容的文件只上传一次，服务端已有的 style.css 不上传
+        let uploads: Vec<_> = requests
+            .iter()
+            .filter(|r| r.method == "PUT")
+            .map(|r| (r.path.as_str(), r.body.as_slice()))
+            .collect();
+        assert_eq!(
+            uploads,
+            [
+                ("/deploys/d1/files/copy.html", b"home".as_slice()),
+                ("/deploys/d1/files/posts/hello.html", b"hello".as_slice()),
+            ]
+        );
+
+        let status = target
+            .wait_until_ready("d1", Duration::from_millis(10), Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(status.state, DeployState::Ready);
+        assert_eq!(status.url.as_deref(), Some("https://blog.netlify.app"));
+    }
+
+    #[tokio::test]
+    async fn wait_gives_up_after_timeout() {
+        let server = MockServer::start(|_: &MockRequest| {
+            MockResponse::status(200).body(json!({ "id": "d1", "state": "processing" }).to_string())
+        })
+        .await;
+        let status = target(&server, false)
+            .wait_until_ready("d1", Duration::from_millis(20), Duration::from_millis(100))
+            .await
+            .unwrap();
+        assert_eq!(status.state, DeployState::Processing);
+        let polls = server.requests().len();
+        assert!((2..=6).contains(&polls), "{} polls", polls);
+    }
+
+    #[tokio::test]
+    async fn maps_deploy_states() {
+        let server = MockServer::start(|_: &MockRequest| MockResponse::status(404)).await;
+        let deploy = |state: &str| NetlifyDeploy {
+            id: "d1".to_string(),
+            state: state.to_string(),
+            required: Vec::new(),
+            ssl_url: Some("https://blog.netlify.app".to_string()),
+            url: None,
+            deploy_ssl_url: Some("https://d1--blog.netlify.app".to_string()),
+            error_message: None,
+        };
+        let live = target(&server, false);
+        for (state, expected) in [
+            ("new", DeployState::Pending),
+            ("uploading", DeployState::Pending),
+            ("processing", DeployState::Processing),
+            ("prepared", DeployState::Processing),
+            ("ready", DeployState::Ready),
+            ("error", DeployState::Failed),
+            ("rejected", DeployState::Failed),
+        ] {
+            assert_eq!(live.status_of(deploy(state)).state, expected, "{}", state);
+        }
+        assert_eq!(live.status_of(deploy("ready")).url.as_deref(), Some("https://blog.netlify.app"));
+        let draft = target(&server, true);
+        assert_eq!(draft.status_of(deploy("ready")).url.as_deref(), Some("https://d1--blog.netlify.app"));
+    }
+}
@@ -0,0 +1,843 @@
+use async_trait::async_trait;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use ssh2::{Channel, CheckResult, KnownHostFileKind, Session, Sftp};
+use crate::deploy::{output_files, DeployOutcome, DeployPhase, DeployState, DeployStatus, DeployTarget, Progress};
+use crate::error::{AppError, Result};
+
+/// SFTP 部署记录已上传文件的哈希，用于增量上传。清单不能放在站点根目录中，否则会被公开访问：
+/// 原子部署放在 `<remote_path>/` 下、`releases/` 旁边，
+/// 其他情况放在服务器用户主目录的 `.pineapple/manifests/` 下，按站点目录区分
+const MANIFEST_FILE: &str = ".pineapple-manifest.json";
+const REMOTE_MANIFEST_DIR: &str = ".pineapple/manifests";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferMethod {
+    Sftp,
+    /// 调用本地的 `rsync` 和 `ssh`
+    Rsync,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SshOptions {
+    pub host: String,
+    pub port: u16,
+    pub user: String,
+    /// 私钥路径；为空时使用 ssh-agent。私钥的密码保存在系统钥匙串的 `passphrase` 字段
+    pub key_path: Option<String>,
+    /// 服务器上的站点目录，可以是相对于用户主目录的路径
+    pub remote_path: String,
+    pub method: TransferMethod,
+    /// 先上传到 `releases/` 下的新目录，再把 `current` 符号链接切换过去；
+    /// Web 服务器应使用 `<remote_path>/current` 作为站点根目录。
+    /// 服务器需要 GNU coreutils（`cp -al` 和 `mv -T`），macOS 和 BSD 自带的工具不支持
+    pub atomic: bool,
+    /// 原子部署保留的版本数，包括当前版本
+    pub keep_releases: usize,
+    /// 非原子部署时删除构建输出中已经不存在的远程文件；
+    /// 原子部署的新版本总是与构建输出一致
+    pub delete_removed: bool,
+    /// 检查主机密钥使用的 known_hosts 文件，为空时使用 `~/.ssh/known_hosts`
+    pub known_hosts: Option<String>,
+}
+
+impl Default for SshOptions {
+    fn default() -> Self {
+        Self {
+            host: String::new(),
+            port: 22,
+            user: String::new(),
+            key_path: None,
+            remote_path: String::new(),
+            method: TransferMethod::Sftp,
+            atomic: false,
+            keep_releases: 5,
+            delete_removed: false,
+            known_hosts: None,
+        }
+    }
+}
+
+pub struct SshTarget {
+    name: String,
+    options: SshOptions,
+    /// 私钥的密码
+    passphrase: Option<String>,
+}
+
+impl SshTarget {
+    pub fn new(name: String, options: SshOptions, passphrase: Option<String>) -> Self {
+        Self {
+            name,
+            options,
+            passphrase,
+        }
+    }
+}
+
+#[async_trait]
+impl DeployTarget for SshTarget {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// ssh2 和 rsync 都是阻塞调用，在阻塞线程池中执行，进度通过通道转发
+    async fn deploy(&self, output_dir: &Path, progress: &Progress<'_>) -> Result<DeployOutcome> {
+        let name = self.name.clone();
+        let options = self.options.clone();
+        let passphrase = self.passphrase.clone();
+        let output_dir = output_dir.to_path_buf();
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let task = tokio::task::spawn_blocking(move || {
+            let send = move |event| {
+                let _ = tx.send(event);
+            };
+            deploy(&name, &output_dir, &options, passphrase.as_deref(), &Progress::new(&name, &send))
+        });
+        while let Some(event) = rx.recv().await {
+            progress.forward(event);
+        }
+        task.await.map_err(|e| AppError::DeployFailed(e.to_string()))?
+    }
+
+    async fn status(&self, revision: &str) -> Result<DeployStatus> {
+        Ok(DeployStatus {
+            revision: revision.to_string(),
+            state: DeployState::Ready,
+            url: None,
+            error: None,
+        })
+    }
+}
+
+/// 原子部署中的一个版本，目录为 `<remote_path>/releases/<id>`
+struct Release {
+    id: String,
+    dir: String,
+    /// 当前 `current` 指向的版本
+    previous: Option<String>,
+}
+
+/// 同步构建输出到服务器。服务器的主机密钥必须已经在 `~/.ssh/known_hosts` 中
+pub fn deploy(
+    target_name: &str,
+    output_dir: &Path,
+    options: &SshOptions,
+    passphrase: Option<&str>,
+    progress: &Progress<'_>,
+) -> Result<DeployOutcome> {
+    if !output_dir.is_dir() {
+        return Err(AppError::PathNotFound(output_dir.display().to_string()));
+    }
+    if options.host.trim().is_empty() || options.user.trim().is_empty() {
+        return Err(AppError::DeployFailed("Host and user are required".to_string()));
+    }
+    let base = options.remote_path.trim().trim_end_matches('/');
+    if base.is_empty() {
+        return Err(AppError::DeployFailed("Remote path is empty".to_string()));
+    }
+
+    let files = output_files(output_dir);
+    let shell = match options.method {
+        TransferMethod::Sftp => RemoteShell::Session(connect(options, passphrase)?),
+        TransferMethod::Rsync => RemoteShell::OpenSsh(options),
+    };
+    let release = if options.atomic {
+        Some(prepare_release(&shell, base)?)
+    } else {
+        None
+    };
+    let dest = release.as_ref().map_or(base, |release| release.dir.as_str());
+    let delete_removed = options.delete_removed || release.is_some();
+
+    let manifest_path = manifest_path(base, options.atomic);
+    let (changed, manifest) = match &shell {
+        RemoteShell::Session(session) => {
+            // 没有可以复制的当前版本或目录不存在时，旧清单已经不能说明服务器上有哪些文件
+            let has_previous = match &release {
+                Some(release) => release.previous.is_some(),
+                None => session.sftp()?.stat(Path::new(dest)).is_ok(),
+            };
+            let path = Some(manifest_path.as_str()).filter(|_| has_previous);
+            sftp_sync(session, dest, path, &files, delete_removed, progress)?
+        }
+        RemoteShell::OpenSsh(_) => {
+            let link_dest = release.as_ref().and_then(|r| r.previous.as_deref());
+            let changed = rsync(options, output_dir, dest, link_dest, delete_removed, files.len(), progress)?;
+            // rsync 不维护清单，之后改用 SFTP 时需要全部重新上传
+            shell.exec(&format!("rm -f {}", quote(&manifest_path)))?;
+            (changed, None)
+        }
+    };
+
+    let revision = match release {
+        Some(release) if !changed && release.previous.is_some() => {
+            shell.exec(&format!("rm -rf {}", quote(&release.dir)))?;
+            release.previous
+        }
+        Some(release) => {
+            progress.report(DeployPhase::Activate, 0, 1, None);
+            activate(&shell, base, &release.id)?;
+            prune_releases(&shell, base, options.keep_releases)?;
+            progress.report(DeployPhase::Activate, 1, 1, None);
+            Some(release.id)
+        }
+        None => None,
+    };
+    // 清单描述的是已经生效的文件，所以在切换版本之后才写入
+    if let (RemoteShell::Session(session), Some(manifest)) = (&shell, manifest) {
+        write_manifest(session, &manifest_path, &manifest)?;
+    }
+
+    Ok(DeployOutcome {
+        target: target_name.to_string(),
+        files: files.len(),
+        changed,
+        revision,
+        state: DeployState::Ready,
+        url: None,
+        finished_at: Utc::now(),
+        checks: None,
+    })
+}
+
+/// 在服务器上执行命令：SFTP 方式复用同一个 SSH 会话，rsync 方式调用本地的 `ssh`
+enum RemoteShell<'a> {
+    Session(Session),
+    OpenSsh(&'a SshOptions),
+}
+
+impl RemoteShell<'_> {
+    fn exec(&self, command: &str) -> Result<String> {
+        let (status, stdout, stderr) = match self {
+            RemoteShell::Session(session) => {
+                let mut channel = session.channel_session()?;
+                channel.exec(command)?;
+                // 依次读到结束会在另一个输出写满窗口时互相等待，所以非阻塞地交替读取
+                session.set_blocking(false);
+                let output = read_channel(&mut channel);
+                session.set_blocking(true);
+                let (stdout, stderr) = output?;
+                channel.wait_close()?;
+                (
+                    channel.exit_status()?,
+                    String::from_utf8_lossy(&stdout).into_owned(),
+                    String::from_utf8_lossy(&stderr).into_owned(),
+                )
+            }
+            RemoteShell::OpenSsh(options) => {
+                let output = Command::new("ssh")
+                    .args(ssh_args(options))
+                    .arg(format!("{}@{}", options.user, options.host))
+                    .arg(command)
+                    .stdin(Stdio::null())
+                    .output()
+                    .map_err(|e| AppError::DeployFailed(format!("ssh: {}", e)))?;
+                (
+                    output.status.code().unwrap_or(-1),
+                    String::from_utf8_lossy(&output.stdout).into_owned(),
+                    String::from_utf8_lossy(&output.stderr).into_owned(),
+                )
+            }
+        };
+        if status != 0 {
+            return Err(AppError::DeployFailed(format!("{}: {}", command, stderr.trim())));
+        }
+        Ok(stdout)
+    }
+}
+
+fn read_channel(channel: &mut Channel) -> std::io::Result<(Vec<u8>, Vec<u8>)> {
+    fn read_some(read: std::io::Result<usize>, buffer: &[u8], output: &mut Vec<u8>) -> std::io::Result<bool> {
+        match read {
+            Ok(n) => {
+                output.extend_from_slice(&buffer[..n]);
+                Ok(n > 0)
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+    let mut buffer = [0u8; 16 * 1024];
+    loop {
+        let read = channel.read(&mut buffer);
+        let mut progressed = read_some(read, &buffer, &mut stdout)?;
+        let read = channel.stderr().read(&mut buffer);
+        progressed |= read_some(read, &buffer, &mut stderr)?;
+        if !progressed {
+            if channel.eof() {
+                return Ok((stdout, stderr));
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+    }
+}
+
+fn connect(options: &SshOptions, passphrase: Option<&str>) -> Result<Session> {
+    let tcp = TcpStream::connect((options.host.as_str(), options.port))
+        .map_err(|e| AppError::DeployFailed(format!("{}:{}: {}", options.host, options.port, e)))?;
+    let mut session = Session::new()?;
+    session.set_tcp_stream(tcp);
+    session.handshake()?;
+    check_host_key(&session, options)?;
+
+    match options.key_path.as_deref().filter(|k| !k.trim().is_empty()) {
+        Some(key) => session.userauth_pubkey_file(&options.user, None, &expand_home(key), passphrase)?,
+        None => session.userauth_agent(&options.user)?,
+    }
+    if !session.authenticated() {
+        return Err(AppError::DeployFailed(format!("Authentication failed for {}", options.user)));
+    }
+    Ok(session)
+}
+
+/// 与 `ssh` 的默认行为一致：不信任未知主机，主机密钥变化时拒绝连接
+fn check_host_key(session: &Session, options: &SshOptions) -> Result<()> {
+    let mut known_hosts = session.known_hosts()?;
+    let file = known_hosts_file(options);
+    if file.is_file() {
+        known_hosts.read_file(&file, KnownHostFileKind::OpenSSH)?;
+    }
+    let (key, _) = session
+        .host_key()
+        .ok_or_else(|| AppError::DeployFailed("Server sent no host key".to_string()))?;
+    match known_hosts.check_port(&options.host, options.port, key) {
+        CheckResult::Match => Ok(()),
+        CheckResult::Mismatch => Err(AppError::DeployFailed(format!(
+            "Host key for {} does not match known_hosts",
+            options.host
+        ))),
+        CheckResult::NotFound | CheckResult::Failure => Err(AppError::DeployFailed(format!(
+            "Unknown host {}; connect once with ssh to add it to known_hosts",
+            options.host
+        ))),
+    }
+}
+
+/// 新版本目录。SFTP 方式先用硬链接复制当前版本，这样只需上传有变化的文件
+fn prepare_release(shell: &RemoteShell, base: &str) -> Result<Release> {
+    let previous = shell
+        .exec(&format!("readlink {}", quote(&format!("{}/current", base))))
+        .ok()
+        .and_then(|target| {
+            let target = target.trim().trim_end_matches('/');
+            target.rsplit('/').next().filter(|id| !id.is_empty()).map(str::to_string)
+        });
+
+    let id = Utc::now().format("%Y%m%d%H%M%S%3f").to_string();
+    let releases = format!("{}/releases", base);
+    let dir = format!("{}/{}", releases, id);
+    match (&previous, shell) {
+        (Some(previous), RemoteShell::Session(_)) => shell.exec(&format!(
+            "mkdir -p {} && cp -al {} {}",
+            quote(&releases),
+            quote(&format!("{}/{}", releases, previous)),
+            quote(&dir)
+        ))?,
+        _ => shell.exec(&format!("mkdir -p {}", quote(&dir)))?,
+    };
+
+    Ok(Release { id, dir, previous })
+}
+
+/// 用 `rename` 替换符号链接，访问者不会看到一半新一半旧的站点
+fn activate(shell: &RemoteShell, base: &str, id: &str) -> Result<()> {
+    let temp = format!("{}/current.tmp", base);
+    shell.exec(&format!(
+        "ln -sfn {} {} && mv -Tf {} {}",
+        quote(&format!("releases/{}", id)),
+        quote(&temp),
+        quote(&temp),
+        quote(&format!("{}/current", base))
+    ))?;
+    Ok(())
+}
+
+/// 版本目录名是时间戳，按名称排序就是按时间排序。在本地决定要删除的版本，
+/// 不依赖服务器上 `head` 等工具的具体实现
+fn prune_releases(shell: &RemoteShell, base: &str, keep: usize) -> Result<()> {
+    let releases = format!("{}/releases", base);
+    let mut ids: Vec<String> = shell
+        .exec(&format!("ls -1 {}", quote(&releases)))?
+        .lines()
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(str::to_string)
+        .collect();
+    ids.sort();
+    let stale = ids.len().saturating_sub(keep.max(1));
+    if stale == 0 {
+        return Ok(());
+    }
+    let paths: Vec<String> = ids[..stale]
+        .iter()
+        .map(|id| quote(&format!("{}/{}", releases, id)))
+        .collect();
+    shell.exec(&format!("rm -rf {}", paths.join(" ")))?;
+    Ok(())
+}
+
+/// 按清单比较哈希，只上传有变化的文件，有变化时返回新的清单。`manifest_path` 为 None 时全部上传。
+/// 原子部署的新版本是当前版本的硬链接副本，所以写入前先删除旧文件，不能直接覆盖
+fn sftp_sync(
+    session: &Session,
+    dest: &str,
+    manifest_path: Option<&str>,
+    files: &[(PathBuf, String)],
+    delete_removed: bool,
+    progress: &Progress<'_>,
+) -> Result<(bool, Option<BTreeMap<String, String>>)> {
+    let sftp = session.sftp()?;
+    let previous: BTreeMap<String, String> = manifest_path
+        .and_then(|path| read_remote(&sftp, path))
+        .and_then(|data| serde_json::from_slice(&data).ok())
+        .unwrap_or_default();
+
+    let mut manifest = BTreeMap::new();
+    let mut uploads = Vec::new();
+    for (file, relative) in files {
+        let hash = format!("{:x}", Sha256::digest(fs::read(file)?));
+        if previous.get(relative) != Some(&hash) {
+            uploads.push((file, relative));
+        }
+        manifest.insert(relative.clone(), hash);
+    }
+    let removed: Vec<&String> = previous.keys().filter(|key| !manifest.contains_key(*key)).collect();
+    if uploads.is_empty() && (removed.is_empty() || !delete_removed) {
+        return Ok((false, None));
+    }
+
+    let mut created = HashSet::new();
+    for (index, (file, relative)) in uploads.iter().enumerate() {
+        progress.report(DeployPhase::Upload, index, uploads.len(), Some(relative));
+        create_parents(&sftp, dest, relative, &mut created)?;
+        write_remote(&sftp, &format!("{}/{}", dest, relative), &fs::read(file)?)?;
+    }
+    progress.report(DeployPhase::Upload, uploads.len(), uploads.len(), None);
+
+    if delete_removed {
+        for (index, relative) in removed.iter().enumerate() {
+            progress.report(DeployPhase::Delete, index, removed.len(), Some(relative));
+            let _ = sftp.unlink(Path::new(&format!("{}/{}", dest, relative)));
+            remove_empty_parents(&sftp, dest, relative);
+        }
+    }
+
+    Ok((true, Some(manifest)))
+}
+
+/// 清单的远程路径，相对路径相对于用户主目录
+fn manifest_path(base: &str, atomic: bool) -> String {
+    if atomic {
+        format!("{}/{}", base, MANIFEST_FILE)
+    } else {
+        let key = format!("{:x}", Sha256::digest(base.as_bytes()));
+        format!("{}/{}.json", REMOTE_MANIFEST_DIR, &key[..16])
+    }
+}
+
+fn write_manifest(session: &Session, path: &str, manifest: &BTreeMap<String, String>) -> Result<()> {
+    let sftp = session.sftp()?;
+    if !path.starts_with('/') {
+        create_parents(&sftp, ".", path, &mut HashSet::new())?;
+    }
+    write_remote(&sftp, path, &serde_json::to_vec_pretty(manifest)?)
+}
+
+fn read_remote(sftp: &Sftp, path: &str) -> Option<Vec<u8>> {
+    let mut file = sftp.open(Path::new(path)).ok()?;
+    let mut data = Vec::new();
+    file.read_to_end(&mut data).ok()?;
+    Some(data)
+}
+
+fn write_remote(sftp: &Sftp, path: &str, data: &[u8]) -> Result<()> {
+    let path = Path::new(path);
+    let _ = sftp.unlink(path);
+    let mut file = sftp.create(path)?;
+    file.write_all(data)?;
+    Ok(())
+}
+
+fn create_parents(sftp: &Sftp, dest: &str, relative: &str, created: &mut HashSet<String>) -> Result<()> {
+    let mut dir = dest.to_string();
+    let Some((parents, _)) = relative.rsplit_once('/') else {
+        return Ok(());
+    };
+    for part in parents.split('/') {
+        dir = format!("{}/{}", dir, part);
+        if created.contains(&dir) {
+            continue;
+        }
+        if sftp.stat(Path::new(&dir)).is_err() {
+            sftp.mkdir(Path::new(&dir), 0o755)?;
+        }
+        created.insert(dir.clone());
+    }
+    Ok(())
+}
+
+/// 删除文件后留下的空目录；目录不为空时 `rmdir` 会失败，正好停下
+fn remove_empty_parents(sftp: &Sftp, dest: &str, relative: &str) {
+    let mut parents = relative;
+    while let Some((parent, _)) = parents.rsplit_once('/') {
+        if sftp.rmdir(Path::new(&format!("{}/{}", dest, parent))).is_err() {
+            break;
+        }
+        parents = parent;
+    }
+}
+
+/// rsync 用 `--checksum` 比较内容，构建后修改时间都变了也只传输有变化的文件；
+/// 原子部署时用 `--link-dest` 把没有变化的文件硬链接到上一个版本
+fn rsync(
+    options: &SshOptions,
+    output_dir: &Path,
+    dest: &str,
+    link_dest: Option<&str>,
+    delete_removed: bool,
+    total: usize,
+    progress: &Progress<'_>,
+) -> Result<bool> {
+    let ssh = std::iter::once("ssh".to_string())
+        .chain(ssh_args(options).into_iter().map(|arg| quote(&arg)))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let mut command = Command::new("rsync");
+    command
+        .arg("-rlz")
+        .arg("--checksum")
+        .arg("--out-format=%i %n")
+        .arg("-e")
+        .arg(ssh);
+    if delete_removed {
+        command.arg("--delete");
+    }
+    if let Some(previous) = link_dest {
+        command.arg(format!("--link-dest=../{}", previous));
+    }
+    let mut source = output_dir.as_os_str().to_owned();
+    source.push("/");
+    command
+        .arg(source)
+        .arg(format!("{}@{}:{}/", options.user, options.host, dest))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    let mut child = command
+        .spawn()
+        .map_err(|e| AppError::DeployFailed(format!("rsync: {}", e)))?;
+    // 在另一个线程中读取 stderr，避免管道写满后 rsync 阻塞、这里又在等 stdout
+    let stderr = child.stderr.take().map(|mut pipe| {
+        std::thread::spawn(move || {
+            let mut stderr = String::new();
+            let _ = pipe.read_to_string(&mut stderr);
+            stderr
+        })
+    });
+    let mut changed = false;
+    let mut uploaded = 0;
+    if let Some(stdout) = child.stdout.take() {
+        for line in BufReader::new(stdout).lines() {
+            let line = line?;
+            let Some((item, name)) = line.split_once(' ') else {
+                continue;
+            };
+            if item == "*deleting" {
+                changed = true;
+                progress.report(DeployPhase::Delete, 0, 0, Some(name.trim()));
+            } else if item.as_bytes().get(1) == Some(&b'f') {
+                changed = true;
+                progress.report(DeployPhase::Upload, uploaded, total, Some(name));
+                uploaded += 1;
+            }
+        }
+    }
+
+    let stderr = stderr.and_then(|reader| reader.join().ok()).unwrap_or_default();
+    if !child.wait()?.success() {
+        return Err(AppError::DeployFailed(format!("rsync: {}", stderr.trim())));
+    }
+    progress.report(DeployPhase::Upload, total, total, None);
+    Ok(changed)
+}
+
+fn ssh_args(options: &SshOptions) -> Vec<String> {
+    let mut args = vec![
+        "-p".to_string(),
+        options.port.to_string(),
+        "-o".to_string(),
+        "BatchMode=yes".to_string(),
+    ];
+    if options.known_hosts.as_deref().is_some_and(|k| !k.trim().is_empty()) {
+        args.push("-o".to_string());
+        args.push(format!("UserKnownHostsFile={}", known_hosts_file(options).display()));
+    }
+    if let Some(key) = options.key_path.as_deref().filter(|k| !k.trim().is_empty()) {
+        args.push("-i".to_string());
+        args.push(expand_home(key).to_string_lossy().into_owned());
+    }
+    args
+}
+
+fn known_hosts_file(options: &SshOptions) -> PathBuf {
+    let file = options.known_hosts.as_deref().map(str::trim).filter(|k| !k.is_empty());
+    expand_home(file.unwrap_or("~/.ssh/known_hosts"))
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"));
+    match (path.strip_prefix("~/"), home) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// 远程命令中的路径统一用单引号括起来
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::process::Child;
+    use std::time::{Duration, Instant};
+
+    /// 只监听 127.0.0.1 的临时 sshd，使用新生成的主机密钥和客户端密钥。没有安装 sshd 时返回 None
+    struct Sshd {
+        dir: tempfile::TempDir,
+        port: u16,
+        user: String,
+        child: Child,
+    }
+
+    impl Sshd {
+        fn start() -> Option<Self> {
+            let sshd = ["/usr/sbin/sshd", "/usr/bin/sshd", "/usr/local/sbin/sshd"]
+                .into_iter()
+                .find(|path| Path::new(path).is_file())?;
+            let dir = tempfile::tempdir().unwrap();
+            let root = dir.path();
+            for key in ["host_key", "client_key"] {
+                let status = Command::new("ssh-keygen")
+                    .args(["-q", "-t", "ecdsa", "-m", "PEM", "-N", "", "-f"])
+                    .arg(root.join(key))
+                    .status()
+                    .ok()?;
+                assert!(status.success());
+            }
+            fs::copy(root.join("client_key.pub"), root.join("authorized_keys")).unwrap();
+            let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+            let host_key = fs::read_to_string(root.join("host_key.pub")).unwrap();
+            let host_key: Vec<&str> = host_key.split_whitespace().take(2).collect();
+            fs::write(
+                root.join("known_hosts"),
+                format!("[127.0.0.1]:{} {}\n", port, host_key.join(" ")),
+            )
+            .unwrap();
+            fs::write(
+                root.join("sshd_config"),
+                format!(
+                    "Port {port}\nListenAddress 127.0.0.1\nHostKey {root}/host_key\n\
+                     AuthorizedKeysFile {root}/authorized_keys\nPidFile {root}/sshd.pid\n\
+                     StrictModes no\nUsePAM no\nPasswordAuthentication no\nPermitRootLogin yes\n\
+                     Subsystem sftp internal-sftp\n",
+                    port = port,
+                    root = root.display()
+                ),
+            )
+            .unwrap();
+
+            let child = Command::new(sshd)
+                .args(["-D", "-e", "-f"])
+                .arg(root.join("sshd_config"))
+                .stderr(Stdio::null())
+                .spawn()
+                .ok()?;
+            let output = Command::new("id").arg("-un").output().unwrap();
+            let user = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            let server = Self { dir, port, user, child };
+            let deadline = Instant::now() + Duration::from_secs(10);
+            while TcpStream::connect(("127.0.0.1", port)).is_err() {
+                assert!(Instant::now() < deadline, "sshd did not start");
+                std::thread::sleep(Duration::from_millis(50));
+            }
+            Some(server)
+        }
+
+        fn options(&self, method: TransferMethod) -> SshOptions {
+            SshOptions {
+                host: "127.0.0.1".to_string(),
+                port: self.port,
+                user: self.user.clone(),
+                key_path: Some(self.path("client_key").display().to_string()),
+                remote_path: self.path("www").display().to_string(),
+                method,
+                known_hosts: Some(self.path("known_hosts").display().to_string()),
+                ..Default::default()
+            }
+        }
+
+        fn path(&self, name: &str) -> PathBuf {
+            self.dir.path().join(name)
+        }
+    }
+
+    impl Drop for Sshd {
+        fn drop(&mut self) {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+    }
+
+    fn has_rsync() -> bool {
+        Command::new("rsync").arg("--version").output().is_ok()
+    }
+
+    fn write(path: &Path, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn run(output: &Path, options: &SshOptions) -> DeployOutcome {
+        let callback = |_| {};
+        deploy("vps", output, options, None, &Progress::new("vps", &callback)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn unknown_host_is_rejected() {
+        let Some(sshd) = Sshd::start() else {
+            eprintln!("sshd not installed, skipping");
+            return;
+        };
+        let output = tempfile::tempdir().unwrap();
+        write(&output.path().join("index.html"), "home");
+        fs::write(sshd.path("known_hosts"), "").unwrap();
+        let target = SshTarget::new("vps".to_string(), sshd.options(TransferMethod::Sftp), None);
+        let callback = |_| {};
+        let error = target
+            .deploy(output.path(), &Progress::new("vps", &callback))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("Unknown host"));
+    }
+
+    #[test]
+    fn syncs_changed_files() {
+        let Some(sshd) = Sshd::start() else {
+            eprintln!("sshd not installed, skipping");
+            return;
+        };
+        let mut methods = vec![TransferMethod::Sftp];
+        if has_rsync() {
+            methods.push(TransferMethod::Rsync);
+        }
+        for method in methods {
+            let _ = fs::remove_dir_all(sshd.path("www"));
+            fs::create_dir_all(sshd.path("www")).unwrap();
+            let output = tempfile::tempdir().unwrap();
+            let output = output.path();
+            write(&output.join("index.html"), "home");
+            write(&output.join("posts/old/index.html"), "old");
+            let mut options = sshd.options(method);
+            options.delete_removed = true;
+
+            let first = run(output, &options);
+            assert!(first.changed);
+            assert_eq!(fs::read_to_string(sshd.path("www/posts/old/index.html")).unwrap(), "old");
+            // 清单不在站点根目录中
+            assert!(!sshd.path("www").join(MANIFEST_FILE).exists());
+
+            assert!(!run(output, &options).changed);
+
+            write(&output.join("index.html"), "new home");
+            fs::remove_dir_all(output.join("posts/old")).unwrap();
+            assert!(run(output, &options).changed);
+            assert_eq!(fs::read_to_string(sshd.path("www/index.html")).unwrap(), "new home");
+            assert!(!sshd.path("www/posts/old/index.html").exists());
+        }
+        let base = sshd.options(TransferMethod::Sftp).remote_path;
+        let _ = fs::remove_file(expand_home(&format!("~/{}", manifest_path(&base, false))));
+    }
+
+    #[test]
+    fn manifest_stays_outside_the_web_root() {
+        assert_eq!(manifest_path("/var/www/site", true), "/var/www/site/.pineapple-manifest.json");
+        let path = manifest_path("/var/www/site", false);
+        assert!(path.starts_with(".pineapple/manifests/") && path.ends_with(".json"), "{}", path);
+        assert_ne!(path, manifest_path("/var/www/other", false));
+    }
+
+    #[test]
+    fn exec_reads_stdout_and_stderr_together() {
+        let Some(sshd) = Sshd::start() else {
+            eprintln!("sshd not installed, skipping");
+            return;
+        };
+        let options = sshd.options(TransferMethod::Sftp);
+        let shell = RemoteShell::Session(connect(&options, None).unwrap());
+        // 两个输出都远大于 SSH 通道窗口，依次读取会卡住
+        let stdout = shell
+            .exec("head -c 3000000 /dev/zero | tr '\\0' e >&2; head -c 3000000 /dev/zero | tr '\\0' o")
+            .unwrap();
+        assert_eq!(stdout.len(), 3_000_000);
+        let error = shell.exec("echo out; echo broken >&2; exit 2").unwrap_err();
+        assert!(error.to_string().ends_with("broken"), "{}", error);
+    }
+
+    #[test]
+    fn atomic_releases_switch_and_prune() {
+        let Some(sshd) = Sshd::start() else {
+            eprintln!("sshd not installed, skipping");
+            return;
+        };
+        let output = tempfile::tempdir().unwrap();
+        let output = output.path();
+        write(&output.join("index.html"), "v0");
+        let mut options = sshd.options(TransferMethod::Sftp);
+        options.atomic = true;
+        options.keep_releases = 2;
+
+        let mut revisions = Vec::new();
+        for version in 1..=3 {
+            write(&output.join("index.html"), &format!("v{}", version));
+            let outcome = run(output, &options);
+            assert!(outcome.changed);
+            revisions.push(outcome.revision.unwrap());
+            assert_eq!(
+                fs::read_to_string(sshd.path("www/current/index.html")).unwrap(),
+                format!("v{}", version)
+            );
+        }
+
+        // 没有变化时删除新建的版本目录，current 保持不变
+        let unchanged = run(output, &options);
+        assert!(!unchanged.changed);
+        assert_eq!(unchanged.revision.as_ref(), revisions.last());
+
+        let mut releases: Vec<String> = fs::read_dir(sshd.path("www/releases"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        releases.sort();
+        assert_eq!(releases, revisions[1..]);
+        let current = fs::read_link(sshd.path("www/current")).unwrap();
+        assert_eq!(current, Path::new("releases").join(&revisions[2]));
+        assert!(sshd.path("www").join(MANIFEST_FILE).is_file());
+        assert!(!sshd.path("www/current").join(MANIFEST_FILE).exists());
+    }
+}
@@ -0,0 +1,27 @@
+use async_trait::async_trait;
+use std::path::Path;
+use std::time::Duration;
+use tokio::time::Instant;
+use crate::deploy::{DeployOutcome, DeployState, DeployStatus, Progress};
+use crate::error::Result;
+
+#[async_trait]
+pub trait DeployTarget: Send + Sync {
+    fn name(&self) -> &str;
+    async fn deploy(&self, output_dir: &Path, progress: &Progress<'_>) -> Result<DeployOutcome>;
+    /// 查询一次部署的状态；部署完成时返回站点地址
+    async fn status(&self, revision: &str) -> Result<DeployStatus>;
+
+    /// 每隔 `interval` 查询一次状态，直到部署完成或失败；超过 `timeout` 时返回最后一次查询到的状态
+    async fn wait_until_ready(&self, revision: &str, interval: Duration, timeout: Duration) -> Result<DeployStatus> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let status = self.status(revision).await?;
+            let finished = matches!(status.state, DeployState::Ready | DeployState::Failed);
+            if finished || Instant::now() + interval > deadline {
+                return Ok(status);
+            }
+            tokio::time::sleep(interval).await;
+        }
+    }
+}
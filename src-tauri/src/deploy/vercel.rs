@@ -0,0 +1,320 @@
+use async_trait::async_trait;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use chrono::Utc;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha1::{Digest, Sha1};
+use crate::deploy::http::{uri_encode, ApiClient};
+use crate::deploy::{output_files, DeployOutcome, DeployPhase, DeployState, DeployStatus, DeployTarget, Progress};
+use crate::error::{AppError, Result};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VercelOptions {
+    /// 项目名称，不存在时由 Vercel 创建
+    pub project: String,
+    pub team_id: Option<String>,
+    /// 发布到正式地址；为 false 时是预览部署
+    pub production: bool,
+    pub api_url: String,
+}
+
+impl Default for VercelOptions {
+    fn default() -> Self {
+        Self {
+            project: String::new(),
+            team_id: None,
+            production: true,
+            api_url: "https://api.vercel.com".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VercelDeployment {
+    id: String,
+    url: Option<String>,
+    ready_state: Option<String>,
+    #[serde(default)]
+    alias: Vec<String>,
+    error_message: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct VercelErrorBody {
+    error: VercelError,
+}
+
+#[derive(Deserialize)]
+struct VercelError {
+    code: Option<String>,
+    /// `missing_files` 错误中服务端还没有的文件摘要
+    #[serde(default)]
+    missing: Vec<String>,
+}
+
+/// 按文件摘要创建部署；服务端缺少文件时先上传缺少的文件再重新创建
+pub struct VercelTarget {
+    name: String,
+    options: VercelOptions,
+    api: ApiClient,
+}
+
+impl VercelTarget {
+    pub fn new(name: String, options: VercelOptions, token: &str) -> Result<Self> {
+        let api = ApiClient::new(&options.api_url, token)?;
+        Ok(Self { name, options, api })
+    }
+
+    /// 团队项目的请求都要带上 `teamId`
+    fn path(&self, path: &str) -> String {
+        match self.options.team_id.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+            Some(team) => format!("{}?teamId={}", path, uri_encode(team, true)),
+            None => path.to_string(),
+        }
+    }
+
+    fn status_of(&self, deployment: VercelDeployment) -> DeployStatus {
+        let state = match deployment.ready_state.as_deref() {
+            Some("READY") => DeployState::Ready,
+            Some("ERROR") | Some("CANCELED") => DeployState::Failed,
+            Some("BUILDING") | Some("INITIALIZING") => DeployState::Processing,
+            _ => DeployState::Pending,
+        };
+        let host = match deployment.alias.first() {
+            Some(alias) if self.options.production => Some(alias.clone()),
+            _ => deployment.url,
+        };
+        DeployStatus {
+            revision: deployment.id,
+            state,
+            url: host.map(|host| format!("https://{}", host)),
+            error: deployment.error_message,
+        }
+    }
+
+    async fn upload(&self, file: &Path, digest: &str) -> Result<()> {
+        let data = fs::read(file)?;
+        let request = self
+            .api
+            .request(Method::POST, &self.path("/v2/files"))
+            .header(CONTENT_TYPE, "application/octet-stream")
+            .header("x-vercel-digest", digest)
+            .body(data);
+        self.api.send::<Value>(request).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl DeployTarget for VercelTarget {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn deploy(&self, output_dir: &Path, progress: &Progress<'_>) -> Result<DeployOutcome> {
+        if self.options.project.trim().is_empty() {
+            return Err(AppError::DeployFailed("Project is empty".to_string()));
+        }
+        let files = output_files(output_dir);
+        let mut manifest = Vec::with_capacity(files.len());
+        let mut by_digest: BTreeMap<String, PathBuf> = BTreeMap::new();
+        for (file, relative) in &files {
+            let data = fs::read(file)?;
+            let digest = format!("{:x}", Sha1::digest(&data));
+            manifest.push(json!({ "file": relative, "sha": digest, "size": data.len() }));
+            by_digest.entry(digest).or_insert_with(|| file.clone());
+        }
+        let mut body = json!({
+            "name": self.options.project.trim(),
+            "files": manifest,
+            "projectSettings": { "framework": null },
+        });
+        if self.options.production {
+            body["target"] = json!("production");
+        }
+
+        let mut uploaded = HashSet::new();
+        let deployment = loop {
+            let request = self.api.request(Method::POST, &self.path("/v13/deployments")).json(&body);
+            let (status, text) = self.api.send_raw(request).await?;
+            if status.is_success() {
+                break serde_json::from_str::<VercelDeployment>(&text)
+                    .map_err(|e| AppError::ParseError(format!("{}: {}", e, text.trim())))?;
+            }
+
+            let missing = match serde_json::from_str::<VercelErrorBody>(&text) {
+                Ok(VercelErrorBody { error })
+                    if status == StatusCode::BAD_REQUEST && error.code.as_deref() == Some("missing_files") =>
+                {
+                    error.missing
+                }
+                _ => return Err(AppError::DeployFailed(format!("{}: {}", status, text.trim()))),
+            };
+            // 已经上传过还说缺少，说明服务端没有接受，避免无限重试
+            if missing.is_empty() || missing.iter().any(|digest| uploaded.contains(digest)) {
+                return Err(AppError::DeployFailed(format!("{}: {}", status, text.trim())));
+            }
+            for (index, digest) in missing.iter().enumerate() {
+                let file = by_digest
+                    .get(digest)
+                    .ok_or_else(|| AppError::DeployFailed(format!("Unknown file digest: {}", digest)))?;
+                let relative = file.strip_prefix(output_dir).unwrap_or(file).to_string_lossy().replace('\\', "/");
+                progress.report(DeployPhase::Upload, index, missing.len(), Some(&relative));
+                self.upload(file, digest).await?;
+                uploaded.insert(digest.clone());
+            }
+            progress.report(DeployPhase::Upload, missing.len(), missing.len(), None);
+        };
+
+        let status = self.status_of(deployment);
+        Ok(DeployOutcome {
+            target: self.name.clone(),
+            files: files.len(),
+            changed: !uploaded.is_empty(),
+            revision: Some(status.revision),
+            state: status.state,
+            url: status.url,
+            finished_at: Utc::now(),
+            checks: None,
+        })
+    }
+
+    async fn status(&self, revision: &str) -> Result<DeployStatus> {
+        let deployment: VercelDeployment = self
+            .api
+            .get(&self.path(&format!("/v13/deployments/{}", uri_encode(revision, true))))
+            .await?;
+        Ok(self.status_of(deployment))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{MockRequest, MockResponse, MockServer};
+    use std::sync::Mutex;
+
+    fn sha1(data: &str) -> String {
+        format!("{:x}", Sha1::digest(data.as_bytes()))
+    }
+
+    fn target(server: &MockServer, production: bool) -> VercelTarget {
+        let options = VercelOptions {
+            project: "blog".to_string(),
+            team_id: Some("team 1".to_string()),
+            production,
+            api_url: server.url().to_string(),
+        };
+        VercelTarget::new("vercel".to_string(), options, "secret").unwrap()
+    }
+
+    fn missing(digests: &[String]) -> MockResponse {
+        MockResponse::status(400)
+            .body(json!({ "error": { "code": "missing_files", "missing": digests } }).to_string())
+    }
+
+    #[tokio::test]
+    async fn uploads_missing_files_and_retries() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path();
+        fs::write(output.join("index.html"), "home").unwrap();
+        fs::write(output.join("about.html"), "about").unwrap();
+
+        let uploaded: Mutex<Vec<String>> = Mutex::new(Vec::new());
+        let server = MockServer::start(move |request: &MockRequest| {
+            assert_eq!(request.header("authorization"), Some("Bearer secret"));
+            match (request.method.as_str(), request.path.as_str()) {
+                ("POST", "/v13/deployments?teamId=team%201") => {
+                    if uploaded.lock().unwrap().is_empty() {
+                        return missing(&[sha1("about")]);
+                    }
+                    MockResponse::status(200).body(
+                        json!({
+                            "id": "dpl_1",
+                            "url": "blog-abc.vercel.app",
+                            "readyState": "QUEUED",
+                            "alias": ["blog.example.com"],
+                        })
+                        .to_string(),
+                    )
+                }
+                ("POST", "/v2/files?teamId=team%201") => {
+                    uploaded.lock().unwrap().push(request.header("x-vercel-digest").unwrap().to_string());
+                    MockResponse::status(200).body("{}")
+                }
+                _ => MockResponse::status(404),
+            }
+        })
+        .await;
+
+        let callback = |_| {};
+        let outcome = target(&server, true)
+            .deploy(output, &Progress::new("vercel", &callback))
+            .await
+            .unwrap();
+        assert!(outcome.changed);
+        assert_eq!(outcome.revision.as_deref(), Some("dpl_1"));
+        assert_eq!(outcome.state, DeployState::Pending);
+        assert_eq!(outcome.url.as_deref(), Some("https://blog.example.com"));
+
+        let requests = server.requests();
+        let paths: Vec<_> = requests.iter().map(|r| r.path.split('?').next().unwrap()).collect();
+        assert_eq!(paths, ["/v13/deployments", "/v2/files", "/v13/deployments"]);
+        assert_eq!(requests[1].header("x-vercel-digest"), Some(sha1("about").as_str()));
+        assert_eq!(requests[1].body, b"about");
+        let created: Value = serde_json::from_slice(&requests[2].body).unwrap();
+        assert_eq!(created["target"], "production");
+        assert_eq!(created["files"].as_array().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn stops_when_uploaded_files_are_still_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("index.html"), "home").unwrap();
+        let server = MockServer::start(|request: &MockRequest| match request.path.split('?').next() {
+            Some("/v13/deployments") => missing(&[sha1("home")]),
+            _ => MockResponse::status(200).body("{}"),
+        })
+        .await;
+
+        let callback = |_| {};
+        let error = target(&server, false)
+            .deploy(dir.path(), &Progress::new("vercel", &callback))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("missing_files"));
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn maps_ready_states() {
+        let server = MockServer::start(|request: &MockRequest| {
+            let state = request.path.split('/').nth(3).unwrap().split('?').next().unwrap().to_string();
+            MockResponse::status(200).body(
+                json!({ "id": state, "url": "blog-abc.vercel.app", "readyState": state, "alias": ["blog.example.com"] })
+                    .to_string(),
+            )
+        })
+        .await;
+        let target = target(&server, false);
+        for (state, expected) in [
+            ("QUEUED", DeployState::Pending),
+            ("INITIALIZING", DeployState::Processing),
+            ("BUILDING", DeployState::Processing),
+            ("READY", DeployState::Ready),
+            ("ERROR", DeployState::Failed),
+            ("CANCELED", DeployState::Failed),
+        ] {
+            let status = target.status(state).await.unwrap();
+            assert_eq!(status.state, expected, "{}", state);
+            // 预览部署使用部署自己的地址
+            assert_eq!(status.url.as_deref(), Some("https://blog-abc.vercel.app"));
+        }
+    }
+}
@@ -1,10 +1,17 @@
 use async_trait::async_trait;
 use std::path::{Path, PathBuf};
-use std::time::Instant;
+use std::time::{Duration, Instant};
+use tokio::process::Child;
 use crate::engine::traits::SiteEngine;
 use crate::error::{AppError, Result};
-use crate::models::{BuildOptions, BuildResult, EngineType};
-use crate::utils::{get_hugo_binary_path, execute_command};
+use crate::models::{BuildOptions, BuildResult, EngineType, ServeOptions};
+use crate::preview::lan::{base_url_for, link_address, probe_address};
+use crate::preview::localhost;
+use crate::utils::{get_hugo_binary_path, execute_command, spawn_server};
+
+const SERVER_START_TIMEOUT: Duration = Duration::from_secs(30);
+/// Printed once the server is listening
+const READY_LINE: &str = "Web Server is available at";
 
 pub struct HugoAdapter {
     binary_path: PathBuf,
@@ -42,18 +49,44 @@ impl SiteEngine for HugoAdapter {
 
     async fn build(&self, path: &Path, options: &BuildOptions) -> Result<BuildResult> {
         let start = Instant::now();
-        let mut args = vec![];
+        let mut args: Vec<String> = vec![];
 
         if options.minify {
-            args.push("--minify");
+            args.push("--minify".to_string());
         }
         if options.clean {
-            args.push("--gc");
+            args.push("--gc".to_string());
         }
         if options.draft {
-            args.push("-D");
+            args.push("-D".to_string());
+        }
+        if let Some(environment) = &options.environment {
+            args.push("--environment".to_string());
+            args.push(environment.clone());
+        }
+        if let Some(base_url) = &options.base_url {
+            args.push("--baseURL".to_string());
+            args.push(base_url.clone());
+        }
+        if options.build_future {
+            args.push("--buildFuture".to_string());
+        }
+        if options.build_expired {
+            args.push("--buildExpired".to_string());
+        }
+        if let Some(destination) = &options.destination {
+            args.push("--destination".to_string());
+            args.push(destination.clone());
+        }
+        if let Some(cache_dir) = &options.cache_dir {
+            args.push("--cacheDir".to_string());
+            args.push(cache_dir.clone());
+        }
+        if options.template_metrics {
+            args.push("--templateMetrics".to_string());
         }
 
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
         let output = execute_command(&self.binary_path, &args, path).await?;
 
         let duration = start.elapsed().as_secs_f64();
@@ -73,24 +106,36 @@ impl SiteEngine for HugoAdapter {
         })
     }
 
-    async fn serve(&self, path: &Path, port: u16) -> Result<()> {
-        use std::process::Command;
-
-        Command::new(&self.binary_path)
-            .args(&[
-                "server",
-                "-D",
-                "--bind",
-                "127.0.0.1",
-                "--port",
-                &port.to_string(),
-                "--navigateToChanged",
-            ])
-            .current_dir(path)
-            .spawn()
-            .map_err(|e| AppError::EngineError(e.to_string()))?;
+    async fn serve(&self, path: &Path, options: &ServeOptions) -> Result<Child> {
+        let bind = options.lan_address.unwrap_or_else(localhost);
+        let bind_str = bind.to_string();
+        let port_str = options.port.to_string();
+        let mut args = vec![
+            "server",
+            "-D",
+            "--bind",
+            &bind_str,
+            "--port",
+            &port_str,
+            "--navigateToChanged",
+        ];
+
+        // 局域网预览时让链接指向本机的局域网地址，其他设备才能正常跳转
+        let base_url;
+        if options.lan_address.is_some() {
+            base_url = base_url_for(link_address(bind, options.advertise_address)?);
+            args.extend(["--baseURL", &base_url, "--appendPort"]);
+        }
 
-        Ok(())
+        spawn_server(
+            &self.binary_path,
+            &args,
+            path,
+            probe_address(bind, options.port),
+            READY_LINE,
+            SERVER_START_TIMEOUT,
+        )
+        .await
     }
 
     async fn new_post(&self, path: &Path, title: &str) -> Result<String> {
@@ -1,10 +1,17 @@
 use async_trait::async_trait;
 use std::path::{Path, PathBuf};
-use std::time::Instant;
+use std::time::{Duration, Instant};
+use tokio::process::Child;
 use crate::engine::traits::SiteEngine;
 use crate::error::{AppError, Result};
-use crate::models::{BuildOptions, BuildResult, EngineType};
-use crate::utils::{get_zola_binary_path, execute_command};
+use crate::models::{BuildOptions, BuildResult, EngineType, ServeOptions};
+use crate::preview::lan::{link_address, probe_address, url_host};
+use crate::preview::localhost;
+use crate::utils::{get_zola_binary_path, execute_command, spawn_server};
+
+const SERVER_START_TIMEOUT: Duration = Duration::from_secs(30);
+/// Printed once the server is listening
+const READY_LINE: &str = "Web server is available at";
 
 pub struct ZolaAdapter {
     binary_path: PathBuf,
@@ -24,8 +31,6 @@ impl SiteEngine for ZolaAdapter {
     }
 
     async fn init(&self, path: &Path, site_name: &str) -> Result<()> {
-        let target_path = path.join(site_name);
-        
         // Use --force to skip confirmation prompt
         let output = execute_command(
             &self.binary_path,
@@ -45,12 +50,32 @@ impl SiteEngine for ZolaAdapter {
 
     async fn build(&self, path: &Path, options: &BuildOptions) -> Result<BuildResult> {
         let start = Instant::now();
-        let mut args = vec!["build"];
+
+        let unsupported = unsupported_options(options);
+        if !unsupported.is_empty() {
+            return Err(AppError::UnsupportedOption(format!(
+                "Zola does not support: {}",
+                unsupported.join(", ")
+            )));
+        }
+
+        // `clean` needs no flag: Zola always empties the output directory
+        let mut args: Vec<String> = vec!["build".to_string()];
 
         if options.draft {
-            args.push("--drafts");
+            args.push("--drafts".to_string());
+        }
+        if let Some(base_url) = &options.base_url {
+            args.push("--base-url".to_string());
+            args.push(base_url.clone());
+        }
+        if let Some(destination) = &options.destination {
+            args.push("--output-dir".to_string());
+            args.push(destination.clone());
+            args.push("--force".to_string());
         }
 
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
         let output = execute_command(&self.binary_path, &args, path).await?;
 
         let duration = start.elapsed().as_secs_f64();
@@ -70,23 +95,35 @@ impl SiteEngine for ZolaAdapter {
         })
     }
 
-    async fn serve(&self, path: &Path, port: u16) -> Result<()> {
-        use std::process::Command;
-
-        Command::new(&self.binary_path)
-            .args(&[
-                "serve",
-                "--interface",
-                "127.0.0.1",
-                "--port",
-                &port.to_string(),
-                "--drafts",
-            ])
-            .current_dir(path)
-            .spawn()
-            .map_err(|e| AppError::EngineError(e.to_string()))?;
+    async fn serve(&self, path: &Path, options: &ServeOptions) -> Result<Child> {
+        let bind = options.lan_address.unwrap_or_else(localhost);
+        let bind_str = bind.to_string();
+        let port_str = options.port.to_string();
+        let mut args = vec![
+            "serve",
+            "--interface",
+            &bind_str,
+            "--port",
+            &port_str,
+            "--drafts",
+        ];
+
+        // Zola appends the port to --base-url by itself
+        let base_url;
+        if options.lan_address.is_some() {
+            base_url = url_host(link_address(bind, options.advertise_address)?);
+            args.extend(["--base-url", &base_url]);
+        }
 
-        Ok(())
+        spawn_server(
+            &self.binary_path,
+            &args,
+            path,
+            probe_address(bind, options.port),
+            READY_LINE,
+            SERVER_START_TIMEOUT,
+        )
+        .await
     }
 
     async fn new_post(&self, path: &Path, title: &str) -> Result<String> {
@@ -129,3 +166,27 @@ Write your content here...
             .to_string())
     }
 }
+
+/// Zola has no CLI equivalent for these; refuse them instead of ignoring them
+pub(crate) fn unsupported_options(options: &BuildOptions) -> Vec<&'static str> {
+    let mut unsupported = vec![];
+    if options.minify {
+        unsupported.push("minify (set `minify_html = true` in config.toml)");
+    }
+    if options.environment.is_some() {
+        unsupported.push("environment");
+    }
+    if options.build_future {
+        unsupported.push("build_future");
+    }
+    if options.build_expired {
+        unsupported.push("build_expired");
+    }
+    if options.cache_dir.is_some() {
+        unsupported.push("cache_dir");
+    }
+    if options.template_metrics {
+        unsupported.push("template_metrics");
+    }
+    unsupported
+}
@@ -8,6 +8,9 @@ pub enum AppError {
     #[error("Path not found: {0}")]
     PathNotFound(String),
 
+    #[error("Not found: {0}")]
+    NotFound(String),
+
     #[error("Invalid path: {0}")]
     InvalidPath(String),
 
@@ -17,6 +20,15 @@ pub enum AppError {
     #[error("Engine error: {0}")]
     EngineError(String),
 
+    #[error("Unsupported option: {0}")]
+    UnsupportedOption(String),
+
+    #[error("No free port: {0}")]
+    PortUnavailable(String),
+
+    #[error("Server failed to start: {0}")]
+    ServerStartFailed(String),
+
     #[error("Build failed: {0}")]
     BuildFailed(String),
 
@@ -26,8 +38,56 @@ pub enum AppError {
     #[error("Serialization error: {0}")]
     SerializationError(#[from] serde_json::Error),
 
+    #[error("Parse error: {0}")]
+    ParseError(String),
+
     #[error("Database error: {0}")]
     DatabaseError(String),
+
+    #[error("Git error: {0}")]
+    GitError(String),
+
+    #[error("Credential error: {0}")]
+    CredentialError(String),
+
+    #[error("Deploy failed: {0}")]
+    DeployFailed(String),
+
+    #[error("Hook failed: {0}")]
+    HookFailed(String),
+
+    #[error("Archive error: {0}")]
+    ArchiveError(String),
+}
+
+impl From<rusqlite::Error> for AppError {
+    fn from(error: rusqlite::Error) -> Self {
+        AppError::DatabaseError(error.to_string())
+    }
+}
+
+impl From<git2::Error> for AppError {
+    fn from(error: git2::Error) -> Self {
+        AppError::GitError(error.message().to_string())
+    }
+}
+
+impl From<keyring::Error> for AppError {
+    fn from(error: keyring::Error) -> Self {
+        AppError::CredentialError(error.to_string())
+    }
+}
+
+impl From<ssh2::Error> for AppError {
+    fn from(error: ssh2::Error) -> Self {
+        AppError::DeployFailed(error.message().to_string())
+    }
+}
+
+impl From<zip::result::ZipError> for AppError {
+    fn from(error: zip::result::ZipError) -> Self {
+        AppError::ArchiveError(error.to_string())
+    }
 }
 
 pub type Result<T> = std::result::Result<T, AppError>;
@@ -0,0 +1,340 @@
+use serde_json::Value;
+use toml_edit::{Array, DocumentMut, Item};
+use crate::error::{AppError, Result};
+use crate::front_matter::{get_field, parse_front_matter, split_front_matter, FrontMatterFormat};
+
+/// 写入 Front Matter 的值
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    String(String),
+    Bool(bool),
+    List(Vec<String>),
+}
+
+/// 设置 Front Matter 中的字段（`path` 为嵌套键，例如 `["taxonomies", "tags"]`），
+/// 只改动该字段所在的行，其余内容、注释和顺序保持不变。没有 Front Matter 时新建 YAML 块
+pub fn set_field(content: &str, path: &[&str], value: &FieldValue) -> Result<String> {
+    let document = split_front_matter(content);
+    let Some(block) = document.front_matter else {
+        let mut raw = String::new();
+        yaml_insert(&mut raw, path, value);
+        return Ok(format!("---\n{}---\n{}", raw, content));
+    };
+
+    let raw = match block.format {
+        FrontMatterFormat::Yaml => yaml_set(block.raw, path, value),
+        FrontMatterFormat::Toml => toml_set(block.raw, path, value)?,
+        FrontMatterFormat::Json => json_set(block.raw, path, value)?,
+    };
+
+    let end = block.raw_offset + block.raw.len();
+    Ok(format!("{}{}{}", &content[..block.raw_offset], raw, &content[end..]))
+}
+
+/// 读取列表字段，修改后写回；字段不存在时视为空列表，单个字符串视为只有一项的列表
+pub fn update_list(content: &str, path: &[&str], edit: impl FnOnce(&mut Vec<String>)) -> Result<String> {
+    let mut items = read_list(content, path);
+    let original = items.clone();
+    edit(&mut items);
+    if items == original {
+        return Ok(content.to_string());
+    }
+    set_field(content, path, &FieldValue::List(items))
+}
+
+pub fn read_list(content: &str, path: &[&str]) -> Vec<String> {
+    let front_matter = split_front_matter(content)
+        .front_matter
+        .and_then(|block| parse_front_matter(&block).ok())
+        .unwrap_or(Value::Null);
+    let value = path.iter().try_fold(&front_matter, |value, key| get_field(value, key));
+
+    match value {
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|item| match item {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            })
+            .collect(),
+        Some(Value::String(s)) => vec![s.clone()],
+        _ => Vec::new(),
+    }
+}
+
+fn toml_set(raw: &str, path: &[&str], value: &FieldValue) -> Result<String> {
+    let mut doc: DocumentMut = raw.parse().map_err(|e: toml_edit::TomlError| AppError::ParseError(e.to_string()))?;
+    let Some((last, parents)) = path.split_last() else {
+        return Ok(raw.to_string());
+    };
+
+    let mut table = doc.as_table_mut() as &mut dyn toml_edit::TableLike;
+    for key in parents {
+        let key = existing_key(table.iter().map(|(k, _)| k), key);
+        if table.get(&key).and_then(Item::as_table_like).is_none() {
+            table.insert(&key, Item::Table(toml_edit::Table::new()));
+        }
+        table = table
+            .get_mut(&key)
+            .and_then(Item::as_table_like_mut)
+            .ok_or_else(|| AppError::ParseError(format!("{} is not a table", key)))?;
+    }
+
+    let key = existing_key(table.iter().map(|(k, _)| k), last);
+    let is_string = table.get(&key).and_then(Item::as_str).is_some();
+    match value {
+        FieldValue::String(s) => toml_set_scalar(table, &key, toml_edit::Value::from(s.as_str())),
+        FieldValue::Bool(b) => toml_set_scalar(table, &key, toml_edit::Value::from(*b)),
+        FieldValue::List(items) => match table.get_mut(&key).and_then(Item::as_array_mut) {
+            Some(array) => {
+                let trailing_comma = array.trailing_comma();
+                let multiline = array.iter().any(|v| v.decor().prefix().and_then(|p| p.as_str()).is_some_and(|p| p.contains('\n')));
+                let indent = array
+                    .iter()
+                    .next()
+                    .and_then(|v| v.decor().prefix().and_then(|p| p.as_str()).map(str::to_string));
+                array.clear();
+                for item in items {
+                    array.push(item.as_str());
+                }
+                if multiline {
+                    let prefix = indent.unwrap_or_else(|| "\n  ".to_string());
+                    for item in array.iter_mut() {
+                        item.decor_mut().set_prefix(prefix.clone());
+                    }
+                    array.set_trailing_comma(true);
+                    array.set_trailing("\n");
+                } else {
+                    array.set_trailing_comma(trailing_comma);
+                }
+            }
+            // `tags = "rust"` 这种单个字符串的写法，只剩一个词条时保持原样
+            None if items.len() == 1 && is_string => {
+                toml_set_scalar(table, &key, toml_edit::Value::from(items[0].as_str()));
+            }
+            None => {
+                let array: Array = items.iter().map(String::as_str).collect();
+                table.insert(&key, toml_edit::value(array));
+            }
+        },
+    }
+
+    Ok(doc.to_string())
+}
+
+/// 保留原值前后的空白和注释
+fn toml_set_scalar(table: &mut dyn toml_edit::TableLike, key: &str, value: toml_edit::Value) {
+    match table.get_mut(key).and_then(Item::as_value_mut) {
+        Some(existing) => {
+            let decor = existing.decor().clone();
+            *existing = value;
+            *existing.decor_mut() = decor;
+        }
+        None => {
+            table.insert(key, Item::Value(value));
+        }
+    }
+}
+
+/// Hugo 的键名不区分大小写，沿用文件中已有的写法
+fn existing_key<'a>(mut keys: impl Iterator<Item = &'a str>, key: &str) -> String {
+    keys.find(|k| k.eq_ignore_ascii_case(key))
+        .unwrap_or(key)
+        .to_string()
+}
+
+fn json_set(raw: &str, path: &[&str], value: &FieldValue) -> Result<String> {
+    let mut root: Value = serde_json::from_str(raw)?;
+    let Some((last, parents)) = path.split_last() else {
+        return Ok(raw.to_string());
+    };
+
+    let mut object = root
+        .as_object_mut()
+        .ok_or_else(|| AppError::ParseError("Front matter is not an object".to_string()))?;
+    for key in parents {
+        let key = existing_key(object.keys().map(String::as_str), key);
+        let child = object
+            .entry(key.clone())
+            .or_insert_with(|| Value::Object(Default::default()));
+        object = child
+            .as_object_mut()
+            .ok_or_else(|| AppError::ParseError(format!("{} is not an object", key)))?;
+    }
+
+    let key = existing_key(object.keys().map(String::as_str), last);
+    object.insert(
+        key,
+        match value {
+            FieldValue::String(s) => Value::String(s.clone()),
+            FieldValue::Bool(b) => Value::Bool(*b),
+            FieldValue::List(items) => Value::Array(items.iter().cloned().map(Value::String).collect()),
+        },
+    );
+
+    let mut json = serde_json::to_string_pretty(&root)?;
+    json.push('\n');
+    Ok(json)
+}
+
+/// YAML 按行编辑：找到键所在的行和它下面缩进更深的行，只替换这一段
+fn yaml_set(raw: &str, path: &[&str], value: &FieldValue) -> String {
+    let mut lines: Vec<String> = raw.lines().map(str::to_string).collect();
+    let mut range = 0..lines.len();
+    let mut indent = 0;
+
+    for (depth, key) in path.iter().enumerate() {
+        let Some(found) = yaml_find_key(&lines, range.clone(), indent, key) else {
+            // 从这一层开始的键都不存在，在当前范围末尾插入
+            let mut inserted = String::new();
+            yaml_insert_at(&mut inserted, &path[depth..], value, indent);
+            let at = yaml_trim_trailing_blank(&lines, range);
+            lines.splice(at..at, inserted.lines().map(str::to_string));
+            return join_lines(lines);
+        };
+
+        let block_end = yaml_block_end(&lines, found + 1, range.end, indent);
+        if depth + 1 == path.len() {
+            let key_line = &lines[found];
+            let key_text = key_line[indent..].split(':').next().unwrap_or(key).to_string();
+            let inline = key_line.split_once(':').map(|(_, v)| v.trim().to_string()).unwrap_or_default();
+            let item_indent = lines[found + 1..block_end]
+                .iter()
+                .find(|l| l.trim_start().starts_with("- "))
+                .map(|l| l.len() - l.trim_start().len())
+                .unwrap_or(indent + 2);
+
+            let mut replacement = Vec::new();
+            match value {
+                FieldValue::String(s) => {
+                    replacement.push(format!("{}{}: {}", " ".repeat(indent), key_text, yaml_scalar(s)));
+                }
+                FieldValue::Bool(b) => {
+                    replacement.push(format!("{}{}: {}", " ".repeat(indent), key_text, b));
+                }
+                FieldValue::List(items) if inline.starts_with('[') || (items.is_empty() && !inline.is_empty()) => {
+                    let items: Vec<String> = items.iter().map(|s| yaml_scalar(s)).collect();
+                    replacement.push(format!("{}{}: [{}]", " ".repeat(indent), key_text, items.join(", ")));
+                }
+                FieldValue::List(items) if items.is_empty() => {
+                    replacement.push(format!("{}{}: []", " ".repeat(indent), key_text));
+                }
+                // `tags: rust` 这种单个字符串的写法，只剩一个词条时保持原样
+                FieldValue::List(items) if items.len() == 1 && !inline.is_empty() && !inline.starts_with('#') => {
+                    replacement.push(format!("{}{}: {}", " ".repeat(indent), key_text, yaml_scalar(&items[0])));
+                }
+                FieldValue::List(items) => {
+                    replacement.push(format!("{}{}:", " ".repeat(indent), key_text));
+                    for item in items {
+                        replacement.push(format!("{}- {}", " ".repeat(item_indent), yaml_scalar(item)));
+                    }
+                }
+            }
+
+            let end = yaml_trim_trailing_blank(&lines, found + 1..block_end);
+            lines.splice(found..end, replacement);
+            return join_lines(lines);
+        }
+
+        let child_indent = lines[found + 1..block_end]
+            .iter()
+            .find(|l| !l.trim().is_empty())
+            .map(|l| l.len() - l.trim_start().len())
+            .unwrap_or(indent + 2);
+        range = found + 1..block_end;
+        indent = child_indent;
+    }
+
+    join_lines(lines)
+}
+
+fn yaml_find_key(lines: &[String], range: std::ops::Range<usize>, indent: usize, key: &str) -> Option<usize> {
+    range.into_iter().find(|&i| {
+        let line = &lines[i];
+        let current = line.len() - line.trim_start().len();
+        if current != indent {
+            return false;
+        }
+        let Some((name, _)) = line.trim_start().split_once(':') else {
+            return false;
+        };
+        name.trim().trim_matches(|c| c == '"' || c == '\'').eq_ignore_ascii_case(key)
+    })
+}
+
+/// 键的值所占的行：缩进更深的行、空行，以及与键同缩进的 `- ` 列表项
+fn yaml_block_end(lines: &[String], start: usize, end: usize, indent: usize) -> usize {
+    (start..end)
+        .find(|&i| {
+            let line = &lines[i];
+            if line.trim().is_empty() {
+                return false;
+            }
+            let current = line.len() - line.trim_start().len();
+            current < indent || (current == indent && !line.trim_start().starts_with("- "))
+        })
+        .unwrap_or(end)
+}
+
+fn yaml_trim_trailing_blank(lines: &[String], range: std::ops::Range<usize>) -> usize {
+    let mut end = range.end;
+    while end > range.start && lines[end - 1].trim().is_empty() {
+        end -= 1;
+    }
+    end
+}
+
+fn yaml_insert(out: &mut String, path: &[&str], value: &FieldValue) {
+    yaml_insert_at(out, path, value, 0);
+}
+
+fn yaml_insert_at(out: &mut String, path: &[&str], value: &FieldValue, indent: usize) {
+    let pad = " ".repeat(indent);
+    match path {
+        [] => {}
+        [key] => match value {
+            FieldValue::String(s) => out.push_str(&format!("{}{}: {}\n", pad, key, yaml_scalar(s))),
+            FieldValue::Bool(b) => out.push_str(&format!("{}{}: {}\n", pad, key, b)),
+            FieldValue::List(items) if items.is_empty() => out.push_str(&format!("{}{}: []\n", pad, key)),
+            FieldValue::List(items) => {
+                out.push_str(&format!("{}{}:\n", pad, key));
+                for item in items {
+                    out.push_str(&format!("{}  - {}\n", pad, yaml_scalar(item)));
+                }
+            }
+        },
+        [key, rest @ ..] => {
+            out.push_str(&format!("{}{}:\n", pad, key));
+            yaml_insert_at(out, rest, value, indent + 2);
+        }
+    }
+}
+
+/// 能直接写的字符串不加引号，其余用双引号
+fn yaml_scalar(value: &str) -> String {
+    let reserved = matches!(
+        value.to_ascii_lowercase().as_str(),
+        "true" | "false" | "yes" | "no" | "on" | "off" | "null" | "~"
+    );
+    let needs_quotes = value.is_empty()
+        || reserved
+        || value.parse::<f64>().is_ok()
+        || value.trim() != value
+        || value.starts_with(['-', '?', '!', '&', '*', '@', '`', '|', '>', '%', '\'', '"', '[', '{'])
+        || value.contains(": ")
+        || value.contains(" #")
+        || value.contains([',', '[', ']', '{', '}', '\n'])
+        || value.ends_with(':');
+
+    if needs_quotes {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+    } else {
+        value.to_string()
+    }
+}
+
+fn join_lines(lines: Vec<String>) -> String {
+    let mut raw = lines.join("\n");
+    raw.push('\n');
+    raw
+}
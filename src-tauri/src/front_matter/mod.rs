@@ -0,0 +1,128 @@
+pub mod edit;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::error::{AppError, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FrontMatterFormat {
+    Yaml,
+    Toml,
+    Json,
+}
+
+/// 文章开头的 Front Matter 块
+#[derive(Debug, Clone, Copy)]
+pub struct FrontMatterBlock<'a> {
+    pub format: FrontMatterFormat,
+    /// 分隔符之间的原始内容
+    pub raw: &'a str,
+    /// `raw` 在整篇文章中的字节偏移
+    pub raw_offset: usize,
+}
+
+/// 拆分后的文章：Front Matter + 正文
+#[derive(Debug, Clone, Copy)]
+pub struct SplitDocument<'a> {
+    pub front_matter: Option<FrontMatterBlock<'a>>,
+    pub body: &'a str,
+    /// 正文在整篇文章中的字节偏移
+    pub body_offset: usize,
+    /// 正文第一行的行号（从 0 开始）
+    pub body_line: usize,
+}
+
+/// 识别 YAML（`---`）、TOML（`+++`）和 JSON（`{ ... }`）三种 Front Matter
+pub fn split_front_matter(source: &str) -> SplitDocument<'_> {
+    let no_front_matter = SplitDocument {
+        front_matter: None,
+        body: source,
+        body_offset: 0,
+        body_line: 0,
+    };
+
+    let start = if source.starts_with('\u{feff}') { 3 } else { 0 };
+    let mut lines = source[start..].split_inclusive('\n');
+    let Some(first) = lines.next() else {
+        return no_front_matter;
+    };
+
+    let (format, closing) = match first.trim_end() {
+        "---" => (FrontMatterFormat::Yaml, "---"),
+        "+++" => (FrontMatterFormat::Toml, "+++"),
+        "{" => (FrontMatterFormat::Json, "}"),
+        _ => return no_front_matter,
+    };
+
+    let raw_offset = start + first.len();
+    let mut offset = raw_offset;
+    for (line_count, line) in (2..).zip(lines) {
+        if line.trim_end() == closing {
+            // JSON 的大括号属于内容本身
+            let (raw_start, raw_end) = match format {
+                FrontMatterFormat::Json => (start, offset + line.len()),
+                _ => (raw_offset, offset),
+            };
+            let body_offset = offset + line.len();
+            return SplitDocument {
+                front_matter: Some(FrontMatterBlock {
+                    format,
+                    raw: &source[raw_start..raw_end],
+                    raw_offset: raw_start,
+                }),
+                body: &source[body_offset..],
+                body_offset,
+                body_line: line_count,
+            };
+        }
+        offset += line.len();
+    }
+
+    no_front_matter
+}
+
+/// 把 Front Matter 解析成 JSON 值，便于统一读取字段
+pub fn parse_front_matter(block: &FrontMatterBlock) -> Result<Value> {
+    match block.format {
+        FrontMatterFormat::Yaml => {
+            if block.raw.trim().is_empty() {
+                return Ok(Value::Object(Default::default()));
+            }
+            serde_yaml::from_str(block.raw).map_err(|e| AppError::ParseError(e.to_string()))
+        }
+        FrontMatterFormat::Toml => {
+            let value: toml::Value =
+                toml::from_str(block.raw).map_err(|e| AppError::ParseError(e.to_string()))?;
+            Ok(toml_to_json(value))
+        }
+        FrontMatterFormat::Json => Ok(serde_json::from_str(block.raw)?),
+    }
+}
+
+/// TOML 日期时间转为字符串，其余类型一一对应
+pub fn toml_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::from(i),
+        toml::Value::Float(f) => Value::from(f),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(dt) => Value::String(dt.to_string()),
+        toml::Value::Array(items) => Value::Array(items.into_iter().map(toml_to_json).collect()),
+        toml::Value::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(k, v)| (k, toml_to_json(v)))
+                .collect(),
+        ),
+    }
+}
+
+/// 按键名读取字段，Hugo 的键名不区分大小写
+pub fn get_field<'a>(value: &'a Value, key: &str) -> Option<&'a Value> {
+    value
+        .as_object()?
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(key))
+        .map(|(_, v)| v)
+}
@@ -0,0 +1,840 @@
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Utc};
+use git2::build::CheckoutBuilder;
+use git2::{
+    BranchType, Cred, CredentialType, DiffFormat, DiffOptions, ErrorCode, FetchOptions, IndexAddOption,
+    ObjectType, PushOptions, RemoteCallbacks, Repository, Signature, Sort, Status, StatusOptions,
+};
+use serde::{Deserialize, Serialize};
+use crate::error::{AppError, Result};
+use crate::revisions::{DiffHunk, DiffLine, DiffLineKind};
+use crate::utils::resolve_project_file;
+
+/// 与 Git 相关的项目设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GitSettings {
+    /// 拉取和推送使用的远程仓库
+    pub remote: String,
+}
+
+impl Default for GitSettings {
+    fn default() -> Self {
+        Self {
+            remote: "origin".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    New,
+    Modified,
+    Deleted,
+    Renamed,
+    TypeChange,
+    Conflicted,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileStatus {
+    /// 相对于站点根目录，使用 `/` 分隔
+    pub path: String,
+    /// 暂存区相对 HEAD 的变化
+    pub staged: Option<ChangeKind>,
+    /// 工作区相对暂存区的变化
+    pub unstaged: Option<ChangeKind>,
+    /// 是否为内容目录中的文章
+    pub is_post: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RepoStatus {
+    /// 当前分支；HEAD 处于分离状态时为 None
+    pub branch: Option<String>,
+    pub upstream: Option<String>,
+    pub ahead: usize,
+    pub behind: usize,
+    pub files: Vec<FileStatus>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CommitInfo {
+    pub id: String,
+    pub short_id: String,
+    pub summary: String,
+    pub message: String,
+    pub author: String,
+    pub email: String,
+    pub time: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BranchInfo {
+    pub name: String,
+    pub current: bool,
+    pub upstream: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PostDiff {
+    pub path: String,
+    /// 二进制文件没有逐行差异
+    pub binary: bool,
+    pub hunks: Vec<DiffHunk>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PullOutcome {
+    UpToDate,
+    FastForward,
+    Merged,
+}
+
+/// 站点所在的仓库。站点可以是仓库中的子目录，对外使用的路径都相对于站点根目录
+pub struct SiteRepo {
+    repo: Repository,
+    /// 站点根目录相对于仓库工作区的路径，站点就是仓库根目录时为空
+    prefix: PathBuf,
+}
+
+impl SiteRepo {
+    pub fn open(project_path: &Path) -> Result<Self> {
+        let repo = Repository::discover(project_path)?;
+        let workdir = repo
+            .workdir()
+            .ok_or_else(|| AppError::GitError("Bare repositories are not supported".to_string()))?;
+        let workdir = workdir.canonicalize()?;
+        let project = project_path.canonicalize()?;
+        let prefix = project
+            .strip_prefix(&workdir)
+            .map(Path::to_path_buf)
+            .map_err(|_| AppError::InvalidPath(project_path.display().to_string()))?;
+        Ok(Self { repo, prefix })
+    }
+
+    pub fn status(&self) -> Result<RepoStatus> {
+        let mut options = StatusOptions::new();
+        options
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .renames_head_to_index(true);
+        if !self.prefix.as_os_str().is_empty() {
+            options.pathspec(self.repo_path_str(&self.prefix));
+        }
+
+        let mut files = Vec::new();
+        for entry in self.repo.statuses(Some(&mut options))?.iter() {
+            let Some(path) = entry.path().and_then(|p| self.site_path(p)) else {
+                continue;
+            };
+            let status = entry.status();
+            files.push(FileStatus {
+                is_post: is_post(&path),
+                staged: staged_change(status),
+                unstaged: unstaged_change(status),
+                path,
+            });
+        }
+
+        let branch = self.current_branch()?;
+        let mut upstream = None;
+        let (mut ahead, mut behind) = (0, 0);
+        if let Some(name) = &branch {
+            if let Ok(local) = self.repo.find_branch(name, BranchType::Local) {
+                if let Ok(remote) = local.upstream() {
+                    upstream = remote.name()?.map(str::to_string);
+                    if let (Some(local), Some(remote)) = (local.get().target(), remote.get().target()) {
+                        (ahead, behind) = self.repo.graph_ahead_behind(local, remote)?;
+                    }
+                }
+            }
+        }
+
+        Ok(RepoStatus {
+            branch,
+            upstream,
+            ahead,
+            behind,
+            files,
+        })
+    }
+
+    /// 暂存文件；`paths` 为空时暂存站点目录下的所有变化。已删除的文件从暂存区移除
+    pub fn stage(&self, paths: &[String]) -> Result<()> {
+        let mut index = self.repo.index()?;
+        if paths.is_empty() {
+            let spec = if self.prefix.as_os_str().is_empty() {
+                "*".to_string()
+            } else {
+                self.repo_path_str(&self.prefix)
+            };
+            index.add_all([spec.as_str()], IndexAddOption::DEFAULT, None)?;
+            index.update_all([spec.as_str()], None)?;
+        } else {
+            let workdir = self.workdir();
+            for path in paths {
+                let repo_path = self.repo_path(path)?;
+                if workdir.join(&repo_path).exists() {
+                    index.add_path(&repo_path)?;
+                } else {
+                    index.remove_path(&repo_path)?;
+                }
+            }
+        }
+        index.write()?;
+        Ok(())
+    }
+
+    /// 取消暂存，恢复为 HEAD 中的状态
+    pub fn unstage(&self, paths: &[String]) -> Result<()> {
+        let repo_paths = paths
+            .iter()
+            .map(|path| self.repo_path(path).map(|p| self.repo_path_str(&p)))
+            .collect::<Result<Vec<_>>>()?;
+
+        match self.head_commit()? {
+            Some(head) => self.repo.reset_default(Some(head.as_object()), repo_paths.iter())?,
+            // 还没有提交时直接从暂存区移除
+            None => {
+                let mut index = self.repo.index()?;
+                for path in &repo_paths {
+                    index.remove_path(Path::new(path))?;
+                }
+                index.write()?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn commit(&self, message: &str) -> Result<CommitInfo> {
+        if message.trim().is_empty() {
+            return Err(AppError::GitError("Commit message is empty".to_string()));
+        }
+        let mut index = self.repo.index()?;
+        if index.has_conflicts() {
+            return Err(AppError::GitError("Resolve conflicts before committing".to_string()));
+        }
+
+        let tree = self.repo.find_tree(index.write_tree()?)?;
+        let parent = self.head_commit()?;
+        if parent.as_ref().is_some_and(|p| p.tree_id() == tree.id()) {
+            return Err(AppError::GitError("Nothing to commit".to_string()));
+        }
+
+        let signature = self.signature()?;
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        let id = self
+            .repo
+            .commit(Some("HEAD"), &signature, &signature, message, &tree, &parents)?;
+        Ok(commit_info(&self.repo.find_commit(id)?))
+    }
+
+    /// 修改过某篇文章的提交，新的在前。合并提交只与第一个父提交比较
+    pub fn file_history(&self, path: &str, limit: usize) -> Result<Vec<CommitInfo>> {
+        let repo_path = self.repo_path(path)?;
+        if self.head_commit()?.is_none() {
+            return Ok(Vec::new());
+        }
+
+        let mut walk = self.repo.revwalk()?;
+        walk.push_head()?;
+        walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+
+        let mut history = Vec::new();
+        for id in walk {
+            let commit = self.repo.find_commit(id?)?;
+            let tree = commit.tree()?;
+            let parent_tree = match commit.parent(0) {
+                Ok(parent) => Some(parent.tree()?),
+                Err(_) => None,
+            };
+            let mut options = DiffOptions::new();
+            options.pathspec(&repo_path);
+            let diff = self
+                .repo
+                .diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), Some(&mut options))?;
+            if diff.deltas().len() > 0 {
+                history.push(commit_info(&commit));
+                if history.len() >= limit {
+                    break;
+                }
+            }
+        }
+        Ok(history)
+    }
+
+    /// 文章（工作区，包括未暂存的修改）与 HEAD 的差异
+    pub fn diff_against_head(&self, path: &str) -> Result<PostDiff> {
+        let repo_path = self.repo_path(path)?;
+        let head_tree = match self.head_commit()? {
+            Some(head) => Some(head.tree()?),
+            None => None,
+        };
+        let mut options = DiffOptions::new();
+        options
+            .pathspec(&repo_path)
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .show_untracked_content(true);
+        let diff = self
+            .repo
+            .diff_tree_to_workdir_with_index(head_tree.as_ref(), Some(&mut options))?;
+
+        let binary = diff.deltas().any(|delta| delta.flags().is_binary());
+        let mut hunks: Vec<DiffHunk> = Vec::new();
+        let mut current_header: Option<Vec<u8>> = None;
+        diff.print(DiffFormat::Patch, |_, hunk, line| {
+            let kind = match line.origin() {
+                ' ' => DiffLineKind::Equal,
+                '+' => DiffLineKind::Insert,
+                '-' => DiffLineKind::Delete,
+                _ => return true,
+            };
+            let header = hunk.map(|h| h.header().to_vec());
+            if hunks.is_empty() || header != current_header {
+                hunks.push(DiffHunk { lines: Vec::new() });
+                current_header = header;
+            }
+            let text = String::from_utf8_lossy(line.content());
+            hunks.last_mut().unwrap().lines.push(DiffLine {
+                kind,
+                old_line: line.old_lineno().map(|n| n as usize),
+                new_line: line.new_lineno().map(|n| n as usize),
+                text: text.trim_end_matches(['\n', '\r']).to_string(),
+            });
+            true
+        })?;
+
+        Ok(PostDiff {
+            path: path.to_string(),
+            binary,
+            hunks,
+        })
+    }
+
+    pub fn branches(&self) -> Result<Vec<BranchInfo>> {
+        let mut branches = Vec::new();
+        for branch in self.repo.branches(Some(BranchType::Local))? {
+            let (branch, _) = branch?;
+            let Some(name) = branch.name()? else {
+                continue;
+            };
+            branches.push(BranchInfo {
+                name: name.to_string(),
+                current: branch.is_head(),
+                upstream: branch
+                    .upstream()
+                    .ok()
+                    .and_then(|upstream| upstream.name().ok().flatten().map(str::to_string)),
+            });
+        }
+        Ok(branches)
+    }
+
+    /// 切换分支。本地没有该分支时，从远程同名分支创建并跟踪它；
+    /// `create` 为 true 时也可以从当前提交新建。工作区中会被覆盖的修改会导致切换失败
+    pub fn switch_branch(&self, name: &str, remote: &str, create: bool) -> Result<()> {
+        let branch = match self.repo.find_branch(name, BranchType::Local) {
+            Ok(branch) => branch,
+            Err(e) if e.code() == ErrorCode::NotFound => {
+                let remote_name = format!("{}/{}", remote, name);
+                match self.repo.find_branch(&remote_name, BranchType::Remote) {
+                    Ok(remote_branch) => {
+                        let commit = remote_branch.get().peel_to_commit()?;
+                        let mut branch = self.repo.branch(name, &commit, false)?;
+                        branch.set_upstream(Some(&remote_name))?;
+                        branch
+                    }
+                    Err(_) if create => {
+                        let head = self
+                            .head_commit()?
+                            .ok_or_else(|| AppError::GitError("No commits yet".to_string()))?;
+                        self.repo.branch(name, &head, false)?
+                    }
+                    Err(_) => return Err(e.into()),
+                }
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let reference = branch.get();
+        let target = reference.peel(ObjectType::Commit)?;
+        self.repo.checkout_tree(&target, Some(CheckoutBuilder::new().safe()))?;
+        let reference_name = reference
+            .name()
+            .ok_or_else(|| AppError::GitError("Invalid branch name".to_string()))?;
+        self.repo.set_head(reference_name)?;
+        Ok(())
+    }
+
+    /// 拉取当前分支：能快进时直接快进，否则在没有冲突时创建合并提交
+    pub fn pull(&self, remote_name: &str) -> Result<PullOutcome> {
+        let branch = self
+            .current_branch()?
+            .ok_or_else(|| AppError::GitError("HEAD is detached".to_string()))?;
+        let tracking = format!("refs/remotes/{}/{}", remote_name, branch);
+
+        let mut remote = self.find_remote(remote_name)?;
+        let mut options = FetchOptions::new();
+        options.remote_callbacks(remote_callbacks());
+        remote.fetch(
+            &[format!("+refs/heads/{}:{}", branch, tracking)],
+            Some(&mut options),
+            None,
+        )?;
+
+        let theirs = self.repo.find_reference(&tracking)?;
+        let theirs = self.repo.reference_to_annotated_commit(&theirs)?;
+        let (analysis, _) = self.repo.merge_analysis(&[&theirs])?;
+        if analysis.is_up_to_date() {
+            return Ok(PullOutcome::UpToDate);
+        }
+
+        let their_commit = self.repo.find_commit(theirs.id())?;
+        let local_ref = format!("refs/heads/{}", branch);
+        if analysis.is_fast_forward() || analysis.is_unborn() {
+            self.repo
+                .checkout_tree(their_commit.as_object(), Some(CheckoutBuilder::new().safe()))?;
+            self.repo
+                .reference(&local_ref, theirs.id(), true, &format!("pull: fast-forward to {}", theirs.id()))?;
+            self.repo.set_head(&local_ref)?;
+            return Ok(PullOutcome::FastForward);
+        }
+
+        let ours = self
+            .head_commit()?
+            .ok_or_else(|| AppError::GitError("No commits yet".to_string()))?;
+        let mut index = self.repo.merge_commits(&ours, &their_commit, None)?;
+        if index.has_conflicts() {
+            return Err(AppError::GitError(format!(
+                "Pulling {}/{} would cause conflicts; resolve them in a terminal",
+                remote_name, branch
+            )));
+        }
+        let tree = self.repo.find_tree(index.write_tree_to(&self.repo)?)?;
+        let signature = self.signature()?;
+        let message = format!("Merge branch '{}' of {}", branch, remote_name);
+        let merged = self
+            .repo
+            .commit(None, &signature, &signature, &message, &tree, &[&ours, &their_commit])?;
+        let merged = self.repo.find_commit(merged)?;
+        self.repo
+            .checkout_tree(merged.as_object(), Some(CheckoutBuilder::new().safe()))?;
+        self.repo
+            .reference(&local_ref, merged.id(), true, &message)?;
+        Ok(PullOutcome::Merged)
+    }
+
+    /// 推送当前分支；还没有上游分支时设为远程同名分支
+    pub fn push(&self, remote_name: &str) -> Result<()> {
+        let branch = self
+            .current_branch()?
+            .ok_or_else(|| AppError::GitError("HEAD is detached".to_string()))?;
+        let mut remote = self.find_remote(remote_name)?;
+        push_refspec(&mut remote, &format!("refs/heads/{0}:refs/heads/{0}", branch))?;
+
+        let mut local = self.repo.find_branch(&branch, BranchType::Local)?;
+        if let Some(target) = local.get().target() {
+            self.repo.reference(
+                &format!("refs/remotes/{}/{}", remote_name, branch),
+                target,
+                true,
+                "push",
+            )?;
+        }
+        if local.upstream().is_err() {
+            local.set_upstream(Some(&format!("{}/{}", remote_name, branch)))?;
+        }
+        Ok(())
+    }
+
+    fn find_remote(&self, name: &str) -> Result<git2::Remote<'_>> {
+        let remote = self.repo.find_remote(name)?;
+        match remote.url() {
+            Some(url) if is_relative_url(url) => {
+                let absolute = resolve_remote_url(url, self.workdir());
+                Ok(self.repo.remote_anonymous(&absolute)?)
+            }
+            _ => Ok(remote),
+        }
+    }
+
+    /// 远程仓库的地址，相对路径已解析为绝对路径
+    pub fn remote_url(&self, name: &str) -> Result<String> {
+        let remote = self.repo.find_remote(name)?;
+        let url = remote
+            .url()
+            .ok_or_else(|| AppError::GitError(format!("Remote {} has no URL", name)))?;
+        Ok(resolve_remote_url(url, self.workdir()))
+    }
+
+    /// HEAD 指向的提交，还没有提交时为 None
+    pub fn head_id(&self) -> Result<Option<String>> {
+        Ok(self.head_commit()?.map(|commit| commit.id().to_string()))
+    }
+
+    fn current_branch(&self) -> Result<Option<String>> {
+        match self.repo.head() {
+            Ok(head) if head.is_branch() => Ok(head.shorthand().map(str::to_string)),
+            Ok(_) => Ok(None),
+            // 还没有提交时 HEAD 指向尚不存在的分支
+            Err(e) if e.code() == ErrorCode::UnbornBranch => {
+                let head = self.repo.find_reference("HEAD")?;
+                Ok(head
+                    .symbolic_target()
+                    .and_then(|target| target.strip_prefix("refs/heads/"))
+                    .map(str::to_string))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn head_commit(&self) -> Result<Option<git2::Commit<'_>>> {
+        match self.repo.head() {
+            Ok(head) => Ok(Some(head.peel_to_commit()?)),
+            Err(e) if matches!(e.code(), ErrorCode::UnbornBranch | ErrorCode::NotFound) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn signature(&self) -> Result<Signature<'static>> {
+        signature(&self.repo)
+    }
+
+    fn workdir(&self) -> &Path {
+        self.repo.workdir().unwrap_or_else(|| self.repo.path())
+    }
+
+    fn repo_path(&self, site_path: &str) -> Result<PathBuf> {
+        resolve_project_file(&self.prefix, site_path)
+    }
+
+    fn repo_path_str(&self, path: &Path) -> String {
+        path.to_string_lossy().replace('\\', "/")
+    }
+
+    /// 仓库中的路径 → 站点中的路径，站点目录之外的文件返回 None
+    fn site_path(&self, repo_path: &str) -> Option<String> {
+        Path::new(repo_path)
+            .strip_prefix(&self.prefix)
+            .ok()
+            .map(|path| self.repo_path_str(path))
+    }
+}
+
+/// 使用 Git 配置中的 user.name / user.email，没有配置时使用编辑器的名字
+pub(crate) fn signature(repo: &Repository) -> Result<Signature<'static>> {
+    match repo.signature() {
+        Ok(signature) => Ok(signature.to_owned()),
+        Err(_) => Ok(Signature::now("Pineappleblog", "pineappleblog@localhost")?),
+    }
+}
+
+/// libgit2 不会像 git 命令那样把 `../site.git` 之类的相对路径解析到工作区，这里手动解析
+pub(crate) fn resolve_remote_url(url: &str, base: &Path) -> String {
+    if is_relative_url(url) {
+        base.join(url).to_string_lossy().into_owned()
+    } else {
+        url.to_string()
+    }
+}
+
+fn is_relative_url(url: &str) -> bool {
+    url.starts_with("./") || url.starts_with("../")
+}
+
+/// 推送并检查远程是否拒绝了更新（例如不是快进）
+pub(crate) fn push_refspec(remote: &mut git2::Remote, refspec: &str) -> Result<()> {
+    let rejected: RefCell<Option<String>> = RefCell::new(None);
+    let mut callbacks = remote_callbacks();
+    callbacks.push_update_reference(|reference, status| {
+        if let Some(status) = status {
+            *rejected.borrow_mut() = Some(format!("{}: {}", reference, status));
+        }
+        Ok(())
+    });
+    let mut options = PushOptions::new();
+    options.remote_callbacks(callbacks);
+    remote.push(&[refspec], Some(&mut options))?;
+    drop(options);
+
+    match rejected.into_inner() {
+        Some(reason) => Err(AppError::GitError(format!("Push rejected: {}", reason))),
+        None => Ok(()),
+    }
+}
+
+/// 依次尝试 SSH agent、Git 凭据助手和系统默认凭据，避免无限重试
+pub(crate) fn remote_callbacks() -> RemoteCallbacks<'static> {
+    let mut callbacks = RemoteCallbacks::new();
+    let config = git2::Config::open_default().ok();
+    let mut attempts = 0;
+    callbacks.credentials(move |url, username, allowed| {
+        attempts += 1;
+        if attempts > 3 {
+            return Err(git2::Error::from_str("Authentication failed"));
+        }
+        if allowed.contains(CredentialType::SSH_KEY) {
+            if let Some(username) = username {
+                return Cred::ssh_key_from_agent(username);
+            }
+        }
+        if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) {
+            if let Some(config) = &config {
+                if let Ok(cred) = Cred::credential_helper(config, url, username) {
+                    return Ok(cred);
+                }
+            }
+        }
+        if allowed.contains(CredentialType::DEFAULT) {
+            return Cred::default();
+        }
+        Err(git2::Error::from_str("No credentials available"))
+    });
+    callbacks
+}
+
+fn staged_change(status: Status) -> Option<ChangeKind> {
+    if status.is_conflicted() {
+        Some(ChangeKind::Conflicted)
+    } else if status.is_index_new() {
+        Some(ChangeKind::New)
+    } else if status.is_index_modified() {
+        Some(ChangeKind::Modified)
+    } else if status.is_index_deleted() {
+        Some(ChangeKind::Deleted)
+    } else if status.is_index_renamed() {
+        Some(ChangeKind::Renamed)
+    } else if status.is_index_typechange() {
+        Some(ChangeKind::TypeChange)
+    } else {
+        None
+    }
+}
+
+fn unstaged_change(status: Status) -> Option<ChangeKind> {
+    if status.is_conflicted() {
+        Some(ChangeKind::Conflicted)
+    } else if status.is_wt_new() {
+        Some(ChangeKind::New)
+    } else if status.is_wt_modified() {
+        Some(ChangeKind::Modified)
+    } else if status.is_wt_deleted() {
+        Some(ChangeKind::Deleted)
+    } else if status.is_wt_renamed() {
+        Some(ChangeKind::Renamed)
+    } else if status.is_wt_typechange() {
+        Some(ChangeKind::TypeChange)
+    } else {
+        None
+    }
+}
+
+fn is_post(path: &str) -> bool {
+    path.starts_with("content/") && (path.ends_with(".md") || path.ends_with(".markdown"))
+}
+
+fn commit_info(commit: &git2::Commit) -> CommitInfo {
+    let id = commit.id().to_string();
+    let author = commit.author();
+    CommitInfo {
+        short_id: id.chars().take(7).collect(),
+        id,
+        summary: commit.summary().unwrap_or_default().to_string(),
+        message: commit.message().unwrap_or_default().to_string(),
+        author: author.name().unwrap_or_default().to_string(),
+        email: author.email().unwrap_or_default().to_string(),
+        time: DateTime::from_timestamp(commit.time().seconds(), 0).unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::open_project_db;
+    use crate::settings::{save_project_settings, ProjectSettings};
+    use std::fs;
+
+    /// 站点放在仓库的 `site/` 子目录中，远程仓库是同一临时目录下的裸仓库
+    fn site_with_remote(root: &Path) -> PathBuf {
+        Repository::init_bare(root.join("remote.git")).unwrap();
+        let repo = Repository::init(root.join("work")).unwrap();
+        repo.remote("origin", &root.join("remote.git").to_string_lossy()).unwrap();
+        let site = root.join("work/site");
+        fs::create_dir_all(site.join("content")).unwrap();
+        fs::write(site.join("config.toml"), "title = \"Blog\"\n").unwrap();
+        fs::write(site.join("content/post.md"), "one\ntwo\nthree\n").unwrap();
+        fs::write(root.join("work/README.md"), "outside the site\n").unwrap();
+        site
+    }
+
+    fn write(path: &Path, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn commit_all(repo: &SiteRepo, message: &str) -> CommitInfo {
+        repo.stage(&[]).unwrap();
+        repo.commit(message).unwrap()
+    }
+
+    #[test]
+    fn status_stage_commit_and_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let site = site_with_remote(dir.path());
+        let repo = SiteRepo::open(&site).unwrap();
+
+        // 站点目录之外的文件不出现在状态中
+        let status = repo.status().unwrap();
+        let mut files: Vec<_> = status.files.iter().map(|f| (f.path.as_str(), f.is_post)).collect();
+        files.sort();
+        assert_eq!(files, [("config.toml", false), ("content/post.md", true)]);
+        assert!(status.files.iter().all(|f| f.staged.is_none() && f.unstaged == Some(ChangeKind::New)));
+
+        repo.stage(&["content/post.md".to_string()]).unwrap();
+        let status = repo.status().unwrap();
+        let post = status.files.iter().find(|f| f.path == "content/post.md").unwrap();
+        assert_eq!((post.staged, post.unstaged), (Some(ChangeKind::New), None));
+        repo.unstage(&["content/post.md".to_string()]).unwrap();
+        assert!(repo.status().unwrap().files.iter().all(|f| f.staged.is_none()));
+
+        let first = commit_all(&repo, "Add post");
+        assert!(repo.status().unwrap().files.is_empty());
+        assert!(matches!(repo.commit("Again"), Err(AppError::GitError(_))));
+
+        write(&site.join("content/post.md"), "one\n2\nthree\n");
+        let diff = repo.diff_against_head("content/post.md").unwrap();
+        assert!(!diff.binary);
+        let changed: Vec<_> = diff.hunks[0]
+            .lines
+            .iter()
+            .filter(|line| line.kind != DiffLineKind::Equal)
+            .map(|line| (line.kind, line.text.as_str()))
+            .collect();
+        assert_eq!(changed, [(DiffLineKind::Delete, "two"), (DiffLineKind::Insert, "2")]);
+
+        let second = commit_all(&repo, "Edit post");
+        write(&site.join("config.toml"), "title = \"Renamed\"\n");
+        commit_all(&repo, "Rename blog");
+
+        let history = repo.file_history("content/post.md", 10).unwrap();
+        let ids: Vec<_> = history.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, [second.id.as_str(), first.id.as_str()]);
+        assert_eq!(repo.file_history("content/post.md", 1).unwrap().len(), 1);
+        assert!(repo.diff_against_head("../README.md").is_err());
+    }
+
+    #[test]
+    fn editor_data_is_never_staged() {
+        let dir = tempfile::tempdir().unwrap();
+        let site = site_with_remote(dir.path());
+        save_project_settings(&site, &ProjectSettings::default()).unwrap();
+        open_project_db(&site).unwrap();
+        write(&site.join(".pineapple/undo/1/manifest.json"), "{}");
+        Repository::init_bare(site.join(".pineapple/deploy/pages.git")).unwrap();
+
+        let repo = SiteRepo::open(&site).unwrap();
+        let status = repo.status().unwrap();
+        assert!(status.files.iter().all(|f| !f.path.starts_with(".pineapple")));
+
+        commit_all(&repo, "Initial");
+        let head = repo.repo.head().unwrap().peel_to_tree().unwrap();
+        let site_tree = head.get_path(Path::new("site")).unwrap().to_object(&repo.repo).unwrap();
+        let names: Vec<_> = site_tree
+            .as_tree()
+            .unwrap()
+            .iter()
+            .map(|entry| entry.name().unwrap().to_string())
+            .collect();
+        assert_eq!(names, ["config.toml", "content"]);
+    }
+
+    #[test]
+    fn switch_branch_creates_and_tracks() {
+        let dir = tempfile::tempdir().unwrap();
+        let site = site_with_remote(dir.path());
+        let repo = SiteRepo::open(&site).unwrap();
+        commit_all(&repo, "Initial");
+        repo.push("origin").unwrap();
+        let main = repo.status().unwrap().branch.unwrap();
+
+        assert!(repo.switch_branch("draft", "origin", false).is_err());
+        repo.switch_branch("draft", "origin", true).unwrap();
+        write(&site.join("content/draft.md"), "draft\n");
+        commit_all(&repo, "Draft");
+        repo.push("origin").unwrap();
+
+        repo.switch_branch(&main, "origin", false).unwrap();
+        assert!(!site.join("content/draft.md").exists());
+        let branches = repo.branches().unwrap();
+        let draft = branches.iter().find(|b| b.name == "draft").unwrap();
+        assert!(!draft.current);
+        assert_eq!(draft.upstream.as_deref(), Some("origin/draft"));
+
+        // 另一个克隆中本地没有该分支，从远程同名分支创建并跟踪
+        let clone = dir.path().join("clone");
+        Repository::clone(&dir.path().join("remote.git").to_string_lossy(), &clone).unwrap();
+        let other = SiteRepo::open(&clone.join("site")).unwrap();
+        other.switch_branch("draft", "origin", false).unwrap();
+        assert!(clone.join("site/content/draft.md").exists());
+        let status = other.status().unwrap();
+        assert_eq!(status.branch.as_deref(), Some("draft"));
+        assert_eq!(status.upstream.as_deref(), Some("origin/draft"));
+    }
+
+    #[test]
+    fn push_then_pull_fast_forward_and_merge() {
+        let dir = tempfile::tempdir().unwrap();
+        let site = site_with_remote(dir.path());
+        let repo = SiteRepo::open(&site).unwrap();
+        commit_all(&repo, "Initial");
+        let branch = repo.status().unwrap().branch.unwrap();
+        repo.push("origin").unwrap();
+
+        let status = repo.status().unwrap();
+        assert_eq!(status.upstream, Some(format!("origin/{}", branch)));
+        assert_eq!((status.ahead, status.behind), (0, 0));
+        let remote = Repository::open_bare(dir.path().join("remote.git")).unwrap();
+        let remote_head = remote.refname_to_id(&format!("refs/heads/{}", branch)).unwrap();
+        assert_eq!(repo.head_id().unwrap(), Some(remote_head.to_string()));
+        assert_eq!(repo.pull("origin").unwrap(), PullOutcome::UpToDate);
+
+        let clone = dir.path().join("clone");
+        Repository::clone(&dir.path().join("remote.git").to_string_lossy(), &clone).unwrap();
+        let other = SiteRepo::open(&clone.join("site")).unwrap();
+        write(&clone.join("site/content/second.md"), "second\n");
+        commit_all(&other, "Second post");
+        other.push("origin").unwrap();
+
+        assert_eq!(repo.pull("origin").unwrap(), PullOutcome::FastForward);
+        assert_eq!(fs::read_to_string(site.join("content/second.md")).unwrap(), "second\n");
+        assert_eq!(repo.head_id().unwrap(), other.head_id().unwrap());
+
+        // 两边修改不同的文件，拉取时创建合并提交
+        write(&site.join("content/post.md"), "one\ntwo\nthree\nfour\n");
+        let ours = commit_all(&repo, "Local edit");
+        write(&clone.join("site/config.toml"), "title = \"Remote\"\n");
+        let theirs = commit_all(&other, "Remote edit");
+        other.push("origin").unwrap();
+        // 远程已经有新提交，不能快进的推送被拒绝
+        assert!(matches!(repo.push("origin"), Err(AppError::GitError(_))));
+
+        assert_eq!(repo.pull("origin").unwrap(), PullOutcome::Merged);
+        let merged = repo.repo.head().unwrap().peel_to_commit().unwrap();
+        let parents: Vec<_> = merged.parent_ids().map(|id| id.to_string()).collect();
+        assert_eq!(parents, [ours.id, theirs.id]);
+        assert_eq!(fs::read_to_string(site.join("config.toml")).unwrap(), "title = \"Remote\"\n");
+        assert!(repo.status().unwrap().files.is_empty());
+
+        repo.push("origin").unwrap();
+        assert_eq!(other.pull("origin").unwrap(), PullOutcome::FastForward);
+        assert_eq!(
+            fs::read_to_string(clone.join("site/content/post.md")).unwrap(),
+            "one\ntwo\nthree\nfour\n"
+        );
+    }
+}
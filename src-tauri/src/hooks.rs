@@ -0,0 +1,285 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use crate::engine::create_engine;
+use crate::error::{AppError, Result};
+use crate::models::{BuildOptions, BuildResult, EngineType};
+use crate::site_config::output_dir;
+use crate::utils::{execute_command_streaming, resolve_project_file, OutputStream};
+
+/// 钩子每输出一行时发给前端的事件
+pub const HOOK_OUTPUT_EVENT: &str = "hook-output";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HookStage {
+    PreBuild,
+    /// 构建成功后
+    PostBuild,
+    /// 构建和部署前检查都通过后、上传之前
+    PreDeploy,
+    /// 部署成功后
+    PostDeploy,
+}
+
+impl HookStage {
+    fn name(self) -> &'static str {
+        match self {
+            HookStage::PreBuild => "pre-build",
+            HookStage::PostBuild => "post-build",
+            HookStage::PreDeploy => "pre-deploy",
+            HookStage::PostDeploy => "post-deploy",
+        }
+    }
+}
+
+/// 保存在项目设置中的钩子，同一阶段的钩子按定义顺序运行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Hook {
+    pub stage: HookStage,
+    /// 在系统 shell 中执行（Windows 为 `cmd /C`，其他系统为 `sh -c`）
+    pub command: String,
+    /// 相对于站点根目录，为空时在站点根目录执行
+    #[serde(default)]
+    pub working_dir: Option<String>,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// 失败时中止构建或部署；为 false 时只记录失败
+    #[serde(default = "default_abort_on_failure")]
+    pub abort_on_failure: bool,
+    /// 超过这个时间（秒）仍未结束的钩子会被终止，按失败处理
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_abort_on_failure() -> bool {
+    true
+}
+
+fn default_timeout_secs() -> u64 {
+    600
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HookOutput {
+    pub stage: HookStage,
+    pub command: String,
+    pub stream: OutputStream,
+    pub line: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HookRun {
+    pub stage: HookStage,
+    pub command: String,
+    pub success: bool,
+    /// 被信号终止或超时时为 None
+    pub exit_code: Option<i32>,
+    pub duration: f64,
+}
+
+pub type OutputCallback<'a> = &'a (dyn Fn(HookOutput) + Send + Sync);
+
+/// 依次运行一个阶段的钩子。钩子的环境变量包括 `PINEAPPLE_PROJECT_PATH`、`PINEAPPLE_HOOK_STAGE`
+/// 和调用方传入的 `env`，钩子自己定义的同名变量优先。
+///
+/// `abort_on_failure` 的钩子失败（包括超时）时不再运行后面的钩子，返回 `HookFailed`
+pub async fn run_stage(
+    project_path: &Path,
+    hooks: &[Hook],
+    stage: HookStage,
+    env: &[(&str, String)],
+    on_output: OutputCallback<'_>,
+) -> Result<Vec<HookRun>> {
+    let mut runs = Vec::new();
+    for hook in hooks.iter().filter(|hook| hook.stage == stage) {
+        let working_dir = match hook.working_dir.as_deref().map(str::trim).filter(|dir| !dir.is_empty()) {
+            Some(dir) => resolve_project_file(project_path, dir)?,
+            None => project_path.to_path_buf(),
+        };
+        let mut envs: BTreeMap<String, String> = [
+            ("PINEAPPLE_PROJECT_PATH", project_path.to_string_lossy().to_string()),
+            ("PINEAPPLE_HOOK_STAGE", stage.name().to_string()),
+        ]
+        .into_iter()
+        .chain(env.iter().cloned())
+        .map(|(key, value)| (key.to_string(), value))
+        .collect();
+        envs.extend(hook.env.clone());
+
+        let (shell, flag) = if cfg!(windows) { ("cmd", "/C") } else { ("sh", "-c") };
+        let started = Instant::now();
+        let timeout = Duration::from_secs(hook.timeout_secs.max(1));
+        // 超时后丢弃这个 future 会终止子进程
+        let output = tokio::time::timeout(
+            timeout,
+            execute_command_streaming(
+                Path::new(shell),
+                &[flag, &hook.command],
+                &working_dir,
+                &envs,
+                |stream, line| {
+                    on_output(HookOutput {
+                        stage,
+                        command: hook.command.clone(),
+                        stream,
+                        line: line.to_string(),
+                    })
+                },
+            ),
+        )
+        .await;
+
+        let (status, failure) = match output {
+            Ok(output) => {
+                let status = output?.status;
+                (Some(status), format!("exited with {}", status))
+            }
+            Err(_) => (None, format!("timed out after {}s", timeout.as_secs())),
+        };
+        let run = HookRun {
+            stage,
+            command: hook.command.clone(),
+            success: status.is_some_and(|status| status.success()),
+            exit_code: status.and_then(|status| status.code()),
+            duration: started.elapsed().as_secs_f64(),
+        };
+        if !run.success && hook.abort_on_failure {
+            return Err(AppError::HookFailed(format!(
+                "{} hook `{}` {}",
+                stage.name(),
+                hook.command,
+                failure
+            )));
+        }
+        runs.push(run);
+    }
+    Ok(runs)
+}
+
+/// 构建站点，前后运行 pre-build / post-build 钩子；构建失败时不运行 post-build 钩子。
+/// post-build 钩子可以从 `PINEAPPLE_OUTPUT_DIR` 读取构建输出目录（按构建选项和站点配置确定）
+pub async fn build(
+    project_path: &Path,
+    engine: &EngineType,
+    options: &BuildOptions,
+    hooks: &[Hook],
+    on_output: OutputCallback<'_>,
+) -> Result<BuildResult> {
+    run_stage(project_path, hooks, HookStage::PreBuild, &[], on_output).await?;
+    let result = create_engine(engine.clone())?.build(project_path, options).await?;
+    if result.success {
+        let output_dir = output_dir(project_path, engine, options.destination.as_deref());
+        let env = [("PINEAPPLE_OUTPUT_DIR", output_dir.to_string_lossy().to_string())];
+        run_stage(project_path, hooks, HookStage::PostBuild, &env, on_output).await?;
+    }
+    Ok(result)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn hook(stage: HookStage, command: &str) -> Hook {
+        Hook {
+            stage,
+            command: command.to_string(),
+            working_dir: None,
+            env: BTreeMap::new(),
+            abort_on_failure: true,
+            timeout_secs: default_timeout_secs(),
+        }
+    }
+
+    async fn run(project: &Path, hooks: &[Hook], env: &[(&str, String)]) -> (Result<Vec<HookRun>>, Vec<(OutputStream, String)>) {
+        let lines = Mutex::new(Vec::new());
+        let on_output = |output: HookOutput| lines.lock().unwrap().push((output.stream, output.line));
+        let result = run_stage(project, hooks, HookStage::PreBuild, env, &on_output).await;
+        (result, lines.into_inner().unwrap())
+    }
+
+    fn texts(lines: &[(OutputStream, String)]) -> Vec<&str> {
+        lines.iter().map(|(_, line)| line.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn hook_env_overrides_caller_env() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut custom = hook(HookStage::PreBuild, "echo \"$PINEAPPLE_HOOK_STAGE $PINEAPPLE_OUTPUT_DIR $NAME\"");
+        custom.env.insert("PINEAPPLE_OUTPUT_DIR".to_string(), "mine".to_string());
+        custom.env.insert("NAME".to_string(), "hook".to_string());
+        let plain = hook(HookStage::PreBuild, "echo \"$PINEAPPLE_OUTPUT_DIR $PINEAPPLE_PROJECT_PATH\"");
+        let other_stage = hook(HookStage::PostBuild, "echo never");
+
+        let env = [("PINEAPPLE_OUTPUT_DIR", "caller".to_string())];
+        let (result, lines) = run(dir.path(), &[custom, plain, other_stage], &env).await;
+        assert_eq!(result.unwrap().len(), 2);
+        let project_line = format!("caller {}", dir.path().display());
+        assert_eq!(texts(&lines), ["pre-build mine hook", project_line.as_str()]);
+    }
+
+    #[tokio::test]
+    async fn failing_hook_aborts_or_continues() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut lenient = hook(HookStage::PreBuild, "exit 3");
+        lenient.abort_on_failure = false;
+        let strict = hook(HookStage::PreBuild, "echo strict; exit 4");
+        let after = hook(HookStage::PreBuild, "echo after");
+
+        let (result, lines) = run(dir.path(), &[lenient.clone(), after.clone()], &[]).await;
+        let runs = result.unwrap();
+        assert!(!runs[0].success);
+        assert_eq!(runs[0].exit_code, Some(3));
+        assert!(runs[1].success);
+        assert_eq!(texts(&lines), ["after"]);
+
+        let (result, lines) = run(dir.path(), &[strict, after], &[]).await;
+        let error = result.unwrap_err().to_string();
+        assert!(error.contains("pre-build hook `echo strict; exit 4` exited with"), "{}", error);
+        // 中止后不再运行后面的钩子
+        assert_eq!(texts(&lines), ["strict"]);
+    }
+
+    #[tokio::test]
+    async fn hook_is_killed_after_timeout() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut slow = hook(HookStage::PreBuild, "echo started; sleep 30");
+        slow.timeout_secs = 1;
+
+        let started = Instant::now();
+        let (result, lines) = run(dir.path(), std::slice::from_ref(&slow), &[]).await;
+        let error = result.unwrap_err().to_string();
+        assert!(error.contains("timed out after 1s"), "{}", error);
+        assert!(started.elapsed() < Duration::from_secs(10));
+        assert_eq!(texts(&lines), ["started"]);
+
+        slow.abort_on_failure = false;
+        let (result, _) = run(dir.path(), &[slow], &[]).await;
+        let runs = result.unwrap();
+        assert!(!runs[0].success);
+        assert_eq!(runs[0].exit_code, None);
+    }
+
+    #[tokio::test]
+    async fn output_is_streamed_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = hook(HookStage::PreBuild, "echo one; sleep 0.2; echo two >&2; sleep 0.2; echo three");
+        let second = hook(HookStage::PreBuild, "printf 'four\\nfive'");
+
+        let (result, lines) = run(dir.path(), &[first, second], &[]).await;
+        result.unwrap();
+        assert_eq!(
+            lines,
+            [
+                (OutputStream::Stdout, "one".to_string()),
+                (OutputStream::Stderr, "two".to_string()),
+                (OutputStream::Stdout, "three".to_string()),
+                (OutputStream::Stdout, "four".to_string()),
+                // 没有换行结尾的最后一行也要输出
+                (OutputStream::Stdout, "five".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,484 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use chrono::Utc;
+use reqwest::{Client, Method, StatusCode, Url};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use crate::db::open_project_db;
+use crate::error::{AppError, Result};
+use crate::links::{is_external, markdown_links};
+use crate::markdown::MarkdownConfig;
+use crate::utils::walk_files;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExternalCheckOptions {
+    pub timeout_secs: u64,
+    /// 超过这个耗时的链接报告为慢链接
+    pub slow_threshold_ms: u64,
+    pub max_concurrency: usize,
+    /// 同一主机同时进行的请求数
+    pub per_host_concurrency: usize,
+    /// 同一主机两次请求之间的间隔
+    pub per_host_delay_ms: u64,
+    /// 缓存的检查结果在这段时间内直接使用；为 0 时总是重新检查
+    pub cache_ttl_hours: i64,
+    pub user_agent: String,
+}
+
+impl Default for ExternalCheckOptions {
+    fn default() -> Self {
+        Self {
+            timeout_secs: 10,
+            slow_threshold_ms: 3000,
+            max_concurrency: 8,
+            per_host_concurrency: 2,
+            per_host_delay_ms: 250,
+            cache_ttl_hours: 24,
+            user_agent: format!("Pineapple-LinkChecker/{}", env!("CARGO_PKG_VERSION")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExternalLinkState {
+    Ok,
+    Redirected,
+    Slow,
+    Dead,
+}
+
+/// 一个 URL 的检查结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UrlCheck {
+    pub url: String,
+    pub state: ExternalLinkState,
+    pub status_code: Option<u16>,
+    /// 跟随重定向后的最终地址，与原地址相同时为 None
+    pub final_url: Option<String>,
+    pub elapsed_ms: u64,
+    pub error: Option<String>,
+    /// 是否来自缓存
+    pub cached: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExternalLinkProblem {
+    pub line: usize,
+    #[serde(flatten)]
+    pub check: UrlCheck,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PostLinkProblems {
+    /// 相对于站点根目录，使用 `/` 分隔
+    pub source: String,
+    pub problems: Vec<ExternalLinkProblem>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ExternalLinkReport {
+    /// 不同 URL 的数量
+    pub checked: usize,
+    pub from_cache: usize,
+    pub dead: usize,
+    pub redirected: usize,
+    pub slow: usize,
+    pub posts: Vec<PostLinkProblems>,
+}
+
+/// 检查内容目录中所有文章引用的外部链接，结果缓存在 `.pineapple/project.db`
+pub async fn check_external_links(
+    project_path: &Path,
+    config: &MarkdownConfig,
+    options: &ExternalCheckOptions,
+) -> Result<ExternalLinkReport> {
+    // URL → 出现的位置（文件、行号）
+    let mut occurrences: BTreeMap<String, Vec<(String, usize)>> = BTreeMap::new();
+    for file in walk_files(&project_path.join("content")) {
+        if !matches!(file.extension().and_then(|e| e.to_str()), Some("md") | Some("markdown")) {
+            continue;
+        }
+        let Ok(content) = fs::read_to_string(&file) else {
+            continue;
+        };
+        let source = file
+            .strip_prefix(project_path)
+            .unwrap_or(&file)
+            .to_string_lossy()
+            .replace('\\', "/");
+        for link in markdown_links(&content, config) {
+            if is_http_url(&link.target) {
+                occurrences
+                    .entry(link.target)
+                    .or_default()
+                    .push((source.clone(), link.line));
+            }
+        }
+    }
+
+    let mut results: HashMap<String, UrlCheck> = HashMap::new();
+    let pending: Vec<String> = {
+        let conn = open_project_db(project_path)?;
+        init_schema(&conn)?;
+        for url in occurrences.keys() {
+            if let Some(check) = cached_check(&conn, url, options.cache_ttl_hours)? {
+                results.insert(url.clone(), check);
+            }
+        }
+        occurrences
+            .keys()
+            .filter(|url| !results.contains_key(*url))
+            .cloned()
+            .collect()
+    };
+
+    let client = build_client(options)?;
+    let fresh = check_urls(&client, pending, options).await;
+    {
+        let conn = open_project_db(project_path)?;
+        for check in &fresh {
+            store_check(&conn, check)?;
+        }
+    }
+    results.extend(fresh.into_iter().map(|check| (check.url.clone(), check)));
+
+    let mut report = ExternalLinkReport {
+        checked: results.len(),
+        from_cache: results.values().filter(|c| c.cached).count(),
+        ..Default::default()
+    };
+    for check in results.values() {
+        match check.state {
+            ExternalLinkState::Dead => report.dead += 1,
+            ExternalLinkState::Redirected => report.redirected += 1,
+            ExternalLinkState::Slow => report.slow += 1,
+            ExternalLinkState::Ok => {}
+        }
+    }
+
+    let mut posts: BTreeMap<String, Vec<ExternalLinkProblem>> = BTreeMap::new();
+    for (url, places) in &occurrences {
+        let Some(check) = results.get(url).filter(|c| c.state != ExternalLinkState::Ok) else {
+            continue;
+        };
+        for (source, line) in places {
+            posts.entry(source.clone()).or_default().push(ExternalLinkProblem {
+                line: *line,
+                check: check.clone(),
+            });
+        }
+    }
+    report.posts = posts
+        .into_iter()
+        .map(|(source, mut problems)| {
+            problems.sort_by_key(|p| p.line);
+            PostLinkProblems { source, problems }
+        })
+        .collect();
+
+    Ok(report)
+}
+
+pub fn build_client(options: &ExternalCheckOptions) -> Result<Client> {
+    Client::builder()
+        .timeout(Duration::from_secs(options.timeout_secs.max(1)))
+        .user_agent(options.user_agent.clone())
+        .build()
+        .map_err(|e| AppError::EngineError(e.to_string()))
+}
+
+/// 并发检查一组 URL：总并发数和每个主机的并发数都有限制，同一主机的请求之间有间隔
+pub async fn check_urls(client: &Client, urls: Vec<String>, options: &ExternalCheckOptions) -> Vec<UrlCheck> {
+    let global = Arc::new(Semaphore::new(options.max_concurrency.max(1)));
+    let mut hosts: HashMap<String, Arc<Semaphore>> = HashMap::new();
+    let mut tasks = JoinSet::new();
+
+    for url in urls {
+        let host = Url::parse(&url)
+            .ok()
+            .and_then(|u| u.host_str().map(str::to_string))
+            .unwrap_or_default();
+        let host_limit = hosts
+            .entry(host)
+            .or_insert_with(|| Arc::new(Semaphore::new(options.per_host_concurrency.max(1))))
+            .clone();
+        let global = global.clone();
+        let client = client.clone();
+        let slow_threshold = Duration::from_millis(options.slow_threshold_ms);
+        let delay = Duration::from_millis(options.per_host_delay_ms);
+
+        tasks.spawn(async move {
+            let _host_permit = host_limit.acquire_owned().await;
+            let check = {
+                let _permit = global.acquire_owned().await;
+                check_url(&client, &url, slow_threshold).await
+            };
+            // 释放主机许可前等待，避免短时间内向同一主机发出大量请求
+            tokio::time::sleep(delay).await;
+            check
+        });
+    }
+
+    let mut results = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        if let Ok(check) = joined {
+            results.push(check);
+        }
+    }
+    results.sort_by(|a, b| a.url.cmp(&b.url));
+    results
+}
+
+/// 先发 HEAD 请求；失败或返回错误状态时再用 GET 确认（很多服务器不支持 HEAD）
+pub async fn check_url(client: &Client, url: &str, slow_threshold: Duration) -> UrlCheck {
+    let started = Instant::now();
+    let mut outcome = request(client, Method::HEAD, url).await;
+    let head_failed = match &outcome {
+        Ok((status, _)) => status.is_client_error() || status.is_server_error(),
+        Err(_) => true,
+    };
+    if head_failed {
+        outcome = request(client, Method::GET, url).await;
+    }
+    let elapsed = started.elapsed();
+
+    let mut check = UrlCheck {
+        url: url.to_string(),
+        state: ExternalLinkState::Ok,
+        status_code: None,
+        final_url: None,
+        elapsed_ms: elapsed.as_millis() as u64,
+        error: None,
+        cached: false,
+    };
+
+    match outcome {
+        Ok((status, final_url)) => {
+            check.status_code = Some(status.as_u16());
+            if without_fragment(&final_url) != without_fragment(url) {
+                check.final_url = Some(final_url);
+            }
+            check.state = if status.is_client_error() || status.is_server_error() {
+                ExternalLinkState::Dead
+            } else if check.final_url.is_some() {
+                ExternalLinkState::Redirected
+            } else if elapsed > slow_threshold {
+                ExternalLinkState::Slow
+            } else {
+                ExternalLinkState::Ok
+            };
+        }
+        Err(error) => {
+            check.state = ExternalLinkState::Dead;
+            check.error = Some(error);
+        }
+    }
+
+    check
+}
+
+async fn request(client: &Client, method: Method, url: &str) -> std::result::Result<(StatusCode, String), String> {
+    let response = client
+        .request(method, url)
+        .send()
+        .await
+        .map_err(|e| if e.is_timeout() { "timeout".to_string() } else { e.to_string() })?;
+    Ok((response.status(), response.url().to_string()))
+}
+
+fn init_schema(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS link_cache (
+            url TEXT PRIMARY KEY,
+            state TEXT NOT NULL,
+            status_code INTEGER,
+            final_url TEXT,
+            elapsed_ms INTEGER NOT NULL,
+            error TEXT,
+            checked_at INTEGER NOT NULL
+        );",
+    )?;
+    Ok(())
+}
+
+fn cached_check(conn: &Connection, url: &str, ttl_hours: i64) -> Result<Option<UrlCheck>> {
+    if ttl_hours <= 0 {
+        return Ok(None);
+    }
+    let oldest = Utc::now().timestamp() - ttl_hours * 3600;
+    let row = conn
+        .query_row(
+            "SELECT state, status_code, final_url, elapsed_ms, error
+             FROM link_cache WHERE url = ?1 AND checked_at >= ?2",
+            params![url, oldest],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, Option<u16>>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, i64>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                ))
+            },
+        )
+        .optional()?;
+
+    Ok(row.and_then(|(state, status_code, final_url, elapsed_ms, error)| {
+        Some(UrlCheck {
+            url: url.to_string(),
+            state: serde_json::from_value(serde_json::Value::String(state)).ok()?,
+            status_code,
+            final_url,
+            elapsed_ms: elapsed_ms as u64,
+            error,
+            cached: true,
+        })
+    }))
+}
+
+fn store_check(conn: &Connection, check: &UrlCheck) -> Result<()> {
+    let state = serde_json::to_value(check.state)?;
+    conn.execute(
+        "INSERT OR REPLACE INTO link_cache (url, state, status_code, final_url, elapsed_ms, error, checked_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            check.url,
+            state.as_str().unwrap_or_default(),
+            check.status_code,
+            check.final_url,
+            check.elapsed_ms as i64,
+            check.error,
+            Utc::now().timestamp(),
+        ],
+    )?;
+    Ok(())
+}
+
+/// 规范化后去掉锚点，`https://a.com` 与 `https://a.com/` 视为同一地址
+fn without_fragment(url: &str) -> String {
+    match Url::parse(url) {
+        Ok(mut parsed) => {
+            parsed.set_fragment(None);
+            parsed.to_string()
+        }
+        Err(_) => url.to_string(),
+    }
+}
+
+fn is_http_url(target: &str) -> bool {
+    is_external(target) && (target.starts_with("http://") || target.starts_with("https://"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{MockResponse, MockServer};
+
+    fn options() -> ExternalCheckOptions {
+        ExternalCheckOptions {
+            timeout_secs: 5,
+            slow_threshold_ms: 200,
+            per_host_delay_ms: 0,
+            ..ExternalCheckOptions::default()
+        }
+    }
+
+    async fn server() -> MockServer {
+        MockServer::start(|request| match (request.method.as_str(), request.path.as_str()) {
+            ("HEAD", "/no-head") => MockResponse::status(405),
+            (_, "/no-head") => MockResponse::status(200),
+            (_, "/old") => MockResponse::status(301).header("Location", "/new"),
+            (_, "/new") => MockResponse::status(200),
+            (_, "/slow") => MockResponse::status(200).delay(Duration::from_millis(400)),
+            (_, path) if path.starts_with("/page/") => MockResponse::status(200).delay(Duration::from_millis(150)),
+            _ => MockResponse::status(404),
+        })
+        .await
+    }
+
+    async fn check(server: &MockServer, path: &str) -> UrlCheck {
+        let options = options();
+        let client = build_client(&options).unwrap();
+        let url = format!("{}{}", server.url(), path);
+        check_url(&client, &url, Duration::from_millis(options.slow_threshold_ms)).await
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_get_when_head_is_rejected() {
+        let server = server().await;
+        let check = check(&server, "/no-head").await;
+        assert_eq!((check.state, check.status_code), (ExternalLinkState::Ok, Some(200)));
+        let methods: Vec<String> = server.requests().into_iter().map(|r| r.method).collect();
+        assert_eq!(methods, ["HEAD", "GET"]);
+
+        let check = self::check(&server, "/missing").await;
+        assert_eq!((check.state, check.status_code), (ExternalLinkState::Dead, Some(404)));
+    }
+
+    #[tokio::test]
+    async fn classifies_redirects_and_slow_links() {
+        let server = server().await;
+        let check = check(&server, "/old").await;
+        assert_eq!(check.state, ExternalLinkState::Redirected);
+        assert_eq!(check.final_url, Some(format!("{}/new", server.url())));
+
+        let check = self::check(&server, "/slow").await;
+        assert_eq!(check.state, ExternalLinkState::Slow);
+        assert!(check.elapsed_ms >= 400);
+
+        let check = self::check(&server, "/new#section").await;
+        assert_eq!((check.state, check.final_url), (ExternalLinkState::Ok, None));
+    }
+
+    #[tokio::test]
+    async fn limits_concurrent_requests_per_host() {
+        let server = server().await;
+        let options = ExternalCheckOptions {
+            per_host_concurrency: 2,
+            slow_threshold_ms: 5000,
+            ..options()
+        };
+        let urls: Vec<String> = (0..6).map(|i| format!("{}/page/{}", server.url(), i)).collect();
+        let checks = check_urls(&build_client(&options).unwrap(), urls, &options).await;
+        assert_eq!(checks.len(), 6);
+        assert!(checks.iter().all(|c| c.state == ExternalLinkState::Ok));
+        assert_eq!(server.max_in_flight(), 2);
+    }
+
+    #[tokio::test]
+    async fn reuses_cached_results() {
+        let server = server().await;
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("content")).unwrap();
+        fs::write(
+            dir.path().join("content/post.md"),
+            format!("[ok]({0}/new)\n\n[gone]({0}/missing)\n", server.url()),
+        )
+        .unwrap();
+        let config = MarkdownConfig::default();
+
+        let report = check_external_links(dir.path(), &config, &options()).await.unwrap();
+        assert_eq!((report.checked, report.from_cache, report.dead), (2, 0, 1));
+        assert_eq!(report.posts[0].problems[0].line, 3);
+        let requests = server.requests().len();
+
+        let report = check_external_links(dir.path(), &config, &options()).await.unwrap();
+        assert_eq!((report.checked, report.from_cache, report.dead), (2, 2, 1));
+        assert_eq!(server.requests().len(), requests);
+
+        let uncached = ExternalCheckOptions {
+            cache_ttl_hours: 0,
+            ..options()
+        };
+        let report = check_external_links(dir.path(), &config, &uncached).await.unwrap();
+        assert_eq!(report.from_cache, 0);
+        assert!(server.requests().len() > requests);
+    }
+}
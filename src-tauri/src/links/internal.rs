@@ -0,0 +1,755 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::OnceLock;
+use regex::Regex;
+use serde_json::Value;
+use crate::error::Result;
+use crate::front_matter::{get_field, parse_front_matter, split_front_matter};
+use crate::links::{
+    html_attribute_links, is_external, markdown_links, split_anchor, BrokenLink, BrokenReason,
+    FoundLink, LinkKind, LinkReport,
+};
+use crate::markdown::line_map::LineIndex;
+use crate::markdown::shortcodes::ref_url;
+use crate::markdown::{render_document, MarkdownConfig};
+use crate::models::EngineType;
+use crate::site_config::{config_path, load_site_config};
+use crate::taxonomy::{page_terms, taxonomy_names};
+use crate::utils::{percent_decode, read_site_base_url, walk_files};
+
+/// 构建时自动生成、在内容目录中找不到对应文件的页面
+const GENERATED_FILES: &[&str] = &[
+    "index.xml", "rss.xml", "atom.xml", "feed.xml", "sitemap.xml", "robots.txt", "404.html",
+];
+
+/// 检查站内链接：先按内容目录和 `static/` 解析文章中的链接，
+/// 再检查构建输出目录（存在时）中每个 HTML 页面的 `href` / `src`。
+///
+/// 内容检查按站点的永久链接规则计算页面地址（考虑 `permalinks`、`url`、`slug`、`aliases`），
+/// `permalinks` 中用到无法解析的占位符时以构建输出的检查结果为准。
+pub fn check_internal_links(
+    project_path: &Path,
+    config: &MarkdownConfig,
+    output_dir: Option<&Path>,
+) -> Result<LinkReport> {
+    let site = SiteIndex::build(project_path, config)?;
+    let mut report = LinkReport::default();
+
+    for (index, page) in site.pages.iter().enumerate() {
+        let Ok(content) = fs::read_to_string(&page.file) else {
+            continue;
+        };
+        for link in markdown_links(&content, config) {
+            if let Some(reason) = site.check(index, &link, config) {
+                report.broken.push(BrokenLink {
+                    source: site.relative(&page.file),
+                    line: link.line,
+                    target: link.target,
+                    kind: link.kind,
+                    reason,
+                });
+            }
+            report.checked += 1;
+        }
+    }
+
+    if let Some(output_dir) = output_dir.filter(|dir| dir.is_dir()) {
+        check_output(project_path, output_dir, &site.base_path, &mut report);
+    }
+
+    Ok(report)
+}
+
+pub(crate) struct Page {
+    pub file: PathBuf,
+    pub url: String,
+}
+
+/// 内容目录中的页面、资源文件和自动生成页面的地址表
+pub(crate) struct SiteIndex {
+    project_path: PathBuf,
+    pub content_dir: PathBuf,
+    pub pages: Vec<Page>,
+    /// 地址 → 页面在 `pages` 中的序号
+    urls: HashMap<String, usize>,
+    /// 页面包中的资源、`static/` 中的文件、分区和分类法页面
+    other_urls: HashSet<String>,
+    /// baseURL 中的路径部分，例如 `/blog`
+    pub base_path: String,
+    pub permalinks: Permalinks,
+    anchors: std::cell::RefCell<HashMap<usize, HashSet<String>>>,
+}
+
+impl SiteIndex {
+    pub fn build(project_path: &Path, config: &MarkdownConfig) -> Result<Self> {
+        let content_dir = project_path.join("content");
+        let site_config = load_site_config(project_path)?;
+        let taxonomies = taxonomy_names(&config.engine, &site_config);
+
+        let mut index = Self {
+            project_path: project_path.to_path_buf(),
+            content_dir: content_dir.clone(),
+            pages: Vec::new(),
+            urls: HashMap::new(),
+            other_urls: HashSet::from(["/".to_string()]),
+            base_path: read_site_base_url(project_path)
+                .map(|url| base_path(&url))
+                .unwrap_or_default(),
+            permalinks: Permalinks::from_config(&config.engine, &site_config),
+            anchors: Default::default(),
+        };
+
+        for file in walk_files(&content_dir) {
+            let relative = index.content_relative(&file);
+            if !is_markdown(&file) {
+                index.other_urls.insert(format!("/{}", relative));
+                continue;
+            }
+
+            let front_matter = fs::read_to_string(&file)
+                .ok()
+                .and_then(|content| {
+                    let block = split_front_matter(&content).front_matter?;
+                    parse_front_matter(&block).ok()
+                })
+                .unwrap_or(Value::Null);
+
+            let url = page_url(&relative, &front_matter, &index.permalinks);
+            let page = index.pages.len();
+            index.urls.insert(url.clone(), page);
+            if let Some(Value::Array(aliases)) = get_field(&front_matter, "aliases") {
+                for alias in aliases.iter().filter_map(Value::as_str) {
+                    index.urls.insert(normalize_url(alias), page);
+                }
+            }
+
+            for (taxonomy, terms) in page_terms(&config.engine, &front_matter, &taxonomies) {
+                index.other_urls.insert(format!("/{}/", taxonomy));
+                for term in terms {
+                    index.other_urls.insert(format!("/{}/{}/", taxonomy, urlize(&term)));
+                }
+            }
+
+            index.pages.push(Page { file, url });
+        }
+
+        // 内容目录下的每个目录都可能是一个分区列表页
+        let dirs: HashSet<String> = index
+            .pages
+            .iter()
+            .filter_map(|page| page.file.parent())
+            .filter_map(|dir| dir.strip_prefix(&content_dir).ok())
+            .flat_map(|dir| dir.ancestors().map(Path::to_path_buf).collect::<Vec<_>>())
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .map(|dir| format!("/{}/", dir.to_string_lossy().replace('\\', "/")))
+            .collect();
+        index.other_urls.extend(dirs);
+
+        let mut static_dirs = vec![project_path.join("static")];
+        if let Ok(themes) = fs::read_dir(project_path.join("themes")) {
+            static_dirs.extend(themes.flatten().map(|theme| theme.path().join("static")));
+        }
+        for dir in static_dirs {
+            for file in walk_files(&dir) {
+                if let Ok(relative) = file.strip_prefix(&dir) {
+                    index
+                        .other_urls
+                        .insert(format!("/{}", relative.to_string_lossy().replace('\\', "/")));
+                }
+            }
+        }
+
+        Ok(index)
+    }
+
+    pub fn page_index(&self, file: &Path) -> Option<usize> {
+        self.pages.iter().position(|page| page.file == file)
+    }
+
+    fn check(&self, page_index: usize, link: &FoundLink, config: &MarkdownConfig) -> Option<BrokenReason> {
+        let resolved = match self.resolve(page_index, link)? {
+            Ok(resolved) => resolved,
+            Err(reason) => return Some(reason),
+        };
+        let (_, anchor) = split_anchor(link.target.trim());
+
+        match (resolved, anchor) {
+            (Resolved::Page(target_page), Some(anchor)) if !self.has_anchor(target_page, anchor, config) => {
+                Some(BrokenReason::MissingAnchor)
+            }
+            (Resolved::Missing, _) => Some(BrokenReason::MissingTarget),
+            _ => None,
+        }
+    }
+
+    /// 解析 `page_index` 页面中的链接；外部链接和模板表达式返回 None
+    pub fn resolve(&self, page_index: usize, link: &FoundLink) -> Option<std::result::Result<Resolved, BrokenReason>> {
+        let target = link.target.trim();
+        if target.is_empty() || is_external(target) || target.starts_with("{{") {
+            return None;
+        }
+
+        let page = &self.pages[page_index];
+        let (path, _) = split_anchor(target);
+
+        let resolved = if path.is_empty() {
+            Resolved::Page(page_index)
+        } else if link.kind == LinkKind::Ref {
+            return Some(self.resolve_ref(page, path));
+        } else if let Some(zola_path) = path.strip_prefix("@/") {
+            self.resolve_content_file(&self.content_dir.join(zola_path))
+                .unwrap_or(Resolved::Missing)
+        } else {
+            let decoded = percent_decode(path).unwrap_or_else(|| path.to_string());
+            self.resolve_path(page, &decoded)
+        };
+        Some(Ok(resolved))
+    }
+
+    /// 普通链接：先按文件相对路径解析（页面包中的图片、`../other.md`），再按页面地址解析
+    fn resolve_path(&self, page: &Page, path: &str) -> Resolved {
+        if !path.starts_with('/') {
+            let in_content = page.file.parent().and_then(|dir| self.resolve_content_file(&dir.join(path)));
+            if let Some(resolved) = in_content.filter(|r| *r != Resolved::Missing) {
+                return resolved;
+            }
+        }
+
+        let url = if path.starts_with('/') {
+            let without_base = path.strip_prefix(self.base_path.as_str()).filter(|_| !self.base_path.is_empty());
+            without_base.unwrap_or(path).to_string()
+        } else {
+            join_url(&page.url, path)
+        };
+        self.lookup_url(&url)
+    }
+
+    /// `ref`/`relref`：相对当前文件、相对内容目录，最后按文件名查找
+    fn resolve_ref(&self, page: &Page, path: &str) -> std::result::Result<Resolved, BrokenReason> {
+        let mut candidates = Vec::new();
+        if let Some(relative) = path.strip_prefix('/') {
+            candidates.push(self.content_dir.join(relative));
+        } else {
+            if let Some(dir) = page.file.parent() {
+                candidates.push(dir.join(path));
+            }
+            candidates.push(self.content_dir.join(path));
+        }
+
+        for candidate in candidates {
+            match self.resolve_content_file(&candidate) {
+                Some(Resolved::Missing) | None => {}
+                Some(resolved) => return Ok(resolved),
+            }
+        }
+
+        let name = Path::new(path);
+        let matches: Vec<usize> = self
+            .pages
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| {
+                p.file.file_name() == name.file_name()
+                    || (name.extension().is_none() && p.file.file_stem() == name.file_name())
+            })
+            .map(|(i, _)| i)
+            .collect();
+        match matches.as_slice() {
+            [page] => Ok(Resolved::Page(*page)),
+            [] => Err(BrokenReason::MissingTarget),
+            _ => Err(BrokenReason::AmbiguousRef),
+        }
+    }
+
+    /// 把文件系统路径解析为页面或资源；不在内容目录中时返回 None
+    fn resolve_content_file(&self, path: &Path) -> Option<Resolved> {
+        let path = normalize_path(path);
+        if !path.starts_with(&self.content_dir) {
+            return None;
+        }
+
+        let mut candidates = vec![path.clone()];
+        if path.extension().is_none() {
+            candidates.push(path.with_extension("md"));
+        }
+        candidates.push(path.join("index.md"));
+        candidates.push(path.join("_index.md"));
+
+        for candidate in candidates {
+            if let Some(page) = self.pages.iter().position(|p| p.file == candidate) {
+                return Some(Resolved::Page(page));
+            }
+            if candidate.is_file() {
+                return Some(Resolved::Asset);
+            }
+        }
+        Some(Resolved::Missing)
+    }
+
+    fn lookup_url(&self, url: &str) -> Resolved {
+        let url = normalize_url(url);
+        let trimmed = url.strip_suffix("index.html").unwrap_or(&url);
+        let without_slash = url.trim_end_matches('/');
+
+        for candidate in [url.as_str(), trimmed, without_slash] {
+            if let Some(&page) = self.urls.get(candidate) {
+                return Resolved::Page(page);
+            }
+            if self.other_urls.contains(candidate) {
+                return Resolved::Asset;
+            }
+        }
+
+        let file_name = without_slash.rsplit('/').next().unwrap_or_default();
+        if GENERATED_FILES.contains(&file_name) {
+            return Resolved::Asset;
+        }
+        Resolved::Missing
+    }
+
+    fn has_anchor(&self, page: usize, anchor: &str, config: &MarkdownConfig) -> bool {
+        let anchor = percent_decode(anchor).unwrap_or_else(|| anchor.to_string());
+        let mut cache = self.anchors.borrow_mut();
+        let anchors = cache.entry(page).or_insert_with(|| {
+            let content = fs::read_to_string(&self.pages[page].file).unwrap_or_default();
+            let mut anchors: HashSet<String> = render_document(&content, config)
+                .headings
+                .into_iter()
+                .filter_map(|heading| heading.id)
+                .collect();
+            anchors.extend(html_ids(&content));
+            anchors
+        });
+        anchors.contains(&anchor)
+    }
+
+    fn content_relative(&self, file: &Path) -> String {
+        file.strip_prefix(&self.content_dir)
+            .unwrap_or(file)
+            .to_string_lossy()
+            .replace('\\', "/")
+    }
+
+    fn relative(&self, file: &Path) -> String {
+        file.strip_prefix(&self.project_path)
+            .unwrap_or(file)
+            .to_string_lossy()
+            .replace('\\', "/")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Resolved {
+    Page(usize),
+    Asset,
+    Missing,
+}
+
+/// 检查构建输出中每个 HTML 页面的站内链接，目标文件或锚点不存在时报告
+fn check_output(project_path: &Path, output_dir: &Path, base_path: &str, report: &mut LinkReport) {
+    let base_url = read_site_base_url(project_path).map(|url| url.trim_end_matches('/').to_string());
+    let mut ids: HashMap<PathBuf, HashSet<String>> = HashMap::new();
+
+    for file in walk_files(output_dir) {
+        if file.extension().and_then(|e| e.to_str()) != Some("html") {
+            continue;
+        }
+        let Ok(html) = fs::read_to_string(&file) else {
+            continue;
+        };
+        let page_url = format!(
+            "/{}",
+            file.strip_prefix(output_dir).unwrap_or(&file).to_string_lossy().replace('\\', "/")
+        );
+        let index = LineIndex::new(&html);
+        let mut seen = HashSet::new();
+
+        for (offset, target) in html_attribute_links(&html) {
+            // 指向站点自身 baseURL 的绝对地址也是站内链接
+            let local = match &base_url {
+                Some(base) if !base.is_empty() && target.starts_with(base.as_str()) => {
+                    format!("/{}", target[base.len()..].trim_start_matches('/'))
+                }
+                _ => target.clone(),
+            };
+            if local.is_empty() || is_external(&local) || !seen.insert(target.clone()) {
+                continue;
+            }
+            report.checked += 1;
+
+            let (path, anchor) = split_anchor(&local);
+            let decoded = percent_decode(path).unwrap_or_else(|| path.to_string());
+            let url = if decoded.is_empty() {
+                page_url.clone()
+            } else if decoded.starts_with('/') {
+                decoded
+                    .strip_prefix(base_path)
+                    .filter(|_| !base_path.is_empty())
+                    .unwrap_or(&decoded)
+                    .to_string()
+            } else {
+                join_url(&page_url, &decoded)
+            };
+
+            let mut target_file = normalize_path(&output_dir.join(url.trim_start_matches('/')));
+            if target_file.is_dir() {
+                target_file = target_file.join("index.html");
+            }
+
+            let reason = if !target_file.starts_with(output_dir) || !target_file.is_file() {
+                Some(BrokenReason::MissingTarget)
+            } else {
+                anchor
+                    .filter(|_| target_file.extension().and_then(|e| e.to_str()) == Some("html"))
+                    .filter(|anchor| {
+                        let ids = ids.entry(target_file.clone()).or_insert_with(|| {
+                            html_ids(&fs::read_to_string(&target_file).unwrap_or_default())
+                        });
+                        let anchor = percent_decode(anchor).unwrap_or_else(|| anchor.to_string());
+                        !ids.contains(&anchor)
+                    })
+                    .map(|_| BrokenReason::MissingAnchor)
+            };
+
+            if let Some(reason) = reason {
+                report.broken.push(BrokenLink {
+                    source: file
+                        .strip_prefix(project_path)
+                        .unwrap_or(&file)
+                        .to_string_lossy()
+                        .replace('\\', "/"),
+                    line: index.line_of(offset) + 1,
+                    target,
+                    kind: LinkKind::Html,
+                    reason,
+                });
+            }
+        }
+    }
+}
+
+fn html_ids(html: &str) -> HashSet<String> {
+    static ID: OnceLock<Regex> = OnceLock::new();
+    let re = ID.get_or_init(|| Regex::new(r#"(?i)\s(?:id|name)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap());
+    re.captures_iter(html)
+        .filter_map(|caps| caps.get(1).or_else(|| caps.get(2)))
+        .map(|m| m.as_str().to_string())
+        .collect()
+}
+
+/// 与 Hugo 的 urlize 一致：小写，空白换成 `-`
+fn urlize(term: &str) -> String {
+    term.trim().to_lowercase().split_whitespace().collect::<Vec<_>>().join("-")
+}
+
+/// 按永久链接规则计算页面地址：`slug` 替换最后一段，分区配置了 `permalinks` 时按模式生成，
+/// Hugo 的 `url`、Zola 的 `path` 覆盖整个地址
+pub(crate) fn page_url(content_relative: &str, front_matter: &Value, permalinks: &Permalinks) -> String {
+    let explicit = get_field(front_matter, "url").or_else(|| get_field(front_matter, "path"));
+    if let Some(explicit) = explicit.and_then(Value::as_str) {
+        return normalize_url(explicit);
+    }
+    if let Some(Ok(url)) = permalinks.expand(content_relative, front_matter) {
+        return url;
+    }
+    let mut url = ref_url(content_relative);
+    if let Some(slug) = get_field(front_matter, "slug").and_then(Value::as_str) {
+        url = replace_last_segment(&url, slug);
+    }
+    url
+}
+
+/// Hugo `[permalinks]` 中按分区配置的地址模式，例如 `posts = "/:year/:month/:slug/"`
+#[derive(Debug, Default)]
+pub(crate) struct Permalinks {
+    patterns: HashMap<String, String>,
+}
+
+impl Permalinks {
+    /// 同时支持旧写法 `[permalinks] posts = ...` 和按页面类型分组的 `[permalinks.page]`；
+    /// Zola 没有对应配置
+    pub fn from_config(engine: &EngineType, site_config: &Value) -> Self {
+        let mut patterns = HashMap::new();
+        if !matches!(engine, EngineType::Hugo) {
+            return Self { patterns };
+        }
+        if let Some(Value::Object(map)) = config_path(site_config, &["permalinks"]) {
+            for (section, pattern) in map {
+                if let Some(pattern) = pattern.as_str() {
+                    patterns.insert(section.clone(), pattern.to_string());
+                }
+            }
+        }
+        if let Some(Value::Object(map)) = config_path(site_config, &["permalinks", "page"]) {
+            for (section, pattern) in map {
+                if let Some(pattern) = pattern.as_str() {
+                    patterns.insert(section.clone(), pattern.to_string());
+                }
+            }
+        }
+        Self { patterns }
+    }
+
+    /// 按所在分区的模式生成地址；分区没有配置模式时返回 None，
+    /// 用到不支持的占位符或缺少日期时返回 `Err(占位符)`
+    pub fn expand(&self, content_relative: &str, front_matter: &Value) -> Option<std::result::Result<String, String>> {
+        let path = Path::new(content_relative);
+        let name = path.file_name()?.to_str()?;
+        // 分区列表页不受文章模式影响
+        if name == "_index.md" {
+            return None;
+        }
+        let (section, _) = content_relative.split_once('/')?;
+        let pattern = self.patterns.get(section)?;
+
+        let bundle = matches!(name, "index.md" | "index.markdown");
+        let dir = path.parent().map(|d| d.to_string_lossy().replace('\\', "/")).unwrap_or_default();
+        let filename = if bundle {
+            dir.rsplit('/').next().unwrap_or_default().to_string()
+        } else {
+            path.file_stem().unwrap_or_default().to_string_lossy().to_string()
+        };
+        let sections = if bundle {
+            dir.rsplit_once('/').map(|(parent, _)| parent.to_string()).unwrap_or_default()
+        } else {
+            dir
+        };
+        let field = |key: &str| get_field(front_matter, key).and_then(Value::as_str).map(str::to_string);
+        let title = field("title").map(|title| urlize(&title));
+        let slug = field("slug").or_else(|| title.clone());
+        let date = field("date").and_then(|date| parse_date(&date));
+
+        static TOKEN: OnceLock<Regex> = OnceLock::new();
+        let re = TOKEN.get_or_init(|| Regex::new(r":[a-z]+(?:\[[^\]]*\])?").unwrap());
+        let mut url = String::new();
+        let mut last = 0;
+        for found in re.find_iter(pattern) {
+            url.push_str(&pattern[last..found.start()]);
+            last = found.end();
+            let token = found.as_str();
+            let value = match token {
+                ":year" => date.map(|(y, _, _)| format!("{:04}", y)),
+                ":month" => date.map(|(_, m, _)| format!("{:02}", m)),
+                ":monthname" => date.and_then(|(_, m, _)| MONTHS.get(m as usize - 1)).map(|m| m.to_lowercase()),
+                ":day" => date.map(|(_, _, d)| format!("{:02}", d)),
+                ":section" => Some(section.to_string()),
+                ":sections" => Some(sections.clone()),
+                ":title" => title.clone(),
+                ":slug" => slug.clone(),
+                ":filename" | ":contentbasename" => Some(filename.clone()),
+                ":slugorfilename" | ":slugorcontentbasename" => Some(field("slug").unwrap_or_else(|| filename.clone())),
+                _ => None,
+            };
+            match value {
+                Some(value) => url.push_str(&value),
+                None => return Some(Err(token.to_string())),
+            }
+        }
+        url.push_str(&pattern[last..]);
+
+        let mut url = normalize_url(&url);
+        if !url.ends_with('/') {
+            url.push('/');
+        }
+        Some(Ok(url))
+    }
+}
+
+const MONTHS: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June",
+    "July", "August", "September", "October", "November", "December",
+];
+
+/// 取 `2024-03-05T10:00:00Z` 这类日期的年、月、日
+fn parse_date(date: &str) -> Option<(u32, u32, u32)> {
+    let mut parts = date.get(..10)?.split('-');
+    let year = parts.next()?.parse().ok()?;
+    let month = parts.next()?.parse().ok().filter(|m| (1..=12).contains(m))?;
+    let day = parts.next()?.parse().ok().filter(|d| (1..=31).contains(d))?;
+    Some((year, month, day))
+}
+
+fn replace_last_segment(url: &str, slug: &str) -> String {
+    let trimmed = url.trim_end_matches('/');
+    match trimmed.rsplit_once('/') {
+        Some((parent, _)) => format!("{}/{}/", parent, slug),
+        None => format!("/{}/", slug),
+    }
+}
+
+fn base_path(base_url: &str) -> String {
+    let without_scheme = base_url.split_once("://").map(|(_, rest)| rest).unwrap_or(base_url);
+    match without_scheme.split_once('/') {
+        Some((_, path)) => format!("/{}", path.trim_matches('/')).trim_end_matches('/').to_string(),
+        None => String::new(),
+    }
+}
+
+/// 以 `/` 开头、合并 `.` 和 `..` 的地址
+pub(crate) fn normalize_url(url: &str) -> String {
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in url.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+    let mut normalized = format!("/{}", segments.join("/"));
+    if url.ends_with('/') && normalized.len() > 1 {
+        normalized.push('/');
+    }
+    normalized
+}
+
+/// 相对地址按所在页面的目录解析
+pub(crate) fn join_url(page_url: &str, relative: &str) -> String {
+    let dir = match page_url.rfind('/') {
+        Some(i) => &page_url[..=i],
+        None => "/",
+    };
+    normalize_url(&format!("{}{}", dir, relative))
+}
+
+pub(crate) fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::CurDir => {}
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+fn is_markdown(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|e| e.to_str()),
+        Some("md") | Some("markdown")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn site(files: &[(&str, &str)]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("config.toml"), "baseURL = \"https://example.com/\"\n").unwrap();
+        for (path, content) in files {
+            let file = dir.path().join(path);
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+            fs::write(file, content).unwrap();
+        }
+        dir
+    }
+
+    fn broken(report: &LinkReport) -> Vec<(String, usize, String, BrokenReason)> {
+        let mut broken: Vec<_> = report
+            .broken
+            .iter()
+            .map(|b| (b.source.clone(), b.line, b.target.clone(), b.reason))
+            .collect();
+        broken.sort_by(|a, b| (&a.0, a.1, &a.2).cmp(&(&b.0, b.1, &b.2)));
+        broken
+    }
+
+    fn entry(source: &str, line: usize, target: &str, reason: BrokenReason) -> (String, usize, String, BrokenReason) {
+        (source.to_string(), line, target.to_string(), reason)
+    }
+
+    #[test]
+    fn reports_broken_content_links_with_file_and_line() {
+        let dir = site(&[
+            ("content/posts/a.md", "+++\ntitle = \"A\"\n+++\n## Intro\n\nText <span id=\"raw\"></span>\n"),
+            (
+                "content/posts/b.md",
+                "+++\ntitle = \"B\"\n+++\n\
+                 [rel](a.md) [up](../posts/a.md#intro) [abs](/posts/a/#raw)\n\
+                 [missing](nope.md)\n\
+                 [anchor](a.md#nowhere)\n\
+                 ![logo](/images/logo.png) ![gone](/images/gone.png)\n\
+                 {{< ref \"a.md#intro\" >}} {{< relref \"/posts/bundle\" >}}\n\
+                 {{< ref \"missing.md\" >}} {{< ref \"index.md\" >}}\n\
+                 [cover](bundle/cover.png) [section](/posts/)\n",
+            ),
+            (
+                "content/posts/bundle/index.md",
+                "---\ntitle: Bundle\n---\n![cover](cover.png)\n\n![gone](gone.png)\n",
+            ),
+            ("content/posts/bundle/cover.png", ""),
+            ("content/about/index.md", "---\ntitle: About\n---\n"),
+            ("static/images/logo.png", ""),
+        ]);
+
+        let report = check_internal_links(dir.path(), &MarkdownConfig::default(), None).unwrap();
+        assert_eq!(
+            broken(&report),
+            [
+                entry("content/posts/b.md", 5, "nope.md", BrokenReason::MissingTarget),
+                entry("content/posts/b.md", 6, "a.md#nowhere", BrokenReason::MissingAnchor),
+                entry("content/posts/b.md", 7, "/images/gone.png", BrokenReason::MissingTarget),
+                entry("content/posts/b.md", 9, "index.md", BrokenReason::AmbiguousRef),
+                entry("content/posts/b.md", 9, "missing.md", BrokenReason::MissingTarget),
+                entry("content/posts/bundle/index.md", 6, "gone.png", BrokenReason::MissingTarget),
+            ]
+        );
+        assert_eq!(report.checked, 15);
+    }
+
+    #[test]
+    fn follows_slugs_aliases_and_taxonomy_pages() {
+        let dir = site(&[
+            (
+                "content/posts/a.md",
+                "+++\ntitle = \"A\"\nslug = \"renamed\"\naliases = [\"/old/\"]\ntags = [\"Rust Lang\"]\n+++\n",
+            ),
+            (
+                "content/b.md",
+                "+++\ntitle = \"B\"\n+++\n[slug](/posts/renamed/) [alias](/old/) [tag](/tags/rust-lang/)\n[stale](/posts/a/)\n",
+            ),
+        ]);
+
+        let report = check_internal_links(dir.path(), &MarkdownConfig::default(), None).unwrap();
+        assert_eq!(
+            broken(&report),
+            [entry("content/b.md", 5, "/posts/a/", BrokenReason::MissingTarget)]
+        );
+    }
+
+    #[test]
+    fn crawls_the_build_output() {
+        let dir = site(&[
+            ("content/posts/a.md", "+++\ntitle = \"A\"\n+++\n"),
+            (
+                "public/index.html",
+                "<html><body>\n\
+                 <a href=\"/posts/a/\">ok</a> <a href=\"posts/a/index.html#intro\">ok</a>\n\
+                 <a href=\"/posts/a/#missing\">anchor</a>\n\
+                 <img src=\"/img/none.png\">\n\
+                 <a href=\"https://example.com/posts/a/\">abs</a> <a href=\"https://example.com/nope/\">abs</a>\n\
+                 <a href=\"https://other.example/\">external</a>\n\
+                 </body></html>\n",
+            ),
+            ("public/posts/a/index.html", "<h2 id=\"intro\">Intro</h2>\n<a href=\"../../index.xml\">rss</a>\n"),
+        ]);
+
+        let public = dir.path().join("public");
+        let report = check_internal_links(dir.path(), &MarkdownConfig::default(), Some(&public)).unwrap();
+        assert_eq!(
+            broken(&report),
+            [
+                entry("public/index.html", 3, "/posts/a/#missing", BrokenReason::MissingAnchor),
+                entry("public/index.html", 4, "/img/none.png", BrokenReason::MissingTarget),
+                entry("public/index.html", 5, "https://example.com/nope/", BrokenReason::MissingTarget),
+                entry("public/posts/a/index.html", 2, "../../index.xml", BrokenReason::MissingTarget),
+            ]
+        );
+    }
+}
@@ -0,0 +1,207 @@
+pub mod external;
+pub mod internal;
+
+use std::ops::Range;
+use std::sync::OnceLock;
+use pulldown_cmark::{Event, LinkType, Parser, Tag};
+use regex::Regex;
+use serde::Serialize;
+use crate::front_matter::split_front_matter;
+use crate::markdown::line_map::LineIndex;
+use crate::markdown::render::parser_options;
+use crate::markdown::MarkdownConfig;
+use crate::models::EngineType;
+use crate::shortcodes::{parse_shortcodes, ShortcodeSegment};
+
+pub use external::{check_external_links, ExternalCheckOptions, ExternalLinkReport};
+pub use internal::check_internal_links;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LinkKind {
+    /// Markdown 链接 `[text](target)`
+    Link,
+    /// Markdown 图片 `![alt](src)`
+    Image,
+    /// `ref` / `relref` 短代码
+    Ref,
+    /// `figure` 短代码的 `src`
+    Figure,
+    /// HTML 中的 `href` / `src`（正文中的原始 HTML 或构建输出）
+    Html,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BrokenReason {
+    MissingTarget,
+    MissingAnchor,
+    /// `ref` 只给了文件名，但有多篇同名文章
+    AmbiguousRef,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BrokenLink {
+    /// 相对于站点根目录，使用 `/` 分隔
+    pub source: String,
+    /// 行号，从 1 开始
+    pub line: usize,
+    pub target: String,
+    pub kind: LinkKind,
+    pub reason: BrokenReason,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LinkReport {
+    pub checked: usize,
+    pub broken: Vec<BrokenLink>,
+}
+
+/// 文章中出现的一个链接
+#[derive(Debug, Clone)]
+pub struct FoundLink {
+    pub kind: LinkKind,
+    pub target: String,
+    /// 行号，从 1 开始，已计入 Front Matter
+    pub line: usize,
+    /// 链接（或短代码）在整篇文章中的字节范围，其中包含 `target` 的原文
+    pub span: Range<usize>,
+}
+
+/// 提取文章中的 Markdown 链接和图片、正文里原始 HTML 的 `href`/`src`，
+/// 以及 `ref`/`relref`/`figure` 短代码中的目标
+pub fn markdown_links(content: &str, config: &MarkdownConfig) -> Vec<FoundLink> {
+    let document = split_front_matter(content);
+    let index = LineIndex::new(document.body);
+    let line_of = |offset: usize| document.body_line + index.line_of(offset) + 1;
+    let span = |range: Range<usize>| document.body_offset + range.start..document.body_offset + range.end;
+
+    let mut links = Vec::new();
+    shortcode_links(document.body, 0, &config.engine, &mut |kind, target, range| {
+        links.push(FoundLink {
+            kind,
+            target,
+            line: line_of(range.start),
+            span: span(range),
+        });
+    });
+
+    for (event, range) in Parser::new_ext(document.body, parser_options(config)).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Link { link_type, dest_url, .. }) if link_type != LinkType::Email => {
+                links.push(FoundLink {
+                    kind: LinkKind::Link,
+                    target: dest_url.to_string(),
+                    line: line_of(range.start),
+                    span: span(range),
+                });
+            }
+            Event::Start(Tag::Image { dest_url, .. }) => links.push(FoundLink {
+                kind: LinkKind::Image,
+                target: dest_url.to_string(),
+                line: line_of(range.start),
+                span: span(range),
+            }),
+            Event::Html(html) | Event::InlineHtml(html) => {
+                for (offset, target) in html_attribute_links(&html) {
+                    let start = range.start + offset;
+                    links.push(FoundLink {
+                        kind: LinkKind::Html,
+                        line: line_of(start),
+                        span: span(start..range.end),
+                        target,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    links.sort_by_key(|link| link.line);
+    links
+}
+
+fn shortcode_links(
+    source: &str,
+    base: usize,
+    engine: &EngineType,
+    found: &mut dyn FnMut(LinkKind, String, Range<usize>),
+) {
+    for segment in parse_shortcodes(source, engine) {
+        let ShortcodeSegment::Call(call) = segment else {
+            continue;
+        };
+        // 成对的短代码只取开始标签，内部内容单独递归
+        let end = call.inner.as_ref().map(|inner| inner.start).unwrap_or(call.range.end);
+        let range = base + call.range.start..base + end;
+        match call.name.as_str() {
+            "ref" | "relref" => {
+                if let Some(target) = call.args.get_either("path", 0) {
+                    found(LinkKind::Ref, target.to_string(), range.clone());
+                }
+            }
+            "figure" => {
+                if let Some(src) = call.args.get("src") {
+                    found(LinkKind::Figure, src.to_string(), range.clone());
+                }
+                if let Some(link) = call.args.get("link") {
+                    found(LinkKind::Link, link.to_string(), range.clone());
+                }
+            }
+            _ => {}
+        }
+        if let Some(inner) = &call.inner {
+            shortcode_links(&source[inner.clone()], base + inner.start, engine, found);
+        }
+    }
+}
+
+/// HTML 中的 `href="..."` 和 `src="..."`，返回属性值在文本中的偏移和解码后的值
+pub fn html_attribute_links(html: &str) -> Vec<(usize, String)> {
+    static ATTR: OnceLock<Regex> = OnceLock::new();
+    let re = ATTR.get_or_init(|| {
+        Regex::new(r#"(?i)\s(?:href|src)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap()
+    });
+
+    re.captures_iter(html)
+        .filter_map(|caps| {
+            let value = caps.get(1).or_else(|| caps.get(2))?;
+            Some((value.start(), decode_html_entities(value.as_str())))
+        })
+        .collect()
+}
+
+fn decode_html_entities(value: &str) -> String {
+    value
+        .replace("&amp;", "&")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+}
+
+/// 带协议（`https:`、`mailto:` 等）或以 `//` 开头的链接不在站点内
+pub fn is_external(target: &str) -> bool {
+    if target.starts_with("//") {
+        return true;
+    }
+    match target.split_once(':') {
+        Some((scheme, _)) => {
+            !scheme.is_empty()
+                && scheme.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        }
+        None => false,
+    }
+}
+
+/// 拆分出 `#` 后面的锚点，并去掉查询参数
+pub fn split_anchor(target: &str) -> (&str, Option<&str>) {
+    let (path, anchor) = match target.split_once('#') {
+        Some((path, anchor)) => (path, Some(anchor).filter(|a| !a.is_empty())),
+        None => (target, None),
+    };
+    (path.split('?').next().unwrap_or_default(), anchor)
+}
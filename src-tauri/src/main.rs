@@ -1,35 +1,137 @@
 // Prevents additional console window on Windows in release
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod archive;
 mod commands;
+mod db;
+mod deploy;
 mod engine;
 mod error;
+mod front_matter;
+mod git;
+mod hooks;
+mod links;
+mod markdown;
 mod models;
+mod move_post;
+mod preview;
+mod replace;
+mod revisions;
+mod scheduler;
+mod search;
+mod secrets;
+mod settings;
+mod shortcodes;
+mod site_config;
+mod taxonomy;
+#[cfg(test)]
+mod test_support;
 mod utils;
 
 use commands::*;
+use preview::{EngineServers, PreviewServers};
+use scheduler::PublishScheduler;
+use tauri::Manager;
 
 fn main() {
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_shell::init())
+        .manage(PreviewServers::default())
+        .manage(EngineServers::default())
+        .setup(|app| {
+            let registry = app.path().app_data_dir()?.join("scheduled-projects.json");
+            app.manage(PublishScheduler::load(registry));
+            scheduler::start(app.handle().clone());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             create_project,
             open_project,
+            export_project,
+            read_project_archive,
+            import_project,
             build_project,
+            get_hooks,
+            save_hooks,
+            run_hook_stage,
+            list_build_profiles,
+            save_build_profile,
+            delete_build_profile,
+            build_with_profile,
+            list_deploy_targets,
+            save_deploy_target,
+            delete_deploy_target,
+            save_deploy_credentials,
+            get_deploy_credential_keys,
+            delete_deploy_credentials,
+            get_deploy_gate_settings,
+            save_deploy_gate_settings,
+            check_deploy,
+            deploy_site,
+            deploy_status,
+            list_deploy_history,
+            get_deploy_manifest,
+            rollback_deploy,
             serve_project,
+            list_network_interfaces,
             create_post,
             get_engine_version,
             list_posts,
             read_file,
             save_file,
+            autosave_post,
+            list_revisions,
+            read_revision,
+            diff_revisions,
+            restore_revision,
+            get_revision_settings,
+            save_revision_settings,
+            get_git_settings,
+            save_git_settings,
+            git_status,
+            git_stage,
+            git_unstage,
+            git_commit,
+            git_post_history,
+            git_diff_post,
+            git_list_branches,
+            git_switch_branch,
+            git_pull,
+            git_push,
+            render_preview,
+            preview_block_for_line,
+            source_lines_for_block,
+            list_shortcodes,
+            refresh_search_index,
+            search_content,
+            find_in_project,
+            replace_in_project,
+            list_replace_history,
+            undo_replace,
+            plan_move_post,
+            move_post,
+            check_internal_links,
+            check_external_links,
+            list_taxonomy_terms,
+            preview_taxonomy_edit,
+            apply_taxonomy_edit,
+            get_publish_settings,
+            save_publish_settings,
+            watch_scheduled_publishing,
+            unwatch_scheduled_publishing,
+            list_scheduled_posts,
+            publish_missed_posts,
+            list_publish_history,
             init_blog,
             check_directory_exists,
             create_directory,
             build_site,
             serve_site,
             stop_serve,
+            start_static_preview,
+            stop_static_preview,
             check_path_exists,
             delete_post,
             read_hugo_config,
@@ -0,0 +1,223 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::models::EngineType;
+use crate::site_config::config_path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HeadingIdType {
+    Github,
+    GithubAscii,
+    Blackfriday,
+}
+
+/// 预览渲染选项，对应 Hugo 的 `[markup.goldmark]` 和 `[markup.tableOfContents]`，
+/// 或 Zola 的 `[markdown]`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarkdownConfig {
+    /// 决定短代码语法
+    pub engine: EngineType,
+    pub tables: bool,
+    pub strikethrough: bool,
+    pub task_lists: bool,
+    pub footnotes: bool,
+    pub definition_lists: bool,
+    pub typographer: bool,
+    pub heading_attributes: bool,
+    pub auto_heading_id: bool,
+    pub heading_id_type: HeadingIdType,
+    pub unsafe_html: bool,
+    pub hard_wraps: bool,
+    pub toc_start_level: u8,
+    pub toc_end_level: u8,
+    pub toc_ordered: bool,
+}
+
+impl Default for MarkdownConfig {
+    /// Hugo 的默认值
+    fn default() -> Self {
+        Self {
+            engine: EngineType::Hugo,
+            tables: true,
+            strikethrough: true,
+            task_lists: true,
+            footnotes: true,
+            definition_lists: true,
+            typographer: true,
+            heading_attributes: true,
+            auto_heading_id: true,
+            heading_id_type: HeadingIdType::Github,
+            unsafe_html: false,
+            hard_wraps: false,
+            toc_start_level: 2,
+            toc_end_level: 3,
+            toc_ordered: false,
+        }
+    }
+}
+
+impl MarkdownConfig {
+    pub fn from_site_config(engine: &EngineType, config: &Value) -> Self {
+        match engine {
+            EngineType::Hugo => Self::from_hugo_config(config),
+            EngineType::Zola => Self::from_zola_config(config),
+        }
+    }
+
+    pub fn from_hugo_config(config: &Value) -> Self {
+        let mut md = Self::default();
+        let goldmark = |path: &[&str]| {
+            let full: Vec<&str> = ["markup", "goldmark"].iter().chain(path).copied().collect();
+            config_path(config, &full).cloned()
+        };
+
+        // 扩展既可以是布尔值，也可以是带 `disable` 的表（typographer、footnote 等）
+        let extension = |name: &str, default: bool| match goldmark(&["extensions", name]) {
+            Some(Value::Bool(enabled)) => enabled,
+            Some(table @ Value::Object(_)) => !config_path(&table, &["disable"])
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+            _ => default,
+        };
+
+        md.tables = extension("table", md.tables);
+        md.strikethrough = extension("strikethrough", md.strikethrough);
+        md.task_lists = extension("taskList", md.task_lists);
+        md.footnotes = extension("footnote", md.footnotes);
+        md.definition_lists = extension("definitionList", md.definition_lists);
+        md.typographer = extension("typographer", md.typographer);
+
+        if let Some(Value::Bool(v)) = goldmark(&["parser", "autoHeadingID"]) {
+            md.auto_heading_id = v;
+        }
+        if let Some(Value::String(v)) = goldmark(&["parser", "autoHeadingIDType"]) {
+            md.heading_id_type = match v.to_ascii_lowercase().as_str() {
+                "github-ascii" => HeadingIdType::GithubAscii,
+                "blackfriday" => HeadingIdType::Blackfriday,
+                _ => HeadingIdType::Github,
+            };
+        }
+        if let Some(Value::Bool(v)) = goldmark(&["parser", "attribute", "title"]) {
+            md.heading_attributes = v;
+        }
+        if let Some(Value::Bool(v)) = goldmark(&["renderer", "unsafe"]) {
+            md.unsafe_html = v;
+        }
+        if let Some(Value::Bool(v)) = goldmark(&["renderer", "hardWraps"]) {
+            md.hard_wraps = v;
+        }
+
+        let toc = |key: &str| config_path(config, &["markup", "tableOfContents", key]);
+        if let Some(v) = toc("startLevel").and_then(Value::as_u64) {
+            md.toc_start_level = v.clamp(1, 6) as u8;
+        }
+        if let Some(v) = toc("endLevel").and_then(Value::as_u64) {
+            md.toc_end_level = v.clamp(1, 6) as u8;
+        }
+        if let Some(v) = toc("ordered").and_then(Value::as_bool) {
+            md.toc_ordered = v;
+        }
+
+        md
+    }
+
+    /// Zola 固定开启表格、脚注、删除线和任务列表，标题总是带锚点
+    pub fn from_zola_config(config: &Value) -> Self {
+        let markdown = |key: &str| config_path(config, &["markdown", key]).and_then(Value::as_bool);
+
+        Self {
+            engine: EngineType::Zola,
+            definition_lists: markdown("definition_list").unwrap_or(false),
+            typographer: markdown("smart_punctuation").unwrap_or(false),
+            heading_attributes: true,
+            auto_heading_id: true,
+            heading_id_type: HeadingIdType::GithubAscii,
+            unsafe_html: true,
+            toc_start_level: 1,
+            toc_end_level: 6,
+            ..Self::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn maps_hugo_goldmark_settings() {
+        let config = json!({
+            "markup": {
+                "goldmark": {
+                    "extensions": {
+                        "typographer": { "disable": true },
+                        "footnote": false,
+                        "table": true
+                    },
+                    "parser": {
+                        "autoHeadingID": false,
+                        "autoHeadingIDType": "github-ascii",
+                        "attribute": { "title": false }
+                    },
+                    "renderer": { "unsafe": true, "hardWraps": true }
+                },
+                "tableOfContents": { "startLevel": 1, "endLevel": 9, "ordered": true }
+            }
+        });
+
+        let md = MarkdownConfig::from_site_config(&EngineType::Hugo, &config);
+        assert!(matches!(md.engine, EngineType::Hugo));
+        assert!(!md.typographer);
+        assert!(!md.footnotes);
+        assert!(md.tables);
+        assert!(md.strikethrough);
+        assert!(!md.auto_heading_id);
+        assert_eq!(md.heading_id_type, HeadingIdType::GithubAscii);
+        assert!(!md.heading_attributes);
+        assert!(md.unsafe_html);
+        assert!(md.hard_wraps);
+        assert_eq!(md.toc_start_level, 1);
+        // 超出范围的层级收敛到 6
+        assert_eq!(md.toc_end_level, 6);
+        assert!(md.toc_ordered);
+    }
+
+    #[test]
+    fn hugo_defaults_apply_without_markup_section() {
+        let md = MarkdownConfig::from_hugo_config(&json!({ "title": "Site" }));
+        assert!(md.typographer);
+        assert!(!md.unsafe_html);
+        assert!(!md.hard_wraps);
+        assert_eq!(md.heading_id_type, HeadingIdType::Github);
+        assert_eq!((md.toc_start_level, md.toc_end_level), (2, 3));
+
+        // 启用的扩展以表的形式出现时，没有 `disable` 就视为开启
+        let md = MarkdownConfig::from_hugo_config(&json!({
+            "markup": { "goldmark": {
+                "extensions": { "typographer": { "leftDoubleQuote": "&laquo;" } },
+                "parser": { "autoHeadingIDType": "blackfriday" }
+            } }
+        }));
+        assert!(md.typographer);
+        assert_eq!(md.heading_id_type, HeadingIdType::Blackfriday);
+    }
+
+    #[test]
+    fn maps_zola_markdown_settings() {
+        let md = MarkdownConfig::from_site_config(
+            &EngineType::Zola,
+            &json!({ "markdown": { "smart_punctuation": true, "definition_list": true } }),
+        );
+        assert!(matches!(md.engine, EngineType::Zola));
+        assert!(md.typographer);
+        assert!(md.definition_lists);
+        assert!(md.unsafe_html);
+        assert_eq!(md.heading_id_type, HeadingIdType::GithubAscii);
+        assert_eq!((md.toc_start_level, md.toc_end_level), (1, 6));
+
+        let md = MarkdownConfig::from_zola_config(&json!({}));
+        assert!(!md.typographer);
+        assert!(!md.definition_lists);
+    }
+}
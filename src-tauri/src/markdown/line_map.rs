@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+/// 预览中的一个顶层块及其在源文件中的行范围（从 1 开始，含两端，已计入 Front Matter）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceBlock {
+    pub index: usize,
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+/// 字节偏移到行号的换算表
+pub struct LineIndex {
+    line_starts: Vec<usize>,
+}
+
+impl LineIndex {
+    pub fn new(text: &str) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(text.match_indices('\n').map(|(i, _)| i + 1));
+        Self { line_starts }
+    }
+
+    /// 偏移所在的行（从 0 开始）
+    pub fn line_of(&self, offset: usize) -> usize {
+        self.line_starts.partition_point(|&start| start <= offset) - 1
+    }
+}
+
+/// 编辑器光标所在行对应的预览块：行落在块内时取该块，
+/// 落在块之间（空行）时取前一个块，位于第一个块之前（如 Front Matter）时取第一个块
+pub fn block_for_line(blocks: &[SourceBlock], line: usize) -> Option<&SourceBlock> {
+    let index = blocks.partition_point(|block| block.start_line <= line);
+    match index.checked_sub(1) {
+        Some(i) => blocks.get(i),
+        None => blocks.first(),
+    }
+}
+
+/// 预览中第 `index` 个块对应的源文件行范围
+pub fn block_at(blocks: &[SourceBlock], index: usize) -> Option<&SourceBlock> {
+    blocks.get(index)
+}
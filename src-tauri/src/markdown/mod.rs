@@ -0,0 +1,8 @@
+pub mod config;
+pub mod line_map;
+pub mod render;
+pub mod shortcodes;
+
+pub use config::MarkdownConfig;
+pub use line_map::{block_at, block_for_line, SourceBlock};
+pub use render::{document_blocks, render_document, render_markdown, Heading, RenderedMarkdown};
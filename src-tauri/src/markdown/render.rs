@@ -0,0 +1,450 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::OnceLock;
+use pulldown_cmark::{html, CowStr, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use regex::Regex;
+use serde::Serialize;
+use crate::front_matter::split_front_matter;
+use crate::markdown::config::{HeadingIdType, MarkdownConfig};
+use crate::markdown::line_map::{LineIndex, SourceBlock};
+use crate::markdown::shortcodes::{expand_shortcodes, restore_shortcodes, ExpandedSource, ShortcodeReplacement};
+
+const RAW_HTML_OMITTED: &str = "<!-- raw HTML omitted -->";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Heading {
+    pub level: u8,
+    pub text: String,
+    pub id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RenderedMarkdown {
+    pub html: String,
+    /// 目录，只包含 `toc_start_level..=toc_end_level` 之间的标题
+    pub toc: Vec<Heading>,
+    /// 正文中所有标题及其锚点
+    pub headings: Vec<Heading>,
+    /// 顶层块与源文件行的对应关系，与 HTML 中的 `data-source-line` 一致
+    pub blocks: Vec<SourceBlock>,
+}
+
+/// 渲染 Markdown 正文（不含 Front Matter），短代码渲染为近似的 HTML 或占位块
+pub fn render_markdown(source: &str, config: &MarkdownConfig) -> RenderedMarkdown {
+    render(source, config, None)
+}
+
+/// 渲染整篇文章，并给每个顶层块加上 `data-source-line` / `data-source-line-end`，
+/// 行号相对于整个文件（含 Front Matter）
+pub fn render_document(content: &str, config: &MarkdownConfig) -> RenderedMarkdown {
+    let document = split_front_matter(content);
+    render(document.body, config, Some(document.body_line))
+}
+
+/// 只解析不渲染，计算整篇文章的顶层块行范围
+pub fn document_blocks(content: &str, config: &MarkdownConfig) -> Vec<SourceBlock> {
+    let document = split_front_matter(content);
+    let expanded = expand_shortcodes(document.body, config);
+    let ranges: Vec<(Event, Range<usize>)> = Parser::new_ext(&expanded.markdown, parser_options(config))
+        .into_offset_iter()
+        .collect();
+    let (events, ranges): (Vec<Event>, Vec<Range<usize>>) = ranges.into_iter().unzip();
+
+    top_level_blocks(&events, &ranges, &expanded, document.body, document.body_line)
+        .into_iter()
+        .map(|(_, block)| block)
+        .collect()
+}
+
+fn render(source: &str, config: &MarkdownConfig, line_offset: Option<usize>) -> RenderedMarkdown {
+    let expanded = expand_shortcodes(source, config);
+    let (events, ranges): (Vec<Event>, Vec<Range<usize>>) =
+        Parser::new_ext(&expanded.markdown, parser_options(config))
+            .into_offset_iter()
+            .unzip();
+    let (events, headings) = assign_heading_ids(events, config, &expanded.replacements);
+    let mut events = apply_renderer_options(events, config);
+
+    // 在每个顶层块前插入行号标记，生成 HTML 后再转换成属性
+    let mut blocks = Vec::new();
+    if let Some(line_offset) = line_offset {
+        let located = top_level_blocks(&events, &ranges, &expanded, source, line_offset);
+        let mut marked = Vec::with_capacity(events.len() + located.len());
+        let mut next_block = located.iter().peekable();
+        for (i, event) in events.into_iter().enumerate() {
+            if let Some((_, block)) = next_block.next_if(|(index, _)| *index == i) {
+                let marker = format!("<!--pna-line:{}:{}-->", block.start_line, block.end_line);
+                marked.push(Event::Html(CowStr::from(marker)));
+            }
+            marked.push(event);
+        }
+        events = marked;
+        blocks = located.into_iter().map(|(_, block)| block).collect();
+    }
+
+    let mut html_output = String::with_capacity(expanded.markdown.len() * 3 / 2);
+    html::push_html(&mut html_output, events.into_iter());
+    let mut html_output = restore_shortcodes(html_output, &expanded.replacements);
+    if line_offset.is_some() {
+        html_output = apply_line_markers(&html_output);
+    }
+
+    let toc = headings
+        .iter()
+        .filter(|h| h.level >= config.toc_start_level && h.level <= config.toc_end_level)
+        .cloned()
+        .collect();
+
+    RenderedMarkdown {
+        html: html_output,
+        toc,
+        headings,
+        blocks,
+    }
+}
+
+/// 找出顶层块的起始事件及其行范围
+fn top_level_blocks(
+    events: &[Event],
+    ranges: &[Range<usize>],
+    expanded: &ExpandedSource,
+    source: &str,
+    line_offset: usize,
+) -> Vec<(usize, SourceBlock)> {
+    let lines = LineIndex::new(source);
+    let mut blocks = Vec::new();
+    let mut depth = 0usize;
+
+    for (i, event) in events.iter().enumerate() {
+        let starts_block = match event {
+            Event::Start(_) => {
+                depth += 1;
+                depth == 1
+            }
+            Event::End(_) => {
+                depth = depth.saturating_sub(1);
+                false
+            }
+            Event::Rule => depth == 0,
+            _ => false,
+        };
+        if !starts_block {
+            continue;
+        }
+
+        let range = &ranges[i];
+        let start = expanded.original_offset(range.start);
+        // 块的范围可能包含结尾的空行，行号只算到最后一个非空白字符
+        let mut end = expanded.original_end_offset(range.end.max(range.start + 1)) - 1;
+        while end > start && source.as_bytes().get(end).is_none_or(|b| b.is_ascii_whitespace()) {
+            end -= 1;
+        }
+        blocks.push((
+            i,
+            SourceBlock {
+                index: blocks.len(),
+                start_line: line_offset + lines.line_of(start) + 1,
+                end_line: line_offset + lines.line_of(end.max(start)) + 1,
+            },
+        ));
+    }
+
+    blocks
+}
+
+/// `<!--pna-line:3:5--><p>` → `<p data-source-line="3" data-source-line-end="5">`
+fn apply_line_markers(html: &str) -> String {
+    static MARKER: OnceLock<Regex> = OnceLock::new();
+    let re = MARKER.get_or_init(|| {
+        Regex::new(r"<!--pna-line:(\d+):(\d+)-->(\s*<([A-Za-z][A-Za-z0-9-]*))?").unwrap()
+    });
+
+    re.replace_all(html, |caps: &regex::Captures| match caps.get(3) {
+        Some(tag) => format!(
+            "{} data-source-line=\"{}\" data-source-line-end=\"{}\"",
+            tag.as_str(),
+            &caps[1],
+            &caps[2]
+        ),
+        None => String::new(),
+    })
+    .into_owned()
+}
+
+pub fn parser_options(config: &MarkdownConfig) -> Options {
+    let mut options = Options::empty();
+    options.set(Options::ENABLE_TABLES, config.tables);
+    options.set(Options::ENABLE_STRIKETHROUGH, config.strikethrough);
+    options.set(Options::ENABLE_TASKLISTS, config.task_lists);
+    options.set(Options::ENABLE_FOOTNOTES, config.footnotes);
+    options.set(Options::ENABLE_DEFINITION_LIST, config.definition_lists);
+    options.set(Options::ENABLE_SMART_PUNCTUATION, config.typographer);
+    options.set(Options::ENABLE_HEADING_ATTRIBUTES, config.heading_attributes);
+    options
+}
+
+/// 给没有显式 `{#id}` 的标题生成锚点，并收集标题列表。
+/// 标题中的短代码占位符换成短代码渲染结果的文字
+fn assign_heading_ids<'a>(
+    mut events: Vec<Event<'a>>,
+    config: &MarkdownConfig,
+    replacements: &[ShortcodeReplacement],
+) -> (Vec<Event<'a>>, Vec<Heading>) {
+    let mut headings = Vec::new();
+    let mut used_ids: HashMap<String, usize> = HashMap::new();
+    let mut i = 0;
+
+    while i < events.len() {
+        let Event::Start(Tag::Heading { level, id, .. }) = &events[i] else {
+            i += 1;
+            continue;
+        };
+        let level = heading_level(*level);
+        let explicit_id = id.as_ref().map(|id| id.to_string());
+
+        let mut text = String::new();
+        let mut end = i + 1;
+        while end < events.len() {
+            match &events[end] {
+                Event::End(TagEnd::Heading(_)) => break,
+                Event::Text(t) | Event::Code(t) => text.push_str(t),
+                _ => {}
+            }
+            end += 1;
+        }
+        for replacement in replacements.iter().rev() {
+            if text.contains(&replacement.token) {
+                text = text.replace(&replacement.token, &strip_tags(&replacement.html));
+            }
+        }
+
+        let id = match explicit_id {
+            Some(id) => Some(id),
+            None if config.auto_heading_id => {
+                Some(unique_id(slugify(&text, config.heading_id_type), &mut used_ids))
+            }
+            None => None,
+        };
+
+        if let (Some(id), Event::Start(Tag::Heading { id: slot, .. })) = (&id, &mut events[i]) {
+            *slot = Some(CowStr::from(id.clone()));
+        }
+
+        headings.push(Heading { level, text, id });
+        i = end + 1;
+    }
+
+    (events, headings)
+}
+
+fn strip_tags(html: &str) -> String {
+    static TAG: OnceLock<Regex> = OnceLock::new();
+    let re = TAG.get_or_init(|| Regex::new(r"<[^>]*>").unwrap());
+    re.replace_all(html, "")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+fn apply_renderer_options<'a>(events: Vec<Event<'a>>, config: &MarkdownConfig) -> Vec<Event<'a>> {
+    events
+        .into_iter()
+        .map(|event| match event {
+            Event::Html(_) | Event::InlineHtml(_) if !config.unsafe_html => {
+                Event::InlineHtml(CowStr::Borrowed(RAW_HTML_OMITTED))
+            }
+            Event::SoftBreak if config.hard_wraps => Event::HardBreak,
+            other => other,
+        })
+        .collect()
+}
+
+pub fn heading_level(level: HeadingLevel) -> u8 {
+    match level {
+        HeadingLevel::H1 => 1,
+        HeadingLevel::H2 => 2,
+        HeadingLevel::H3 => 3,
+        HeadingLevel::H4 => 4,
+        HeadingLevel::H5 => 5,
+        HeadingLevel::H6 => 6,
+    }
+}
+
+/// 与 Goldmark 的 autoHeadingIDType 规则保持一致
+pub fn slugify(text: &str, id_type: HeadingIdType) -> String {
+    let mut slug = String::with_capacity(text.len());
+
+    match id_type {
+        HeadingIdType::Github | HeadingIdType::GithubAscii => {
+            for c in text.trim().chars() {
+                if id_type == HeadingIdType::GithubAscii && !c.is_ascii() {
+                    continue;
+                }
+                if c.is_alphanumeric() || c == '_' {
+                    slug.extend(c.to_lowercase());
+                } else if c == ' ' || c == '-' {
+                    slug.push('-');
+                }
+            }
+        }
+        HeadingIdType::Blackfriday => {
+            let mut pending_dash = false;
+            for c in text.chars() {
+                if c.is_alphanumeric() {
+                    if pending_dash && !slug.is_empty() {
+                        slug.push('-');
+                    }
+                    pending_dash = false;
+                    slug.extend(c.to_lowercase());
+                } else {
+                    pending_dash = true;
+                }
+            }
+        }
+    }
+
+    if slug.is_empty() {
+        "heading".to_string()
+    } else {
+        slug
+    }
+}
+
+fn unique_id(base: String, used: &mut HashMap<String, usize>) -> String {
+    match used.get_mut(&base) {
+        None => {
+            used.insert(base.clone(), 0);
+            base
+        }
+        Some(count) => {
+            *count += 1;
+            let id = format!("{}-{}", base, count);
+            used.insert(id.clone(), 0);
+            id
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(content: &str) -> Vec<(usize, usize)> {
+        document_blocks(content, &MarkdownConfig::default())
+            .iter()
+            .map(|block| (block.start_line, block.end_line))
+            .collect()
+    }
+
+    #[test]
+    fn multi_line_shortcode_maps_to_its_full_range() {
+        let content = "---\ntitle: Test\n---\n\nIntro\n\n{{< notice warning >}}\nFirst\nSecond\n{{< /notice >}}\n\nOutro\n";
+        assert_eq!(lines(content), vec![(5, 5), (7, 10), (12, 12)]);
+    }
+
+    #[test]
+    fn shortcode_at_end_of_file_without_newline() {
+        let content = "+++\ntitle = \"Test\"\n+++\nIntro\n\n{{< notice >}}\nBody\n{{< /notice >}}";
+        assert_eq!(lines(content), vec![(4, 4), (6, 8)]);
+    }
+
+    #[test]
+    fn shortcode_inside_paragraph_keeps_paragraph_lines() {
+        let content = "Before {{< ref \"a.md\" >}}\nafter\n\n{{< figure\n  src=\"a.png\"\n>}}";
+        assert_eq!(lines(content), vec![(1, 2), (4, 6)]);
+    }
+
+    fn ids(source: &str, config: &MarkdownConfig) -> Vec<String> {
+        render_markdown(source, config)
+            .headings
+            .into_iter()
+            .filter_map(|h| h.id)
+            .collect()
+    }
+
+    #[test]
+    fn slugs_follow_the_heading_id_type() {
+        let text = "Hello, World! 中文 _x_-y";
+        assert_eq!(slugify(text, HeadingIdType::Github), "hello-world-中文-_x_-y");
+        assert_eq!(slugify(text, HeadingIdType::GithubAscii), "hello-world--_x_-y");
+        assert_eq!(slugify(text, HeadingIdType::Blackfriday), "hello-world-中文-x-y");
+        assert_eq!(slugify("!!!", HeadingIdType::Github), "heading");
+
+        let config = MarkdownConfig {
+            heading_id_type: HeadingIdType::Blackfriday,
+            ..MarkdownConfig::default()
+        };
+        assert_eq!(ids("## C++ & *Rust*\n", &config), ["c-rust"]);
+    }
+
+    #[test]
+    fn duplicate_ids_get_numeric_suffixes() {
+        let source = "# Intro\n\n## Intro\n\n## Intro\n\n## Intro 1\n\n## Custom {#intro}\n";
+        assert_eq!(
+            ids(source, &MarkdownConfig::default()),
+            ["intro", "intro-1", "intro-2", "intro-1-1", "intro"]
+        );
+
+        let config = MarkdownConfig {
+            auto_heading_id: false,
+            ..MarkdownConfig::default()
+        };
+        let rendered = render_markdown("## Plain\n\n## Named {#named}\n", &config);
+        assert_eq!(rendered.headings[0].id, None);
+        assert_eq!(rendered.headings[1].id.as_deref(), Some("named"));
+        assert!(rendered.html.starts_with("<h2>Plain</h2>"), "{}", rendered.html);
+    }
+
+    #[test]
+    fn toc_respects_start_and_end_level() {
+        let source = "# One\n\n## Two\n\n### Three\n\n#### Four\n";
+        let levels = |config: &MarkdownConfig| -> Vec<u8> {
+            render_markdown(source, config).toc.iter().map(|h| h.level).collect()
+        };
+
+        assert_eq!(levels(&MarkdownConfig::default()), [2, 3]);
+        let config = MarkdownConfig {
+            toc_start_level: 1,
+            toc_end_level: 4,
+            ..MarkdownConfig::default()
+        };
+        assert_eq!(levels(&config), [1, 2, 3, 4]);
+        // 目录之外的标题仍然出现在标题列表里
+        assert_eq!(render_markdown(source, &MarkdownConfig::default()).headings.len(), 4);
+    }
+
+    #[test]
+    fn raw_html_is_omitted_unless_unsafe() {
+        let source = "<div class=\"x\">block</div>\n\nText <span>inline</span>\n";
+        let safe = render_markdown(source, &MarkdownConfig::default()).html;
+        assert_eq!(
+            safe,
+            format!("{}\n<p>Text {}inline{}</p>\n", RAW_HTML_OMITTED, RAW_HTML_OMITTED, RAW_HTML_OMITTED)
+        );
+
+        let config = MarkdownConfig {
+            unsafe_html: true,
+            ..MarkdownConfig::default()
+        };
+        let raw = render_markdown(source, &config).html;
+        assert!(raw.contains("<div class=\"x\">block</div>"), "{}", raw);
+        assert!(raw.contains("<span>inline</span>"), "{}", raw);
+    }
+
+    #[test]
+    fn hard_wraps_and_typographer() {
+        let source = "\"Quoted\" -- text\nnext line\n";
+        let plain = render_markdown(source, &MarkdownConfig::default()).html;
+        assert_eq!(plain, "<p>“Quoted” – text\nnext line</p>\n");
+
+        let config = MarkdownConfig {
+            typographer: false,
+            hard_wraps: true,
+            ..MarkdownConfig::default()
+        };
+        let wrapped = render_markdown(source, &config).html;
+        assert_eq!(wrapped, "<p>\"Quoted\" -- text<br />\nnext line</p>\n");
+    }
+}
@@ -0,0 +1,367 @@
+use std::ops::Range;
+use crate::markdown::config::MarkdownConfig;
+use crate::markdown::render::render_markdown;
+use crate::shortcodes::{parse_shortcodes, ShortcodeArgs, ShortcodeCall, ShortcodeSegment, ShortcodeSyntax};
+
+/// 短代码在 Markdown 中被替换成的占位符，渲染完成后再换回 HTML
+pub struct ShortcodeReplacement {
+    pub token: String,
+    pub html: String,
+}
+
+/// 替换短代码后的 Markdown，以及替换前后的偏移对应关系
+pub struct ExpandedSource {
+    pub markdown: String,
+    pub replacements: Vec<ShortcodeReplacement>,
+    /// 每一处替换在新旧文本中的范围
+    spans: Vec<(Range<usize>, Range<usize>)>,
+}
+
+impl ExpandedSource {
+    /// 把替换后文本中的偏移换算回原文；落在占位符内的偏移对应短代码的开头
+    pub fn original_offset(&self, offset: usize) -> usize {
+        let index = self.spans.partition_point(|(expanded, _)| expanded.start <= offset);
+        let Some((expanded, original)) = index.checked_sub(1).map(|i| &self.spans[i]) else {
+            return offset;
+        };
+
+        if offset < expanded.end {
+            original.start
+        } else {
+            original.end + (offset - expanded.end)
+        }
+    }
+
+    /// 换算范围的结束偏移（不含）：结束在占位符内或恰好在其末尾时对应短代码的结尾
+    pub fn original_end_offset(&self, end: usize) -> usize {
+        let index = self.spans.partition_point(|(expanded, _)| expanded.start < end);
+        match index.checked_sub(1).map(|i| &self.spans[i]) {
+            Some((expanded, original)) if end <= expanded.end => original.end,
+            Some((expanded, original)) => original.end + (end - expanded.end),
+            None => end,
+        }
+    }
+}
+
+/// 把短代码替换成纯字母数字的占位符，Markdown 渲染不会改动它们
+pub fn expand_shortcodes(source: &str, config: &MarkdownConfig) -> ExpandedSource {
+    let segments = parse_shortcodes(source, &config.engine);
+    let mut markdown = String::with_capacity(source.len());
+    let mut replacements = Vec::new();
+    let mut spans = Vec::new();
+    let mut pos = 0;
+
+    for segment in segments {
+        let range = segment.range().clone();
+        markdown.push_str(&source[pos..range.start]);
+        let expanded_start = markdown.len();
+        match segment {
+            ShortcodeSegment::Literal { text, .. } => markdown.push_str(&text),
+            ShortcodeSegment::Call(call) => {
+                let token = format!("PNASHORTCODE{}END", replacements.len());
+                markdown.push_str(&token);
+                replacements.push(ShortcodeReplacement {
+                    token,
+                    html: render_shortcode(&call, source, config),
+                });
+            }
+        }
+        spans.push((expanded_start..markdown.len(), range.clone()));
+        pos = range.end;
+    }
+    markdown.push_str(&source[pos..]);
+
+    ExpandedSource {
+        markdown,
+        replacements,
+        spans,
+    }
+}
+
+/// 占位符单独成段时去掉外层的 `<p>`，其余原地替换
+pub fn restore_shortcodes(mut html: String, replacements: &[ShortcodeReplacement]) -> String {
+    for replacement in replacements.iter().rev() {
+        let paragraph = format!("<p>{}</p>", replacement.token);
+        html = html
+            .replace(&paragraph, &replacement.html)
+            .replace(&replacement.token, &replacement.html);
+    }
+    html
+}
+
+fn render_shortcode(call: &ShortcodeCall, source: &str, config: &MarkdownConfig) -> String {
+    let inner = call.inner.as_ref().map(|range| &source[range.clone()]);
+
+    // `{{% %}}` 的内容按 Markdown 渲染，其中可能还有嵌套的短代码
+    if call.syntax == ShortcodeSyntax::HugoMarkdown && !is_builtin(&call.name) {
+        return placeholder(call, inner.map(|inner| render_markdown(inner, config).html));
+    }
+
+    if call.syntax != ShortcodeSyntax::Zola {
+        match call.name.as_str() {
+            "figure" => return render_figure(&call.args),
+            "highlight" => return render_highlight(&call.args, inner.unwrap_or_default()),
+            "ref" | "relref" => {
+                return escape_html(&ref_url(call.args.get_either("path", 0).unwrap_or_default()))
+            }
+            "gist" => return render_gist(&call.args),
+            _ => {}
+        }
+    }
+
+    placeholder(call, inner.map(|inner| render_markdown(inner, config).html))
+}
+
+fn is_builtin(name: &str) -> bool {
+    matches!(name, "figure" | "highlight" | "ref" | "relref" | "gist")
+}
+
+fn render_figure(args: &ShortcodeArgs) -> String {
+    let attr = |name: &str| args.get(name).map(escape_html);
+    let mut html = String::from("<figure");
+    if let Some(class) = attr("class") {
+        html.push_str(&format!(" class=\"{}\"", class));
+    }
+    html.push('>');
+
+    let link = attr("link");
+    if let Some(link) = &link {
+        html.push_str(&format!("<a href=\"{}\"", link));
+        if let Some(target) = attr("target") {
+            html.push_str(&format!(" target=\"{}\"", target));
+        }
+        if let Some(rel) = attr("rel") {
+            html.push_str(&format!(" rel=\"{}\"", rel));
+        }
+        html.push('>');
+    }
+
+    html.push_str(&format!("<img src=\"{}\"", attr("src").unwrap_or_default()));
+    let alt = attr("alt").or_else(|| attr("caption"));
+    if let Some(alt) = alt {
+        html.push_str(&format!(" alt=\"{}\"", alt));
+    }
+    for name in ["width", "height", "loading"] {
+        if let Some(value) = attr(name) {
+            html.push_str(&format!(" {}=\"{}\"", name, value));
+        }
+    }
+    html.push('>');
+    if link.is_some() {
+        html.push_str("</a>");
+    }
+
+    let title = attr("title");
+    let caption = attr("caption");
+    let credit = attr("attr");
+    if title.is_some() || caption.is_some() || credit.is_some() {
+        html.push_str("<figcaption>");
+        if let Some(title) = title {
+            html.push_str(&format!("<h4>{}</h4>", title));
+        }
+        if caption.is_some() || credit.is_some() {
+            html.push_str("<p>");
+            html.push_str(&caption.unwrap_or_default());
+            if let Some(credit) = credit {
+                match attr("attrlink") {
+                    Some(href) => html.push_str(&format!(" <a href=\"{}\">{}</a>", href, credit)),
+                    None => html.push_str(&format!(" {}", credit)),
+                }
+            }
+            html.push_str("</p>");
+        }
+        html.push_str("</figcaption>");
+    }
+
+    html.push_str("</figure>");
+    html
+}
+
+fn render_highlight(args: &ShortcodeArgs, code: &str) -> String {
+    let code = code.strip_prefix("\r\n").or_else(|| code.strip_prefix('\n')).unwrap_or(code);
+    match args.get_at(0) {
+        Some(lang) => format!(
+            "<pre><code class=\"language-{0}\" data-lang=\"{0}\">{1}</code></pre>",
+            escape_html(lang),
+            escape_html(code)
+        ),
+        None => format!("<pre><code>{}</code></pre>", escape_html(code)),
+    }
+}
+
+fn render_gist(args: &ShortcodeArgs) -> String {
+    let user = args.get_either("user", 0).unwrap_or_default();
+    let id = args.get_either("id", 1).unwrap_or_default();
+    let url = format!("https://gist.github.com/{}/{}", user, id);
+    format!(
+        "<div class=\"shortcode-placeholder\" data-shortcode=\"gist\"><span class=\"shortcode-placeholder-label\">gist</span> <a href=\"{0}\">{0}</a></div>",
+        escape_html(&url)
+    )
+}
+
+/// `posts/foo.md#intro` → `/posts/foo/#intro`，与 Hugo 默认的永久链接规则一致
+pub fn ref_url(target: &str) -> String {
+    let (path, anchor) = match target.split_once('#') {
+        Some((path, anchor)) => (path, Some(anchor)),
+        None => (target, None),
+    };
+
+    let mut url = String::new();
+    if !path.is_empty() {
+        let mut trimmed = path
+            .trim_start_matches('/')
+            .trim_end_matches(".md")
+            .trim_end_matches(".markdown");
+        for bundle_index in ["_index", "index"] {
+            if trimmed == bundle_index {
+                trimmed = "";
+            } else if let Some(dir) = trimmed.strip_suffix(bundle_index).and_then(|t| t.strip_suffix('/')) {
+                trimmed = dir;
+            }
+        }
+        url.push('/');
+        if !trimmed.is_empty() {
+            url.push_str(trimmed.trim_end_matches('/'));
+            url.push('/');
+        }
+    }
+    if let Some(anchor) = anchor {
+        url.push('#');
+        url.push_str(anchor);
+    }
+    url
+}
+
+/// 未知短代码显示为带名称的占位块
+fn placeholder(call: &ShortcodeCall, inner_html: Option<String>) -> String {
+    let mut args: Vec<String> = call.args.positional.iter().map(|v| format!("\"{}\"", v)).collect();
+    args.extend(call.args.named.iter().map(|(k, v)| format!("{}=\"{}\"", k, v)));
+
+    let mut html = format!(
+        "<div class=\"shortcode-placeholder\" data-shortcode=\"{0}\"><span class=\"shortcode-placeholder-label\">{0}</span>",
+        escape_html(&call.name)
+    );
+    if !args.is_empty() {
+        html.push_str(&format!(
+            " <code class=\"shortcode-placeholder-args\">{}</code>",
+            escape_html(&args.join(" "))
+        ));
+    }
+    if let Some(inner_html) = inner_html {
+        html.push_str("<div class=\"shortcode-placeholder-inner\">");
+        html.push_str(&inner_html);
+        html.push_str("</div>");
+    }
+    html.push_str("</div>");
+    html
+}
+
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::EngineType;
+
+    fn html(source: &str) -> String {
+        render_markdown(source, &MarkdownConfig::default()).html
+    }
+
+    #[test]
+    fn renders_figure_alone_without_paragraph() {
+        let out = html("{{< figure src=\"a.png\" alt=\"A\" caption=\"<Cap>\" link=\"/x/\" >}}\n");
+        assert_eq!(
+            out.trim(),
+            "<figure><a href=\"/x/\"><img src=\"a.png\" alt=\"A\"></a>\
+             <figcaption><p>&lt;Cap&gt;</p></figcaption></figure>"
+        );
+    }
+
+    #[test]
+    fn inline_shortcode_stays_in_paragraph() {
+        let out = html("See [A]({{< ref \"posts/a.md#intro\" >}}) and {{< relref \"/about/_index.md\" >}}.\n");
+        assert_eq!(out.trim(), "<p>See <a href=\"/posts/a/#intro\">A</a> and /about/.</p>");
+    }
+
+    #[test]
+    fn renders_highlight_and_gist() {
+        let out = html("{{< highlight go >}}\nfmt.Println(\"<hi>\")\n{{< /highlight >}}\n");
+        assert_eq!(
+            out.trim(),
+            "<pre><code class=\"language-go\" data-lang=\"go\">fmt.Println(&quot;&lt;hi&gt;&quot;)\n</code></pre>"
+        );
+
+        let out = html("{{< gist spf13 7896402 >}}\n");
+        assert!(out.contains("data-shortcode=\"gist\""), "{}", out);
+        assert!(out.contains("<a href=\"https://gist.github.com/spf13/7896402\">"), "{}", out);
+    }
+
+    #[test]
+    fn unknown_shortcode_renders_placeholder_with_inner_markdown() {
+        let out = html("{{< notice warning title=\"Hey\" >}}\nBe **careful**\n{{< /notice >}}\n");
+        assert!(out.starts_with("<div class=\"shortcode-placeholder\" data-shortcode=\"notice\">"), "{}", out);
+        assert!(out.contains("<code class=\"shortcode-placeholder-args\">&quot;warning&quot; title=&quot;Hey&quot;</code>"), "{}", out);
+        assert!(out.contains("<div class=\"shortcode-placeholder-inner\"><p>Be <strong>careful</strong></p>"), "{}", out);
+
+        // Zola 的短代码没有内置实现，都显示为占位块
+        let config = MarkdownConfig {
+            engine: EngineType::Zola,
+            ..MarkdownConfig::default()
+        };
+        let out = render_markdown("{{ youtube(id=\"abc\") }}\n", &config).html;
+        assert!(out.contains("data-shortcode=\"youtube\""), "{}", out);
+        assert!(out.contains("id=&quot;abc&quot;"), "{}", out);
+    }
+
+    #[test]
+    fn nested_markdown_shortcodes_render_inner_calls() {
+        let out = html("{{% note %}}\n**Read** {{< ref \"a.md\" >}} first\n{{% /note %}}\n");
+        assert!(out.contains("data-shortcode=\"note\""), "{}", out);
+        assert!(out.contains("<p><strong>Read</strong> /a/ first</p>"), "{}", out);
+        assert!(!out.contains("PNASHORTCODE"), "{}", out);
+    }
+
+    #[test]
+    fn heading_ids_use_the_rendered_shortcode_text() {
+        let rendered = render_markdown("## Intro {{< ref \"guide.md\" >}}\n", &MarkdownConfig::default());
+        assert_eq!(rendered.headings[0].text, "Intro /guide/");
+        assert_eq!(rendered.headings[0].id.as_deref(), Some("intro-guide"));
+        assert!(rendered.html.starts_with("<h2 id=\"intro-guide\">Intro /guide/</h2>"), "{}", rendered.html);
+    }
+
+    #[test]
+    fn shortcodes_survive_when_raw_html_is_omitted() {
+        let out = html("<div>raw</div>\n\n{{< figure src=\"a.png\" >}}\n");
+        assert!(out.contains("<!-- raw HTML omitted -->"), "{}", out);
+        assert!(!out.contains("<div>raw</div>"), "{}", out);
+        assert!(out.contains("<figure><img src=\"a.png\"></figure>"), "{}", out);
+    }
+
+    #[test]
+    fn placeholders_round_trip() {
+        // 超过十个时 `PNASHORTCODE1END` 不能误替换 `PNASHORTCODE10END` 的一部分
+        let source: String = (0..11).map(|i| format!("{{{{< ref \"p{}.md\" >}}}}\n\n", i)).collect();
+        let expanded = expand_shortcodes(&source, &MarkdownConfig::default());
+        assert_eq!(expanded.replacements.len(), 11);
+        assert!(expanded.markdown.starts_with("PNASHORTCODE0END\n\nPNASHORTCODE1END\n\n"));
+        assert_eq!(expanded.original_offset(2), 0);
+        assert_eq!(expanded.original_end_offset(16), source.find('\n').unwrap());
+        assert_eq!(expanded.original_offset(16), source.find('\n').unwrap());
+
+        let html = "<p>PNASHORTCODE10END</p>\n<p>x PNASHORTCODE1END</p>".to_string();
+        assert_eq!(restore_shortcodes(html, &expanded.replacements), "/p10/\n<p>x /p1/</p>");
+    }
+}
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
 use std::path::PathBuf;
 use chrono::{DateTime, Utc};
 
@@ -17,6 +18,7 @@ pub struct Project {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum EngineType {
     Hugo,
+    Zola,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -67,18 +69,106 @@ pub struct FrontMatter {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct BuildOptions {
     pub minify: bool,
     pub clean: bool,
     pub draft: bool,
+    pub environment: Option<String>,
+    pub base_url: Option<String>,
+    pub build_future: bool,
+    pub build_expired: bool,
+    pub destination: Option<String>,
+    pub cache_dir: Option<String>,
+    pub template_metrics: bool,
 }
 
 impl Default for BuildOptions {
+    /// 两种引擎都支持的默认构建；Hugo 的 "production" 配置再打开 minify
     fn default() -> Self {
         Self {
-            minify: true,
+            minify: false,
             clean: true,
             draft: false,
+            environment: None,
+            base_url: None,
+            build_future: false,
+            build_expired: false,
+            destination: None,
+            cache_dir: None,
+            template_metrics: false,
+        }
+    }
+}
+
+/// 保存在项目中的构建配置，例如 "preview" 和 "production"
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildProfile {
+    pub name: String,
+    pub options: BuildOptions,
+}
+
+impl BuildProfile {
+    /// 内置的 "preview" 配置：包含草稿。Zola 不支持 environment 和 build_future，只设置 draft
+    pub fn preview(engine: &EngineType) -> Self {
+        let options = match engine {
+            EngineType::Hugo => BuildOptions {
+                draft: true,
+                environment: Some("development".to_string()),
+                build_future: true,
+                ..BuildOptions::default()
+            },
+            EngineType::Zola => BuildOptions {
+                draft: true,
+                ..BuildOptions::default()
+            },
+        };
+        Self {
+            name: "preview".to_string(),
+            options,
+        }
+    }
+
+    /// 内置的 "production" 配置。Zola 不支持 minify 和 environment，使用默认构建
+    pub fn production(engine: &EngineType) -> Self {
+        let options = match engine {
+            EngineType::Hugo => BuildOptions {
+                minify: true,
+                environment: Some("production".to_string()),
+                ..BuildOptions::default()
+            },
+            EngineType::Zola => BuildOptions::default(),
+        };
+        Self {
+            name: "production".to_string(),
+            options,
+        }
+    }
+
+    pub fn builtin(engine: &EngineType) -> Vec<Self> {
+        vec![Self::preview(engine), Self::production(engine)]
+    }
+}
+
+/// 预览服务器选项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServeOptions {
+    pub port: u16,
+    /// 局域网预览（默认关闭）：绑定到该网卡地址而不是 127.0.0.1，
+    /// 同一网络中的手机等设备也能访问。`0.0.0.0` 表示所有网卡
+    #[serde(default)]
+    pub lan_address: Option<IpAddr>,
+    /// 绑定所有网卡时，页面链接使用的局域网地址，由界面在候选地址中选择
+    #[serde(default)]
+    pub advertise_address: Option<IpAddr>,
+}
+
+impl ServeOptions {
+    pub fn local(port: u16) -> Self {
+        Self {
+            port,
+            lan_address: None,
+            advertise_address: None,
         }
     }
 }
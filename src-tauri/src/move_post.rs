@@ -0,0 +1,574 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::error::{AppError, Result};
+use crate::front_matter::edit::{read_list, set_field, update_list, FieldValue};
+use crate::front_matter::{parse_front_matter, split_front_matter};
+use crate::links::internal::{join_url, normalize_path, page_url, Resolved, SiteIndex};
+use crate::links::{is_external, markdown_links, split_anchor, FoundLink, LinkKind};
+use crate::markdown::MarkdownConfig;
+use crate::utils::{percent_decode, resolve_project_file, write_all_atomic};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MoveRequest {
+    /// 相对于站点根目录：单篇文章 `content/posts/foo.md`，或页面包目录 `content/posts/foo`
+    pub from: String,
+    /// 新位置，与 `from` 同类（文件或目录）；只改 slug 时与 `from` 相同
+    pub to: String,
+    #[serde(default)]
+    pub slug: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LinkEdit {
+    pub line: usize,
+    pub old: String,
+    pub new: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileChange {
+    /// 移动前的路径，相对于站点根目录
+    pub path: String,
+    pub edits: Vec<LinkEdit>,
+    /// Front Matter 的改动说明，例如 `aliases += /posts/foo/`
+    pub front_matter: Vec<String>,
+}
+
+/// 移动前的试运行结果，列出所有将被修改的文件
+#[derive(Debug, Clone, Serialize)]
+pub struct MovePlan {
+    pub from: String,
+    pub to: String,
+    pub old_url: String,
+    pub new_url: String,
+    /// 加到文章 `aliases` 中的旧地址
+    pub alias: Option<String>,
+    pub changes: Vec<FileChange>,
+    /// 无法自动改写、需要手动处理的链接和地址
+    pub warnings: Vec<String>,
+}
+
+struct PreparedMove {
+    plan: MovePlan,
+    source: PathBuf,
+    target: PathBuf,
+    writes: Vec<(PathBuf, String)>,
+}
+
+/// 试运行：计算新地址和需要改写的链接，不修改任何文件
+pub fn plan_move(project_path: &Path, config: &MarkdownConfig, request: &MoveRequest) -> Result<MovePlan> {
+    Ok(prepare(project_path, config, request)?.plan)
+}
+
+/// 改写链接、写入别名后移动文件或页面包
+pub fn apply_move(project_path: &Path, config: &MarkdownConfig, request: &MoveRequest) -> Result<MovePlan> {
+    let prepared = prepare(project_path, config, request)?;
+
+    write_all_atomic(&prepared.writes)?;
+    if prepared.source != prepared.target {
+        if let Some(parent) = prepared.target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(&prepared.source, &prepared.target)?;
+    }
+
+    Ok(prepared.plan)
+}
+
+fn prepare(project_path: &Path, config: &MarkdownConfig, request: &MoveRequest) -> Result<PreparedMove> {
+    let content_dir = project_path.join("content");
+    let mut source = resolve_project_file(project_path, &request.from)?;
+    let mut target = resolve_project_file(project_path, &request.to)?;
+    if !source.starts_with(&content_dir) || !target.starts_with(&content_dir) {
+        return Err(AppError::InvalidPath("Posts can only be moved within content/".to_string()));
+    }
+
+    // 页面包按整个目录移动；传入包内的 index.md 时也按目录处理
+    let is_bundle_index = |path: &Path| {
+        matches!(path.file_name().and_then(|n| n.to_str()), Some("index.md") | Some("_index.md"))
+    };
+    if source.is_file() && is_bundle_index(&source) {
+        source = source.parent().unwrap_or(&source).to_path_buf();
+        if is_bundle_index(&target) {
+            target = target.parent().unwrap_or(&target).to_path_buf();
+        }
+    }
+    let bundle = source.is_dir();
+    let (old_file, new_file) = if bundle {
+        let index = ["index.md", "_index.md"]
+            .iter()
+            .map(|name| source.join(name))
+            .find(|path| path.is_file())
+            .ok_or_else(|| AppError::PathNotFound(format!("{}/index.md", request.from)))?;
+        let name = index.file_name().unwrap_or_default().to_owned();
+        (index, target.join(name))
+    } else if source.is_file() {
+        (source.clone(), target.clone())
+    } else {
+        return Err(AppError::PathNotFound(request.from.clone()));
+    };
+
+    if source != target && target.exists() {
+        return Err(AppError::FileOperationFailed(format!("Target already exists: {}", request.to)));
+    }
+    if target.starts_with(&source) && source != target {
+        return Err(AppError::InvalidPath(request.to.clone()));
+    }
+
+    let site = SiteIndex::build(project_path, config)?;
+    let moved = site
+        .page_index(&old_file)
+        .ok_or_else(|| AppError::PathNotFound(request.from.clone()))?;
+    let old_url = site.pages[moved].url.clone();
+
+    let moved_content = fs::read_to_string(&old_file)?;
+    let mut front_matter = split_front_matter(&moved_content)
+        .front_matter
+        .and_then(|block| parse_front_matter(&block).ok())
+        .unwrap_or(Value::Object(Default::default()));
+    let original_front_matter = front_matter.clone();
+    if let (Some(slug), Some(object)) = (&request.slug, front_matter.as_object_mut()) {
+        object.retain(|key, _| !key.eq_ignore_ascii_case("slug"));
+        object.insert("slug".to_string(), Value::String(slug.clone()));
+    }
+    let new_relative = content_relative(&content_dir, &new_file);
+    let new_url = page_url(&new_relative, &front_matter, &site.permalinks);
+
+    // permalinks 模式中有无法解析的占位符时，算出的地址不可信，不写别名
+    let mut warnings = Vec::new();
+    let old_relative = content_relative(&content_dir, &old_file);
+    for (relative, matter) in [(&old_relative, &original_front_matter), (&new_relative, &front_matter)] {
+        if let Some(Err(token)) = site.permalinks.expand(relative, matter) {
+            warnings.push(format!("permalinks: cannot resolve {} for {}; add an alias manually", token, relative));
+        }
+    }
+    let alias = Some(old_url.clone())
+        .filter(|_| warnings.is_empty())
+        .filter(|old| *old != new_url)
+        .filter(|old| !read_list(&moved_content, &["aliases"]).contains(old));
+
+    let context = RewriteContext {
+        site: &site,
+        moved,
+        old_file: &old_file,
+        new_file: &new_file,
+        source: &source,
+        bundle,
+        old_url: &old_url,
+        new_url: &new_url,
+        new_relative: &new_relative,
+    };
+
+    let mut changes = Vec::new();
+    let mut writes = Vec::new();
+    for (index, page) in site.pages.iter().enumerate() {
+        let Ok(content) = fs::read_to_string(&page.file) else {
+            continue;
+        };
+        let relative = relative_to(project_path, &page.file);
+
+        let mut replacements = Vec::new();
+        let mut used = HashSet::new();
+        for link in markdown_links(&content, config) {
+            let Some(new_target) = context.rewrite(index, &link) else {
+                continue;
+            };
+            match locate_target(&content, &link, &mut used) {
+                Some(range) => replacements.push((range, link.line, link.target.clone(), new_target)),
+                None => warnings.push(format!("{}:{}: {}", relative, link.line, link.target)),
+            }
+        }
+
+        let mut updated = content.clone();
+        replacements.sort_by_key(|(range, ..)| std::cmp::Reverse(range.start));
+        for (range, _, _, new_target) in &replacements {
+            updated.replace_range(range.clone(), new_target);
+        }
+        replacements.reverse();
+        let edits: Vec<LinkEdit> = replacements
+            .into_iter()
+            .map(|(_, line, old, new)| LinkEdit { line, old, new })
+            .collect();
+
+        let mut front_matter_notes = Vec::new();
+        if index == moved {
+            if let Some(alias) = &alias {
+                updated = update_list(&updated, &["aliases"], |aliases| aliases.push(alias.clone()))?;
+                front_matter_notes.push(format!("aliases += {}", alias));
+            }
+            if let Some(slug) = &request.slug {
+                updated = set_field(&updated, &["slug"], &FieldValue::String(slug.clone()))?;
+                front_matter_notes.push(format!("slug = {}", slug));
+            }
+        }
+
+        if updated != content {
+            writes.push((page.file.clone(), updated));
+            changes.push(FileChange {
+                path: relative,
+                edits,
+                front_matter: front_matter_notes,
+            });
+        }
+    }
+
+    Ok(PreparedMove {
+        plan: MovePlan {
+            from: request.from.clone(),
+            to: request.to.clone(),
+            old_url,
+            new_url,
+            alias,
+            changes,
+            warnings,
+        },
+        source,
+        target,
+        writes,
+    })
+}
+
+struct RewriteContext<'a> {
+    site: &'a SiteIndex,
+    moved: usize,
+    old_file: &'a Path,
+    new_file: &'a Path,
+    /// 被移动的文件或页面包目录
+    source: &'a Path,
+    bundle: bool,
+    old_url: &'a str,
+    new_url: &'a str,
+    new_relative: &'a str,
+}
+
+impl RewriteContext<'_> {
+    /// 返回链接的新目标；不需要改写时返回 None
+    fn rewrite(&self, page_index: usize, link: &FoundLink) -> Option<String> {
+        let target = link.target.trim();
+        let (path, anchor) = split_anchor(target);
+        if path.is_empty() || is_external(target) {
+            return None;
+        }
+        let suffix = anchor.map(|a| format!("#{}", a)).unwrap_or_default();
+        let resolved = self.site.resolve(page_index, link)?.ok()?;
+        let page = &self.site.pages[page_index];
+        let page_dir = page.file.parent()?;
+
+        if resolved == Resolved::Page(self.moved) {
+            let new_target = if link.kind == LinkKind::Ref {
+                // 只写了文件名、且文件名不变的 ref 不需要改写
+                let name_only = !path.contains('/');
+                if name_only && self.old_file.file_name() == self.new_file.file_name() && !self.bundle {
+                    return None;
+                }
+                let mut new_path = self.ref_path(path.ends_with(".md"));
+                if path.starts_with('/') {
+                    new_path.insert(0, '/');
+                }
+                new_path
+            } else if path.starts_with("@/") {
+                format!("@/{}", self.new_relative)
+            } else if page_index != self.moved && self.points_at_file(page_dir, path) {
+                let to = if path.ends_with(".md") { self.new_file.to_path_buf() } else { self.new_file.parent()?.to_path_buf() };
+                let mut relative = relative_between(page_dir, &to);
+                if path.ends_with('/') && !relative.ends_with('/') {
+                    relative.push('/');
+                }
+                relative
+            } else {
+                self.absolute_url(path, self.new_url)
+            };
+            let new_target = format!("{}{}", new_target, suffix);
+            return (new_target != target).then_some(new_target);
+        }
+
+        // 其他页面引用页面包中的资源（按文件路径或绝对地址）
+        if self.bundle && page_index != self.moved && resolved == Resolved::Asset {
+            let decoded = percent_decode(path).unwrap_or_else(|| path.to_string());
+            if !decoded.starts_with('/') {
+                let file = normalize_path(&page_dir.join(&decoded));
+                if let Ok(rest) = file.strip_prefix(self.source) {
+                    let new_file = self.new_file.parent()?.join(rest);
+                    return Some(format!("{}{}", relative_between(page_dir, &new_file), suffix));
+                }
+            }
+            let url = decoded
+                .strip_prefix(self.site.base_path.as_str())
+                .filter(|_| !self.site.base_path.is_empty())
+                .unwrap_or(&decoded);
+            if let Some(rest) = url.strip_prefix(self.old_url).filter(|_| url.starts_with('/')) {
+                let new_url = format!("{}{}", self.new_url, rest);
+                return Some(format!("{}{}", self.absolute_url(path, &new_url), suffix));
+            }
+        }
+
+        // 被移动的文章里指向别处的相对链接，移动后会失效，改成不依赖位置的写法
+        if page_index == self.moved && !path.starts_with('/') && !path.starts_with("@/") {
+            let new_dir = self.new_file.parent()?;
+            return match resolved {
+                Resolved::Page(other) if link.kind == LinkKind::Ref => {
+                    let from_root = self.site.content_dir.join(path);
+                    let found = [from_root.clone(), from_root.with_extension("md"), from_root.join("index.md"), from_root.join("_index.md")]
+                        .iter()
+                        .any(|p| p.is_file());
+                    (!found).then(|| {
+                        format!("/{}{}", content_relative(&self.site.content_dir, &self.site.pages[other].file), suffix)
+                    })
+                }
+                Resolved::Page(other) => Some(format!("{}{}", self.site.pages[other].url, suffix)),
+                Resolved::Asset => {
+                    let decoded = percent_decode(path).unwrap_or_else(|| path.to_string());
+                    let file = normalize_path(&page_dir.join(&decoded));
+                    if file.starts_with(self.source) {
+                        None
+                    } else if file.is_file() {
+                        Some(format!("{}{}", relative_between(new_dir, &file), suffix))
+                    } else {
+                        Some(format!("{}{}", join_url(&page.url, &decoded), suffix))
+                    }
+                }
+                Resolved::Missing => None,
+            };
+        }
+
+        None
+    }
+
+    /// 链接是否按文件路径（而不是页面地址）指向被移动的文章
+    fn points_at_file(&self, page_dir: &Path, path: &str) -> bool {
+        if path.starts_with('/') {
+            return false;
+        }
+        let decoded = percent_decode(path).unwrap_or_else(|| path.to_string());
+        let resolved = normalize_path(&page_dir.join(decoded.trim_end_matches('/')));
+        resolved == self.old_file || (self.bundle && resolved == self.source)
+    }
+
+    fn ref_path(&self, with_extension: bool) -> String {
+        if self.bundle && !with_extension {
+            return self
+                .new_relative
+                .rsplit_once('/')
+                .map(|(dir, _)| dir.to_string())
+                .unwrap_or_default();
+        }
+        if with_extension {
+            self.new_relative.to_string()
+        } else {
+            self.new_relative.trim_end_matches(".md").to_string()
+        }
+    }
+
+    /// 原链接带有 baseURL 中的路径前缀时保留该前缀
+    fn absolute_url(&self, original: &str, url: &str) -> String {
+        let base = &self.site.base_path;
+        if !base.is_empty() && original.starts_with(base.as_str()) {
+            format!("{}{}", base, url)
+        } else {
+            url.to_string()
+        }
+    }
+}
+
+/// 在链接的原文范围内找到目标文本的位置；同一范围内有多个链接时跳过已经用过的位置
+fn locate_target(content: &str, link: &FoundLink, used: &mut HashSet<usize>) -> Option<std::ops::Range<usize>> {
+    let span = link.span.start.min(content.len())..link.span.end.min(content.len());
+    let text = content.get(span.clone())?;
+    let target = link.target.as_str();
+    let mut from = 0;
+    while let Some(found) = text[from..].find(target) {
+        let start = span.start + from + found;
+        if used.insert(start) {
+            return Some(start..start + target.len());
+        }
+        from += found + target.len().max(1);
+    }
+    None
+}
+
+fn relative_between(from_dir: &Path, to: &Path) -> String {
+    let from: Vec<Component> = from_dir.components().collect();
+    let to_components: Vec<Component> = to.components().collect();
+    let common = from
+        .iter()
+        .zip(&to_components)
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut parts: Vec<String> = vec!["..".to_string(); from.len() - common];
+    parts.extend(
+        to_components[common..]
+            .iter()
+            .map(|c| c.as_os_str().to_string_lossy().to_string()),
+    );
+    parts.join("/")
+}
+
+fn content_relative(content_dir: &Path, file: &Path) -> String {
+    file.strip_prefix(content_dir)
+        .unwrap_or(file)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+fn relative_to(project_path: &Path, file: &Path) -> String {
+    file.strip_prefix(project_path)
+        .unwrap_or(file)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn site(files: &[(&str, &str)]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("config.toml"), "baseURL = \"https://example.com/\"\n").unwrap();
+        for (path, content) in files {
+            let file = dir.path().join(path);
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+            fs::write(file, content).unwrap();
+        }
+        dir
+    }
+
+    fn request(from: &str, to: &str, slug: Option<&str>) -> MoveRequest {
+        MoveRequest {
+            from: from.to_string(),
+            to: to.to_string(),
+            slug: slug.map(str::to_string),
+        }
+    }
+
+    fn read(dir: &tempfile::TempDir, path: &str) -> String {
+        fs::read_to_string(dir.path().join(path)).unwrap()
+    }
+
+    #[test]
+    fn moves_file_and_rewrites_links() {
+        let dir = site(&[
+            ("content/posts/a.md", "+++\ntitle = \"A\"\n+++\nBody\n"),
+            ("content/posts/b.md", "+++\ntitle = \"B\"\n+++\n[rel](a.md) and [abs](/posts/a/)\n"),
+        ]);
+        let config = MarkdownConfig::default();
+        let plan = apply_move(dir.path(), &config, &request("content/posts/a.md", "content/notes/a.md", None)).unwrap();
+
+        assert_eq!((plan.old_url.as_str(), plan.new_url.as_str()), ("/posts/a/", "/notes/a/"));
+        assert!(!dir.path().join("content/posts/a.md").exists());
+        assert_eq!(read_list(&read(&dir, "content/notes/a.md"), &["aliases"]), vec!["/posts/a/"]);
+        assert!(read(&dir, "content/posts/b.md").contains("[rel](../notes/a.md) and [abs](/notes/a/)"));
+        let b = plan.changes.iter().find(|c| c.path == "content/posts/b.md").unwrap();
+        assert_eq!(b.edits.len(), 2);
+        assert_eq!(b.edits[0].line, 4);
+    }
+
+    #[test]
+    fn moves_bundle_with_resources() {
+        let dir = site(&[
+            ("content/posts/trip/index.md", "+++\ntitle = \"Trip\"\n+++\n![](photo.jpg)\n"),
+            ("content/posts/trip/photo.jpg", "jpg"),
+            ("content/posts/other.md", "+++\ntitle = \"Other\"\n+++\n![](trip/photo.jpg)\n"),
+        ]);
+        let config = MarkdownConfig::default();
+        let plan = apply_move(dir.path(), &config, &request("content/posts/trip", "content/travel/trip", None)).unwrap();
+
+        assert_eq!(plan.new_url, "/travel/trip/");
+        assert!(dir.path().join("content/travel/trip/photo.jpg").is_file());
+        assert!(!dir.path().join("content/posts/trip").exists());
+        // 包内的相对引用随包一起移动，不需要改写
+        assert!(read(&dir, "content/travel/trip/index.md").contains("![](photo.jpg)"));
+        assert!(read(&dir, "content/posts/other.md").contains("![](../travel/trip/photo.jpg)"));
+    }
+
+    #[test]
+    fn slug_only_change_keeps_file_in_place() {
+        let dir = site(&[("content/posts/a.md", "+++\ntitle = \"A\"\n+++\nBody\n")]);
+        let config = MarkdownConfig::default();
+        let plan = apply_move(dir.path(), &config, &request("content/posts/a.md", "content/posts/a.md", Some("renamed"))).unwrap();
+
+        assert_eq!(plan.new_url, "/posts/renamed/");
+        assert_eq!(plan.alias.as_deref(), Some("/posts/a/"));
+        let content = read(&dir, "content/posts/a.md");
+        assert!(content.contains("slug = \"renamed\""));
+        assert_eq!(read_list(&content, &["aliases"]), vec!["/posts/a/"]);
+    }
+
+    #[test]
+    fn rewrites_ref_and_relref_targets() {
+        let dir = site(&[
+            ("content/posts/a.md", "+++\ntitle = \"A\"\n+++\nBody\n"),
+            (
+                "content/b.md",
+                "+++\ntitle = \"B\"\n+++\n[x]({{< relref \"posts/a.md\" >}}) [y]({{< ref \"/posts/a\" >}})\n",
+            ),
+        ]);
+        let config = MarkdownConfig::default();
+        apply_move(dir.path(), &config, &request("content/posts/a.md", "content/notes/a.md", None)).unwrap();
+
+        let b = read(&dir, "content/b.md");
+        assert!(b.contains("{{< relref \"notes/a.md\" >}}"), "{}", b);
+        assert!(b.contains("{{< ref \"/notes/a\" >}}"), "{}", b);
+    }
+
+    #[test]
+    fn existing_alias_is_not_duplicated() {
+        let dir = site(&[("content/posts/a.md", "+++\ntitle = \"A\"\naliases = [\"/posts/a/\"]\n+++\nBody\n")]);
+        let config = MarkdownConfig::default();
+        let plan = apply_move(dir.path(), &config, &request("content/posts/a.md", "content/notes/a.md", None)).unwrap();
+
+        assert_eq!(plan.alias, None);
+        assert_eq!(read_list(&read(&dir, "content/notes/a.md"), &["aliases"]), vec!["/posts/a/"]);
+    }
+
+    #[test]
+    fn rejects_existing_target() {
+        let dir = site(&[
+            ("content/posts/a.md", "+++\ntitle = \"A\"\n+++\n"),
+            ("content/posts/b.md", "+++\ntitle = \"B\"\n+++\n"),
+        ]);
+        let config = MarkdownConfig::default();
+        let result = plan_move(dir.path(), &config, &request("content/posts/a.md", "content/posts/b.md", None));
+
+        assert!(matches!(result, Err(AppError::FileOperationFailed(_))));
+        assert_eq!(read(&dir, "content/posts/a.md"), "+++\ntitle = \"A\"\n+++\n");
+    }
+
+    #[test]
+    fn follows_section_permalinks() {
+        let dir = site(&[
+            ("content/posts/a.md", "+++\ntitle = \"Hello World\"\ndate = 2024-03-05T10:00:00Z\n+++\n"),
+            ("content/b.md", "+++\ntitle = \"B\"\n+++\n[a](/2024/03/hello-world/)\n"),
+        ]);
+        fs::write(
+            dir.path().join("config.toml"),
+            "baseURL = \"https://example.com/\"\n[permalinks]\nposts = \"/:year/:month/:slug/\"\n",
+        )
+        .unwrap();
+        let config = MarkdownConfig::default();
+        let plan = apply_move(dir.path(), &config, &request("content/posts/a.md", "content/posts/a.md", Some("hi"))).unwrap();
+
+        assert_eq!((plan.old_url.as_str(), plan.new_url.as_str()), ("/2024/03/hello-world/", "/2024/03/hi/"));
+        assert_eq!(plan.alias.as_deref(), Some("/2024/03/hello-world/"));
+        assert!(read(&dir, "content/b.md").contains("[a](/2024/03/hi/)"));
+    }
+
+    #[test]
+    fn unresolved_permalink_warns_instead_of_aliasing() {
+        let dir = site(&[("content/posts/a.md", "+++\ntitle = \"A\"\n+++\n")]);
+        fs::write(
+            dir.path().join("config.toml"),
+            "[permalinks]\nposts = \"/:sections[1:]/:slug/\"\n",
+        )
+        .unwrap();
+        let config = MarkdownConfig::default();
+        let plan = plan_move(dir.path(), &config, &request("content/posts/a.md", "content/posts/b.md", None)).unwrap();
+
+        assert_eq!(plan.alias, None);
+        assert_eq!(plan.warnings.len(), 2);
+        assert!(plan.warnings[0].contains(":sections[1:]"));
+    }
+}
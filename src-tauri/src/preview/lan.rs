@@ -0,0 +1,139 @@
+use std::net::{IpAddr, SocketAddr};
+use serde::Serialize;
+use crate::error::{AppError, Result};
+use crate::preview::localhost;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NetworkInterface {
+    pub name: String,
+    pub address: IpAddr,
+}
+
+/// 列出可用于局域网预览的网卡（IPv4，不含回环地址）
+pub fn list_lan_interfaces() -> Result<Vec<NetworkInterface>> {
+    let interfaces = if_addrs::get_if_addrs()?;
+
+    Ok(interfaces
+        .into_iter()
+        .filter(|iface| !iface.is_loopback() && iface.ip().is_ipv4())
+        .map(|iface| NetworkInterface {
+            address: iface.ip(),
+            name: iface.name,
+        })
+        .collect())
+}
+
+/// 其他设备访问预览时可以使用的所有主机地址：绑定所有网卡时为每块局域网网卡的地址
+pub fn advertised_addresses(bind: IpAddr) -> Result<Vec<IpAddr>> {
+    if !bind.is_unspecified() {
+        return Ok(vec![bind]);
+    }
+    Ok(list_lan_interfaces()?.into_iter().map(|iface| iface.address).collect())
+}
+
+/// 引擎生成链接时使用的主机地址。绑定所有网卡时由界面在候选地址中选择；
+/// 没有选择且只有一块局域网网卡时使用它，有多块时返回错误并列出候选地址
+pub fn link_address(bind: IpAddr, chosen: Option<IpAddr>) -> Result<IpAddr> {
+    if !bind.is_unspecified() {
+        return Ok(bind);
+    }
+    if let Some(chosen) = chosen {
+        return Ok(chosen);
+    }
+    pick_link_address(&advertised_addresses(bind)?)
+}
+
+fn pick_link_address(candidates: &[IpAddr]) -> Result<IpAddr> {
+    match candidates {
+        [] => Err(AppError::EngineError("No LAN network interface found".to_string())),
+        [only] => Ok(*only),
+        _ => Err(AppError::EngineError(format!(
+            "Several LAN addresses are available, choose one: {}",
+            candidates.iter().map(IpAddr::to_string).collect::<Vec<_>>().join(", ")
+        ))),
+    }
+}
+
+/// 探测服务器是否就绪时连接的地址
+pub fn probe_address(bind: IpAddr, port: u16) -> SocketAddr {
+    if bind.is_unspecified() {
+        SocketAddr::new(localhost(), port)
+    } else {
+        SocketAddr::new(bind, port)
+    }
+}
+
+/// 预览站点可以被访问到的所有地址
+pub fn reachable_urls(bind: IpAddr, port: u16) -> Result<Vec<String>> {
+    let lan = if bind.is_unspecified() {
+        advertised_addresses(bind)?
+    } else {
+        Vec::new()
+    };
+    Ok(urls_for(bind, port, &lan))
+}
+
+fn urls_for(bind: IpAddr, port: u16, lan: &[IpAddr]) -> Vec<String> {
+    let addresses = if bind.is_unspecified() {
+        std::iter::once(localhost()).chain(lan.iter().copied()).collect()
+    } else {
+        vec![bind]
+    };
+    addresses
+        .into_iter()
+        .map(|address| format!("http://{}", SocketAddr::new(address, port)))
+        .collect()
+}
+
+/// URL 中的主机部分，IPv6 地址加方括号
+pub fn url_host(address: IpAddr) -> String {
+    match address {
+        IpAddr::V4(v4) => v4.to_string(),
+        IpAddr::V6(v6) => format!("[{}]", v6),
+    }
+}
+
+/// 不带端口的站点根地址，用作引擎的 baseURL
+pub fn base_url_for(address: IpAddr) -> String {
+    format!("http://{}/", url_host(address))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    const LAN: IpAddr = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 20));
+    const LAN6: IpAddr = IpAddr::V6(Ipv6Addr::new(0xfd00, 0, 0, 0, 0, 0, 0, 0x20));
+
+    #[test]
+    fn base_url_brackets_ipv6() {
+        assert_eq!(base_url_for(LAN), "http://192.168.1.20/");
+        assert_eq!(base_url_for(LAN6), "http://[fd00::20]/");
+        assert_eq!(url_host(LAN6), "[fd00::20]");
+    }
+
+    #[test]
+    fn reachable_urls_for_each_bind_address() {
+        assert_eq!(urls_for(LAN, 1313, &[]), vec!["http://192.168.1.20:1313"]);
+        assert_eq!(urls_for(LAN6, 1313, &[]), vec!["http://[fd00::20]:1313"]);
+        let any = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
+        assert_eq!(
+            urls_for(any, 1313, &[LAN, IpAddr::V4(Ipv4Addr::new(10, 0, 0, 5))]),
+            vec!["http://127.0.0.1:1313", "http://192.168.1.20:1313", "http://10.0.0.5:1313"]
+        );
+        assert_eq!(probe_address(any, 1313), SocketAddr::new(localhost(), 1313));
+        assert_eq!(probe_address(LAN6, 1313).to_string(), "[fd00::20]:1313");
+    }
+
+    #[test]
+    fn link_address_needs_a_choice_between_several_interfaces() {
+        let any = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
+        assert_eq!(link_address(LAN, None).unwrap(), LAN);
+        assert_eq!(link_address(any, Some(LAN6)).unwrap(), LAN6);
+        assert_eq!(pick_link_address(&[LAN]).unwrap(), LAN);
+        let error = pick_link_address(&[LAN, LAN6]).unwrap_err().to_string();
+        assert!(error.contains("192.168.1.20, fd00::20"), "{}", error);
+        assert!(pick_link_address(&[]).is_err());
+    }
+}
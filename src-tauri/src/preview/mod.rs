@@ -0,0 +1,74 @@
+pub mod lan;
+pub mod port;
+pub mod static_server;
+
+pub use lan::{list_lan_interfaces, NetworkInterface};
+pub use port::{find_free_port, localhost, reserve_port, PortRange};
+pub use static_server::StaticPreviewServer;
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tokio::process::Child;
+
+/// 正在运行的静态预览服务器，按项目路径索引
+#[derive(Default)]
+pub struct PreviewServers {
+    servers: Mutex<HashMap<PathBuf, StaticPreviewServer>>,
+}
+
+impl PreviewServers {
+    /// 登记新的服务器，同一项目已有的服务器会被停止
+    pub fn insert(&self, project_path: PathBuf, server: StaticPreviewServer) {
+        let mut servers = self.servers.lock().unwrap();
+        if let Some(old) = servers.insert(project_path, server) {
+            old.stop();
+        }
+    }
+
+    pub fn stop(&self, project_path: &Path) -> bool {
+        let mut servers = self.servers.lock().unwrap();
+        match servers.remove(project_path) {
+            Some(server) => {
+                server.stop();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 构建成功后通知该项目的预览页面刷新
+    pub fn notify_reload(&self, project_path: &Path) {
+        let servers = self.servers.lock().unwrap();
+        if let Some(server) = servers.get(project_path) {
+            server.notify_reload();
+        }
+    }
+}
+
+/// 引擎自带的预览服务器进程（hugo server / zola serve），按项目路径索引
+#[derive(Default)]
+pub struct EngineServers {
+    servers: Mutex<HashMap<PathBuf, Child>>,
+}
+
+impl EngineServers {
+    /// 登记新的服务器进程，同一项目已有的进程会被终止
+    pub fn insert(&self, project_path: PathBuf, server: Child) {
+        let mut servers = self.servers.lock().unwrap();
+        if let Some(mut old) = servers.insert(project_path, server) {
+            let _ = old.start_kill();
+        }
+    }
+
+    pub fn stop(&self, project_path: &Path) -> bool {
+        let mut servers = self.servers.lock().unwrap();
+        match servers.remove(project_path) {
+            Some(mut server) => {
+                let _ = server.start_kill();
+                true
+            }
+            None => false,
+        }
+    }
+}
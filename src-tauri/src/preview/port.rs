@@ -0,0 +1,55 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener};
+use serde::{Deserialize, Serialize};
+use crate::error::{AppError, Result};
+
+/// 预览服务器可使用的端口范围（含两端）
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct PortRange {
+    pub start: u16,
+    pub end: u16,
+}
+
+impl Default for PortRange {
+    fn default() -> Self {
+        Self {
+            start: 1313,
+            end: 1413,
+        }
+    }
+}
+
+/// 绑定并占住一个空闲端口：优先使用 `preferred`，否则在范围内依次尝试
+pub fn reserve_port(host: IpAddr, preferred: Option<u16>, range: PortRange) -> Result<TcpListener> {
+    if range.start > range.end {
+        return Err(AppError::PortUnavailable(format!(
+            "invalid port range {}-{}",
+            range.start, range.end
+        )));
+    }
+
+    let candidates = preferred
+        .into_iter()
+        .chain(range.start..=range.end)
+        .filter(|port| *port != 0);
+
+    for port in candidates {
+        if let Ok(listener) = TcpListener::bind(SocketAddr::new(host, port)) {
+            return Ok(listener);
+        }
+    }
+
+    Err(AppError::PortUnavailable(format!(
+        "no free port on {} in range {}-{}",
+        host, range.start, range.end
+    )))
+}
+
+/// 找到一个空闲端口后立即释放，交给外部进程（hugo/zola server）使用
+pub fn find_free_port(host: IpAddr, preferred: Option<u16>, range: PortRange) -> Result<u16> {
+    let listener = reserve_port(host, preferred, range)?;
+    Ok(listener.local_addr()?.port())
+}
+
+pub fn localhost() -> IpAddr {
+    IpAddr::V4(Ipv4Addr::LOCALHOST)
+}
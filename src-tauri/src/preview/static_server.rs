@@ -0,0 +1,511 @@
+use std::net::SocketAddr;
+use std::path::{Component, Path, PathBuf};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, watch};
+use tokio::task::JoinHandle;
+use crate::error::{AppError, Result};
+use crate::preview::lan::probe_address;
+use crate::utils::percent_decode;
+
+const LIVE_RELOAD_PATH: &str = "/__pineapple/livereload";
+const LIVE_RELOAD_SCRIPT: &str = r#"<script>(function(){var s=new EventSource("/__pineapple/livereload");s.onmessage=function(){location.reload();};})();</script>"#;
+const MAX_REQUEST_HEAD: usize = 16 * 1024;
+
+/// 通过 HTTP 提供构建输出目录（例如 `public/`）。
+///
+/// 指向站点 baseURL 的绝对链接改写为访问者使用的地址（请求的 `Host`），
+/// baseURL 带子路径时以该子路径开头的站内链接改写为从根目录开始；
+/// 每个 HTML 页面注入一小段实时刷新脚本，调用 `notify_reload` 时刷新页面。
+pub struct StaticPreviewServer {
+    addr: SocketAddr,
+    root: PathBuf,
+    reload_tx: broadcast::Sender<()>,
+    shutdown_tx: watch::Sender<bool>,
+    task: JoinHandle<()>,
+}
+
+impl StaticPreviewServer {
+    /// 在已经绑定的监听器上提供 `root`
+    pub fn start(listener: TcpListener, root: PathBuf, base_url: Option<String>) -> Result<Self> {
+        let root = root
+            .canonicalize()
+            .map_err(|_| AppError::PathNotFound(root.display().to_string()))?;
+        let bound = listener.local_addr()?;
+        let addr = probe_address(bound.ip(), bound.port());
+        let (reload_tx, _) = broadcast::channel(16);
+        let (shutdown_tx, mut shutdown_rx) = watch::channel(false);
+
+        let context = ServeContext::new(root.clone(), base_url, addr.to_string(), reload_tx.clone());
+
+        // 连接任务（尤其是实时刷新的事件流）会比接受连接的循环活得更久，
+        // 所以每个连接也要等待停止信号
+        let task = tokio::spawn(async move {
+            loop {
+                let stream = tokio::select! {
+                    accepted = listener.accept() => match accepted {
+                        Ok((stream, _)) => stream,
+                        Err(_) => break,
+                    },
+                    _ = shutdown_rx.wait_for(|stopped| *stopped) => break,
+                };
+                let context = context.clone();
+                let mut shutdown_rx = shutdown_rx.clone();
+                tokio::spawn(async move {
+                    tokio::select! {
+                        _ = handle_connection(stream, &context) => {}
+                        _ = shutdown_rx.wait_for(|stopped| *stopped) => {}
+                    }
+                });
+            }
+        });
+
+        Ok(Self {
+            addr,
+            root,
+            reload_tx,
+            shutdown_tx,
+            task,
+        })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.addr.port()
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// 本机访问的地址；绑定所有网卡时为回环地址
+    pub fn url(&self) -> String {
+        format!("http://{}/", self.addr)
+    }
+
+    /// 通知所有已连接的页面刷新
+    pub fn notify_reload(&self) {
+        let _ = self.reload_tx.send(());
+    }
+
+    /// 停止接受连接，并关闭已打开的连接
+    pub fn stop(&self) {
+        let _ = self.shutdown_tx.send(true);
+        self.task.abort();
+    }
+}
+
+impl Drop for StaticPreviewServer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[derive(Clone)]
+struct ServeContext {
+    root: PathBuf,
+    base_url: Option<String>,
+    /// 站点位于子路径时 `base_url` 中的路径部分，例如 `/blog`
+    base_path: Option<String>,
+    /// 请求没有可用的 `Host` 时使用的地址，例如 `127.0.0.1:1313`
+    default_host: String,
+    reload_tx: broadcast::Sender<()>,
+}
+
+impl ServeContext {
+    fn new(root: PathBuf, base_url: Option<String>, default_host: String, reload_tx: broadcast::Sender<()>) -> Self {
+        let base_url = base_url
+            .map(|url| url.trim_end_matches('/').to_string())
+            .filter(|url| !url.is_empty());
+        let base_path = base_url
+            .as_deref()
+            .and_then(|url| url.split_once("://"))
+            .and_then(|(_, rest)| rest.find('/').map(|pos| rest[pos..].to_string()));
+        Self {
+            root,
+            base_url,
+            base_path,
+            default_host,
+            reload_tx,
+        }
+    }
+}
+
+async fn handle_connection(mut stream: TcpStream, context: &ServeContext) -> std::io::Result<()> {
+    let mut buf = Vec::with_capacity(1024);
+    let mut chunk = [0u8; 1024];
+    loop {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(());
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if buf.windows(4).any(|w| w == b"\r\n\r\n") {
+            break;
+        }
+        if buf.len() > MAX_REQUEST_HEAD {
+            return write_response(&mut stream, "431 Request Header Fields Too Large", "text/plain", b"", false).await;
+        }
+    }
+
+    let head = String::from_utf8_lossy(&buf);
+    let mut parts = head.lines().next().unwrap_or("").split_whitespace();
+    let method = parts.next().unwrap_or("");
+    let target = parts.next().unwrap_or("/");
+
+    let head_only = match method {
+        "GET" => false,
+        "HEAD" => true,
+        _ => return write_response(&mut stream, "405 Method Not Allowed", "text/plain", b"", false).await,
+    };
+
+    let path = target.split(['?', '#']).next().unwrap_or("/");
+    if path == LIVE_RELOAD_PATH {
+        return serve_live_reload(stream, context).await;
+    }
+
+    let Some(relative) = sanitize_request_path(path) else {
+        return write_response(&mut stream, "400 Bad Request", "text/plain", b"", head_only).await;
+    };
+
+    let mut file_path = context.root.join(&relative);
+    if file_path.is_dir() {
+        if !path.ends_with('/') {
+            let location = format!("{}/", path);
+            let response = format!(
+                "HTTP/1.1 301 Moved Permanently\r\nLocation: {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                location
+            );
+            return stream.write_all(response.as_bytes()).await;
+        }
+        file_path = file_path.join("index.html");
+    }
+
+    let (status, file_path) = if file_path.is_file() {
+        ("200 OK", file_path)
+    } else {
+        ("404 Not Found", context.root.join("404.html"))
+    };
+
+    let body = match tokio::fs::read(&file_path).await {
+        Ok(body) => body,
+        Err(_) => {
+            return write_response(&mut stream, "404 Not Found", "text/plain", b"404 Not Found", head_only).await;
+        }
+    };
+
+    // 链接指向访问者使用的地址，局域网中的其他设备也能正常跳转
+    let host = request_host(&head).unwrap_or(&context.default_host);
+    let content_type = content_type_for(&file_path);
+    let body = rewrite_body(body, content_type, context, &format!("http://{}", host));
+    write_response(&mut stream, status, content_type, &body, head_only).await
+}
+
+/// 请求头中的 `Host`；只接受主机名、IP 地址和端口中会出现的字符，避免把任意文本写进页面
+fn request_host(head: &str) -> Option<&str> {
+    let host = head
+        .lines()
+        .skip(1)
+        .find_map(|line| line.split_once(':').filter(|(name, _)| name.trim().eq_ignore_ascii_case("host")))
+        .map(|(_, value)| value.trim())?;
+    let valid = !host.is_empty()
+        && host
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | ':' | '[' | ']'));
+    valid.then_some(host)
+}
+
+/// 保持连接作为事件流，每次重新构建后推送一条消息
+async fn serve_live_reload(mut stream: TcpStream, context: &ServeContext) -> std::io::Result<()> {
+    let mut reload_rx = context.reload_tx.subscribe();
+    stream
+        .write_all(
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n",
+        )
+        .await?;
+
+    loop {
+        match reload_rx.recv().await {
+            Ok(()) | Err(broadcast::error::RecvError::Lagged(_)) => {
+                stream.write_all(b"data: reload\n\n").await?;
+            }
+            Err(broadcast::error::RecvError::Closed) => return Ok(()),
+        }
+    }
+}
+
+/// 解码请求路径，拒绝任何跳出根目录的路径
+fn sanitize_request_path(path: &str) -> Option<PathBuf> {
+    let decoded = percent_decode(path)?;
+    let mut relative = PathBuf::new();
+    for component in Path::new(decoded.trim_start_matches('/')).components() {
+        match component {
+            Component::Normal(part) => relative.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    Some(relative)
+}
+
+fn rewrite_body(body: Vec<u8>, content_type: &str, context: &ServeContext, local_url: &str) -> Vec<u8> {
+    let is_html = content_type.starts_with("text/html");
+    let is_text = is_html
+        || content_type.starts_with("text/")
+        || content_type.starts_with("application/javascript")
+        || content_type.starts_with("application/json")
+        || content_type.starts_with("application/xml");
+    if !is_text {
+        return body;
+    }
+
+    let mut text = match String::from_utf8(body) {
+        Ok(text) => text,
+        Err(e) => return e.into_bytes(),
+    };
+
+    if let Some(base_url) = &context.base_url {
+        text = replace_base_url(&text, base_url, local_url);
+    }
+    if let Some(base_path) = &context.base_path {
+        text = rewrite_root_relative(&text, base_path);
+    }
+
+    if is_html {
+        match text.rfind("</body>") {
+            Some(pos) => text.insert_str(pos, LIVE_RELOAD_SCRIPT),
+            None => text.push_str(LIVE_RELOAD_SCRIPT),
+        }
+    }
+
+    text.into_bytes()
+}
+
+/// 只替换后面紧跟路径、引号、括号、空白或文本结尾的 baseURL，
+/// `https://example.com.cn` 这类只是前缀相同的地址保持不变
+fn replace_base_url(text: &str, base_url: &str, local_url: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(pos) = rest.find(base_url) {
+        let (before, after) = rest.split_at(pos);
+        let after = &after[base_url.len()..];
+        let ends_url = after.is_empty()
+            || after.starts_with(['/', '"', '\'', ')', '?', '#', '<'])
+            || after.starts_with(char::is_whitespace);
+        result.push_str(before);
+        result.push_str(if ends_url { local_url } else { base_url });
+        rest = after;
+    }
+    result.push_str(rest);
+    result
+}
+
+/// 构建输出从根目录提供，站点构建在 `https://example.com/blog` 时，
+/// `/blog/post/` 改写为 `/post/`。只改写紧跟在引号、`=` 或 `(` 之后的链接，正文保持不变
+fn rewrite_root_relative(text: &str, base_path: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(pos) = rest.find(base_path) {
+        let (before, after) = rest.split_at(pos);
+        let after = &after[base_path.len()..];
+        let starts_link = before.ends_with(['"', '\'', '=', '(']);
+        let ends_segment = after.is_empty() || after.starts_with(['/', '"', '\'', ')', '?', '#']);
+        result.push_str(before);
+        if starts_link && ends_segment {
+            if !after.starts_with('/') {
+                result.push('/');
+            }
+        } else {
+            result.push_str(base_path);
+        }
+        rest = after;
+    }
+    result.push_str(rest);
+    result
+}
+
+pub(crate) fn content_type_for(path: &Path) -> &'static str {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .unwrap_or_default();
+
+    match ext.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "application/javascript; charset=utf-8",
+        "json" => "application/json; charset=utf-8",
+        "xml" => "application/xml; charset=utf-8",
+        "txt" => "text/plain; charset=utf-8",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "ico" => "image/x-icon",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "pdf" => "application/pdf",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "wasm" => "application/wasm",
+        _ => "application/octet-stream",
+    }
+}
+
+async fn write_response(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &[u8],
+    head_only: bool,
+) -> std::io::Result<()> {
+    let header = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    );
+    stream.write_all(header.as_bytes()).await?;
+    if !head_only {
+        stream.write_all(body).await?;
+    }
+    stream.flush().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn context(base_url: Option<&str>) -> ServeContext {
+        ServeContext::new(
+            PathBuf::from("."),
+            base_url.map(str::to_string),
+            "127.0.0.1:1313".to_string(),
+            broadcast::channel(1).0,
+        )
+    }
+
+    fn rewrite(text: &str, content_type: &str, base_url: Option<&str>) -> String {
+        let body = rewrite_body(text.as_bytes().to_vec(), content_type, &context(base_url), "http://127.0.0.1:1313");
+        String::from_utf8(body).unwrap()
+    }
+
+    async fn get(port: u16, path: &str, host: &str) -> String {
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        let request = format!("GET {} HTTP/1.1\r\nHost: {}\r\n\r\n", path, host);
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[test]
+    fn rewrites_absolute_base_url() {
+        let html = r#"<a href="https://example.com/posts/">x</a>"#;
+        let out = rewrite(html, "application/xml", Some("https://example.com/"));
+        assert_eq!(out, r#"<a href="http://127.0.0.1:1313/posts/">x</a>"#);
+    }
+
+    #[test]
+    fn base_url_must_end_at_a_boundary() {
+        let text = concat!(
+            r#"<a href="https://example.com">home</a> (https://example.com) https://example.com"#,
+            r#" <a href="https://example.com.cn/">cn</a> https://example.community/ https://example.com-x"#,
+        );
+        let out = rewrite(text, "text/plain", Some("https://example.com"));
+        assert_eq!(
+            out,
+            concat!(
+                r#"<a href="http://127.0.0.1:1313">home</a> (http://127.0.0.1:1313) http://127.0.0.1:1313"#,
+                r#" <a href="https://example.com.cn/">cn</a> https://example.community/ https://example.com-x"#,
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn rewrites_to_the_host_the_visitor_used() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("feed.xml"), "<link>https://example.com/posts/</link>").unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server = StaticPreviewServer::start(
+            listener,
+            dir.path().to_path_buf(),
+            Some("https://example.com/".to_string()),
+        )
+        .unwrap();
+        assert_eq!(server.url(), format!("http://127.0.0.1:{}/", server.port()));
+
+        let response = get(server.port(), "/feed.xml", "192.168.1.20:1313").await;
+        assert!(response.ends_with("<link>http://192.168.1.20:1313/posts/</link>"), "{}", response);
+
+        // 不可信的 Host 退回到监听地址
+        let response = get(server.port(), "/feed.xml", "evil\"><script>").await;
+        let expected = format!("<link>http://127.0.0.1:{}/posts/</link>", server.port());
+        assert!(response.ends_with(&expected), "{}", response);
+    }
+
+    #[test]
+    fn rewrites_links_under_subpath() {
+        let css = r#"a{background:url(/blog/img/bg.png)} b{background:url("/blog")}"#;
+        let out = rewrite(css, "text/css", Some("https://example.com/blog/"));
+        assert_eq!(out, r#"a{background:url(/img/bg.png)} b{background:url("/")}"#);
+
+        let xml = concat!(
+            r#"<a href="https://example.com/blog/a/">a</a>"#,
+            r#"<a href='/blog/b/?x#y'>b</a><a href=/blog/c>c</a>"#,
+            r#"<a href="/blogroll/">kept</a> see /blog/ in prose"#,
+        );
+        let out = rewrite(xml, "application/xml", Some("https://example.com/blog"));
+        assert_eq!(
+            out,
+            concat!(
+                r#"<a href="http://127.0.0.1:1313/a/">a</a>"#,
+                r#"<a href='/b/?x#y'>b</a><a href=/c>c</a>"#,
+                r#"<a href="/blogroll/">kept</a> see /blog/ in prose"#,
+            )
+        );
+    }
+
+    #[test]
+    fn injects_live_reload_script_into_html_only() {
+        let out = rewrite("<html><body><p>hi</p></body></html>", "text/html; charset=utf-8", None);
+        assert_eq!(out, format!("<html><body><p>hi</p>{}</body></html>", LIVE_RELOAD_SCRIPT));
+
+        let out = rewrite("<p>fragment</p>", "text/html", None);
+        assert_eq!(out, format!("<p>fragment</p>{}", LIVE_RELOAD_SCRIPT));
+
+        let out = rewrite("body{}</body>", "text/css", None);
+        assert_eq!(out, "body{}</body>");
+    }
+
+    #[tokio::test]
+    async fn stop_closes_live_reload_connections() {
+        let dir = tempfile::tempdir().unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server = StaticPreviewServer::start(listener, dir.path().to_path_buf(), None).unwrap();
+
+        let mut stream = TcpStream::connect(("127.0.0.1", server.port())).await.unwrap();
+        stream
+            .write_all(format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", LIVE_RELOAD_PATH).as_bytes())
+            .await
+            .unwrap();
+        let mut buf = [0u8; 1024];
+        let n = stream.read(&mut buf).await.unwrap();
+        assert!(String::from_utf8_lossy(&buf[..n]).contains("text/event-stream"));
+
+        server.notify_reload();
+        let n = stream.read(&mut buf).await.unwrap();
+        assert_eq!(&buf[..n], b"data: reload\n\n");
+
+        server.stop();
+        let n = tokio::time::timeout(Duration::from_secs(5), stream.read(&mut buf))
+            .await
+            .expect("connection left open after stop")
+            .unwrap_or(0);
+        assert_eq!(n, 0);
+    }
+}
@@ -0,0 +1,474 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Utc};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use crate::error::{AppError, Result};
+use crate::settings::{ensure_project_data_dir, project_data_dir};
+use crate::utils::{resolve_project_file, walk_files, write_all_atomic};
+
+const UNDO_DIR: &str = "undo";
+const MANIFEST_FILE: &str = "manifest.json";
+const CONTEXT_LINES: usize = 2;
+
+/// 可以进行查找替换的目录
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReplaceScope {
+    Content,
+    Data,
+    Layouts,
+}
+
+impl ReplaceScope {
+    fn dir(&self) -> &'static str {
+        match self {
+            ReplaceScope::Content => "content",
+            ReplaceScope::Data => "data",
+            ReplaceScope::Layouts => "layouts",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FindOptions {
+    pub query: String,
+    pub replacement: String,
+    /// 按正则表达式查找，替换文本中可以使用 `$1`、`${name}`
+    pub regex: bool,
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    pub scopes: Vec<ReplaceScope>,
+    /// 相对于站点根目录的 glob，例如 `content/posts/**`；为空时包含全部文件
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+impl Default for FindOptions {
+    fn default() -> Self {
+        Self {
+            query: String::new(),
+            replacement: String::new(),
+            regex: false,
+            case_sensitive: false,
+            whole_word: false,
+            scopes: vec![ReplaceScope::Content],
+            include: Vec::new(),
+            exclude: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FindMatch {
+    /// 在文件内的序号，应用替换时用它选择要替换的匹配
+    pub index: usize,
+    /// 行号和列号，从 1 开始；列号按字符计
+    pub line: usize,
+    pub column: usize,
+    pub start: usize,
+    pub end: usize,
+    pub matched: String,
+    pub replacement: String,
+    pub line_text: String,
+    pub context_before: Vec<String>,
+    pub context_after: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileMatches {
+    /// 相对于站点根目录，使用 `/` 分隔
+    pub path: String,
+    /// 文件内容的 SHA-256，应用替换前用来确认文件没有被改动过
+    pub fingerprint: String,
+    pub matches: Vec<FindMatch>,
+}
+
+/// 要应用的替换：某个文件中选中的匹配
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReplaceSelection {
+    pub path: String,
+    pub fingerprint: String,
+    pub indices: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplaceResult {
+    /// 没有文件被修改时为 None，不生成撤销记录
+    pub undo_id: Option<String>,
+    pub files_changed: usize,
+    pub replacements: usize,
+}
+
+/// 撤销记录，保存在 `.pineapple/undo/<id>/manifest.json`，原文件内容保存在同一目录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UndoRecord {
+    pub id: String,
+    pub created_at: DateTime<Utc>,
+    pub description: String,
+    pub files: Vec<UndoFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UndoFile {
+    pub path: String,
+    pub backup: String,
+    /// 替换后文件的 SHA-256，撤销前确认文件没有再被修改
+    pub fingerprint: String,
+}
+
+struct Matcher {
+    regex: Regex,
+    expand: bool,
+    replacement: String,
+}
+
+impl Matcher {
+    fn new(options: &FindOptions) -> Result<Self> {
+        if options.query.is_empty() {
+            return Err(AppError::ParseError("Search text is empty".to_string()));
+        }
+
+        let mut pattern = if options.regex {
+            options.query.clone()
+        } else {
+            regex::escape(&options.query)
+        };
+        if options.whole_word {
+            pattern = format!(r"\b(?:{})\b", pattern);
+        }
+
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(!options.case_sensitive)
+            .multi_line(true)
+            .build()
+            .map_err(|e| AppError::ParseError(e.to_string()))?;
+
+        Ok(Self {
+            regex,
+            expand: options.regex,
+            replacement: options.replacement.clone(),
+        })
+    }
+
+    fn replacement_for(&self, caps: &regex::Captures) -> String {
+        if self.expand {
+            let mut out = String::new();
+            caps.expand(&self.replacement, &mut out);
+            out
+        } else {
+            self.replacement.clone()
+        }
+    }
+
+    fn find(&self, content: &str) -> Vec<FindMatch> {
+        let lines: Vec<&str> = content.lines().collect();
+        let line_starts: Vec<usize> = std::iter::once(0)
+            .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+
+        self.regex
+            .captures_iter(content)
+            .filter_map(|caps| {
+                let m = caps.get(0)?;
+                // 空匹配（例如 `^`）没有可替换的内容
+                if m.is_empty() {
+                    return None;
+                }
+                Some((m.start(), m.end(), m.as_str().to_string(), self.replacement_for(&caps)))
+            })
+            .enumerate()
+            .map(|(index, (start, end, matched, replacement))| {
+                let line = line_starts.partition_point(|&s| s <= start) - 1;
+                let column = content[line_starts[line]..start].chars().count() + 1;
+                let line_text = lines.get(line).copied().unwrap_or_default().to_string();
+                let context_before = lines[line.saturating_sub(CONTEXT_LINES)..line.min(lines.len())]
+                    .iter()
+                    .map(|l| l.to_string())
+                    .collect();
+                let context_after = lines
+                    .iter()
+                    .skip(line + 1)
+                    .take(CONTEXT_LINES)
+                    .map(|l| l.to_string())
+                    .collect();
+
+                FindMatch {
+                    index,
+                    line: line + 1,
+                    column,
+                    start,
+                    end,
+                    matched,
+                    replacement,
+                    line_text,
+                    context_before,
+                    context_after,
+                }
+            })
+            .collect()
+    }
+}
+
+/// 试运行：返回每个文件中的全部匹配以及替换后的文本，不修改任何文件
+pub fn find_in_project(project_path: &Path, options: &FindOptions) -> Result<Vec<FileMatches>> {
+    let matcher = Matcher::new(options)?;
+    let include = build_globs(&options.include)?;
+    let exclude = build_globs(&options.exclude)?;
+
+    let mut results = Vec::new();
+    for scope in &options.scopes {
+        for file in walk_files(&project_path.join(scope.dir())) {
+            let relative = relative_path(project_path, &file);
+            if !options.include.is_empty() && !include.is_match(&relative) {
+                continue;
+            }
+            if exclude.is_match(&relative) {
+                continue;
+            }
+            // 跳过图片等非文本文件
+            let Ok(content) = fs::read_to_string(&file) else {
+                continue;
+            };
+
+            let matches = matcher.find(&content);
+            if matches.is_empty() {
+                continue;
+            }
+            results.push(FileMatches {
+                path: relative,
+                fingerprint: fingerprint(&content),
+                matches,
+            });
+        }
+    }
+
+    Ok(results)
+}
+
+/// 应用选中的替换。所有文件先写入临时文件，全部成功后再逐个替换原文件，
+/// 原文件内容保存为撤销记录
+pub fn apply_replacements(
+    project_path: &Path,
+    options: &FindOptions,
+    selections: &[ReplaceSelection],
+) -> Result<ReplaceResult> {
+    let matcher = Matcher::new(options)?;
+
+    let mut planned = Vec::new();
+    let mut replacements = 0;
+    for selection in selections {
+        let file = resolve_project_file(project_path, &selection.path)?;
+        let content = fs::read_to_string(&file)?;
+        if fingerprint(&content) != selection.fingerprint {
+            return Err(AppError::FileOperationFailed(format!(
+                "File changed since the search was run: {}",
+                selection.path
+            )));
+        }
+
+        let matches: Vec<FindMatch> = matcher
+            .find(&content)
+            .into_iter()
+            .filter(|m| selection.indices.contains(&m.index))
+            .collect();
+        if matches.is_empty() {
+            continue;
+        }
+
+        let mut updated = String::with_capacity(content.len());
+        let mut pos = 0;
+        for m in &matches {
+            updated.push_str(&content[pos..m.start]);
+            updated.push_str(&m.replacement);
+            pos = m.end;
+        }
+        updated.push_str(&content[pos..]);
+        if updated == content {
+            continue;
+        }
+
+        replacements += matches.len();
+        planned.push((selection.path.clone(), file, content, updated));
+    }
+
+    if planned.is_empty() {
+        return Ok(ReplaceResult {
+            undo_id: None,
+            files_changed: 0,
+            replacements: 0,
+        });
+    }
+
+    let id = uuid::Uuid::new_v4().to_string();
+    ensure_project_data_dir(project_path)?;
+    let undo_dir = undo_root(project_path).join(&id);
+    fs::create_dir_all(&undo_dir)?;
+
+    let mut record = UndoRecord {
+        id: id.clone(),
+        created_at: Utc::now(),
+        description: format!("Replace \"{}\" with \"{}\"", options.query, options.replacement),
+        files: Vec::new(),
+    };
+    for (i, (relative, _, original, updated)) in planned.iter().enumerate() {
+        let backup = format!("{}.orig", i);
+        fs::write(undo_dir.join(&backup), original)?;
+        record.files.push(UndoFile {
+            path: relative.clone(),
+            backup,
+            fingerprint: fingerprint(updated),
+        });
+    }
+    fs::write(undo_dir.join(MANIFEST_FILE), serde_json::to_string_pretty(&record)?)?;
+
+    let writes: Vec<(PathBuf, String)> = planned
+        .into_iter()
+        .map(|(_, file, _, updated)| (file, updated))
+        .collect();
+    if let Err(e) = write_all_atomic(&writes) {
+        let _ = fs::remove_dir_all(&undo_dir);
+        return Err(e);
+    }
+
+    Ok(ReplaceResult {
+        undo_id: Some(id),
+        files_changed: writes.len(),
+        replacements,
+    })
+}
+
+/// 按撤销记录恢复原文件；替换之后又被修改过的文件会拒绝恢复
+pub fn undo_replacement(project_path: &Path, undo_id: &str) -> Result<UndoRecord> {
+    if undo_id.is_empty() || !undo_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(AppError::InvalidPath(undo_id.to_string()));
+    }
+    let undo_dir = undo_root(project_path).join(undo_id);
+    let manifest = undo_dir.join(MANIFEST_FILE);
+    if !manifest.exists() {
+        return Err(AppError::PathNotFound(undo_id.to_string()));
+    }
+    let record: UndoRecord = serde_json::from_str(&fs::read_to_string(&manifest)?)?;
+
+    let mut writes = Vec::new();
+    for file in &record.files {
+        let target = resolve_project_file(project_path, &file.path)?;
+        let current = fs::read_to_string(&target).unwrap_or_default();
+        if fingerprint(&current) != file.fingerprint {
+            return Err(AppError::FileOperationFailed(format!(
+                "File changed after the replacement: {}",
+                file.path
+            )));
+        }
+        writes.push((target, fs::read_to_string(undo_dir.join(&file.backup))?));
+    }
+
+    write_all_atomic(&writes)?;
+    fs::remove_dir_all(&undo_dir)?;
+    Ok(record)
+}
+
+/// 列出可以撤销的替换，最近的在前
+pub fn list_undo_records(project_path: &Path) -> Result<Vec<UndoRecord>> {
+    let Ok(entries) = fs::read_dir(undo_root(project_path)) else {
+        return Ok(Vec::new());
+    };
+
+    let mut records: Vec<UndoRecord> = entries
+        .flatten()
+        .filter_map(|entry| fs::read_to_string(entry.path().join(MANIFEST_FILE)).ok())
+        .filter_map(|content| serde_json::from_str(&content).ok())
+        .collect();
+    records.sort_by_key(|r| std::cmp::Reverse(r.created_at));
+    Ok(records)
+}
+
+fn relative_path(project_path: &Path, file: &Path) -> String {
+    file.strip_prefix(project_path)
+        .unwrap_or(file)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+pub(crate) fn build_globs(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern).map_err(|e| AppError::ParseError(e.to_string()))?;
+        builder.add(glob);
+    }
+    builder.build().map_err(|e| AppError::ParseError(e.to_string()))
+}
+
+fn undo_root(project_path: &Path) -> PathBuf {
+    project_data_dir(project_path).join(UNDO_DIR)
+}
+
+pub(crate) fn fingerprint(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("content")).unwrap();
+        fs::write(dir.path().join("content/post.md"), "Hello world, hello Hugo\n").unwrap();
+        dir
+    }
+
+    fn select_all(project_path: &Path, options: &FindOptions) -> Vec<ReplaceSelection> {
+        find_in_project(project_path, options)
+            .unwrap()
+            .into_iter()
+            .map(|file| ReplaceSelection {
+                path: file.path,
+                fingerprint: file.fingerprint,
+                indices: file.matches.iter().map(|m| m.index).collect(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn replace_and_undo() {
+        let dir = project();
+        let options = FindOptions {
+            query: "hello".to_string(),
+            replacement: "Hi".to_string(),
+            ..FindOptions::default()
+        };
+        let result = apply_replacements(dir.path(), &options, &select_all(dir.path(), &options)).unwrap();
+        assert_eq!((result.files_changed, result.replacements), (1, 2));
+        let post = dir.path().join("content/post.md");
+        assert_eq!(fs::read_to_string(&post).unwrap(), "Hi world, Hi Hugo\n");
+
+        undo_replacement(dir.path(), result.undo_id.as_deref().unwrap()).unwrap();
+        assert_eq!(fs::read_to_string(&post).unwrap(), "Hello world, hello Hugo\n");
+        assert!(list_undo_records(dir.path()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn no_undo_record_when_nothing_changes() {
+        let dir = project();
+        let options = FindOptions {
+            query: "world".to_string(),
+            replacement: "world".to_string(),
+            case_sensitive: true,
+            ..FindOptions::default()
+        };
+        let selections = select_all(dir.path(), &options);
+        assert_eq!(selections.len(), 1);
+        let result = apply_replacements(dir.path(), &options, &selections).unwrap();
+        assert!(result.undo_id.is_none());
+        assert_eq!(result.files_changed, 0);
+
+        let result = apply_replacements(dir.path(), &options, &[]).unwrap();
+        assert!(result.undo_id.is_none());
+        assert!(list_undo_records(dir.path()).unwrap().is_empty());
+        assert!(!undo_root(dir.path()).exists());
+    }
+}
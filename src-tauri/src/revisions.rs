@@ -0,0 +1,529 @@
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+use chrono::{DateTime, Duration, Utc};
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use crate::db::open_project_db;
+use crate::error::{AppError, Result};
+use crate::front_matter::split_front_matter;
+use crate::replace::fingerprint;
+use crate::search::is_cjk;
+use crate::settings::load_project_settings;
+use crate::utils::{resolve_project_file, write_all_atomic};
+
+/// 修订历史的保留策略，保存在项目设置中
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RevisionSettings {
+    /// 编辑器自动保存修订的间隔，为 0 时关闭自动保存
+    pub autosave_interval_secs: u64,
+    /// 每篇文章最多保留的修订数，为 0 时不限制
+    pub max_per_post: usize,
+    /// 超过这个天数的修订会被删除，为 0 时不限制；每篇文章的最新修订总是保留
+    pub max_age_days: i64,
+}
+
+impl Default for RevisionSettings {
+    fn default() -> Self {
+        Self {
+            autosave_interval_secs: 60,
+            max_per_post: 100,
+            max_age_days: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RevisionKind {
+    Save,
+    Autosave,
+    /// 保存时发现磁盘上的内容不是编辑器写入的（在别处修改过），先记录下来
+    External,
+    /// 恢复旧修订前的内容
+    Restore,
+}
+
+impl RevisionKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            RevisionKind::Save => "save",
+            RevisionKind::Autosave => "autosave",
+            RevisionKind::External => "external",
+            RevisionKind::Restore => "restore",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "autosave" => RevisionKind::Autosave,
+            "external" => RevisionKind::External,
+            "restore" => RevisionKind::Restore,
+            _ => RevisionKind::Save,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RevisionInfo {
+    pub id: i64,
+    /// 相对于站点根目录，使用 `/` 分隔
+    pub path: String,
+    pub created_at: DateTime<Utc>,
+    pub kind: RevisionKind,
+    pub word_count: usize,
+    /// 与上一个修订相比的字数变化
+    pub word_delta: i64,
+    pub size: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffLineKind {
+    Equal,
+    Insert,
+    Delete,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    /// 行号，从 1 开始；新增的行没有旧行号，删除的行没有新行号
+    pub old_line: Option<usize>,
+    pub new_line: Option<usize>,
+    pub text: String,
+}
+
+/// 一段改动及前后各 3 行上下文
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffHunk {
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RevisionDiff {
+    pub from: i64,
+    /// 为 None 时与磁盘上的当前内容比较
+    pub to: Option<i64>,
+    pub word_delta: i64,
+    pub hunks: Vec<DiffHunk>,
+}
+
+const DIFF_CONTEXT: usize = 3;
+/// 逐行比较的表格超过这个大小时不再求最长公共子序列，整段视为删除后新增
+const DIFF_MAX_CELLS: usize = 4_000_000;
+
+/// 保存文章并记录修订。磁盘上的内容与最新修订不同时（例如在别的编辑器中改过），
+/// 先把它记为 `external` 修订，这样第一次保存前的内容也能找回
+pub fn save_with_revision(project_path: &Path, file: &Path, content: &str) -> Result<()> {
+    let relative = relative_path(project_path, file)?;
+    let conn = revisions_db(project_path)?;
+    if let Ok(existing) = fs::read_to_string(file) {
+        let saved: Option<String> = conn
+            .query_row(
+                "SELECT hash FROM revisions WHERE path = ?1 AND kind <> 'autosave' ORDER BY id DESC LIMIT 1",
+                params![relative],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if saved != Some(fingerprint(&existing)) {
+            insert_revision(&conn, &relative, &existing, RevisionKind::External)?;
+        }
+    }
+    fs::write(file, content)?;
+    insert_revision(&conn, &relative, content, RevisionKind::Save)?;
+    prune(&conn, project_path, &relative)
+}
+
+/// 记录编辑器中尚未保存的内容，不写入文件。返回新修订的 id，与最新修订相同时不记录
+pub fn autosave(project_path: &Path, file: &Path, content: &str) -> Result<Option<i64>> {
+    let relative = relative_path(project_path, file)?;
+    let conn = revisions_db(project_path)?;
+    let inserted = insert_revision(&conn, &relative, content, RevisionKind::Autosave)?;
+    prune(&conn, project_path, &relative)?;
+    Ok(inserted)
+}
+
+/// 文章的修订列表，新的在前
+pub fn list_revisions(project_path: &Path, relative: &str) -> Result<Vec<RevisionInfo>> {
+    let conn = revisions_db(project_path)?;
+    let mut revisions = conn
+        .prepare(
+            "SELECT id, created_at, kind, word_count, size FROM revisions
+             WHERE path = ?1 ORDER BY id",
+        )?
+        .query_map(params![relative], |row| {
+            Ok(RevisionInfo {
+                id: row.get(0)?,
+                path: relative.to_string(),
+                created_at: DateTime::from_timestamp_millis(row.get(1)?).unwrap_or_default(),
+                kind: RevisionKind::parse(&row.get::<_, String>(2)?),
+                word_count: row.get::<_, i64>(3)? as usize,
+                word_delta: 0,
+                size: row.get::<_, i64>(4)? as usize,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut previous = None;
+    for revision in &mut revisions {
+        revision.word_delta = revision.word_count as i64 - previous.unwrap_or(0) as i64;
+        previous = Some(revision.word_count);
+    }
+    revisions.reverse();
+    Ok(revisions)
+}
+
+pub fn read_revision(project_path: &Path, id: i64) -> Result<String> {
+    let conn = revisions_db(project_path)?;
+    load_revision(&conn, id).map(|(_, content)| content)
+}
+
+/// 比较两个修订；`to` 为 None 时与磁盘上的当前文件比较
+pub fn diff_revisions(project_path: &Path, from: i64, to: Option<i64>) -> Result<RevisionDiff> {
+    let conn = revisions_db(project_path)?;
+    let (path, old) = load_revision(&conn, from)?;
+    let new = match to {
+        Some(to) => load_revision(&conn, to)?.1,
+        None => fs::read_to_string(resolve_project_file(project_path, &path)?).unwrap_or_default(),
+    };
+
+    Ok(RevisionDiff {
+        from,
+        to,
+        word_delta: count_words(&new) as i64 - count_words(&old) as i64,
+        hunks: diff_lines(&old, &new),
+    })
+}
+
+/// 把文件恢复为某个修订，恢复前的内容记为 `restore` 修订以便撤销。返回恢复后的内容
+pub fn restore_revision(project_path: &Path, id: i64) -> Result<String> {
+    let conn = revisions_db(project_path)?;
+    let (relative, content) = load_revision(&conn, id)?;
+    let file = resolve_project_file(project_path, &relative)?;
+    if let Ok(current) = fs::read_to_string(&file) {
+        insert_revision(&conn, &relative, &current, RevisionKind::Restore)?;
+    }
+    if let Some(parent) = file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    write_all_atomic(&[(file, content.clone())])?;
+    insert_revision(&conn, &relative, &content, RevisionKind::Save)?;
+    prune(&conn, project_path, &relative)?;
+    Ok(content)
+}
+
+fn revisions_db(project_path: &Path) -> Result<Connection> {
+    let conn = open_project_db(project_path)?;
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS revisions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            path TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            kind TEXT NOT NULL,
+            hash TEXT NOT NULL,
+            word_count INTEGER NOT NULL,
+            size INTEGER NOT NULL,
+            content BLOB NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS revisions_path ON revisions (path, id);",
+    )?;
+    Ok(conn)
+}
+
+fn relative_path(project_path: &Path, file: &Path) -> Result<String> {
+    file.strip_prefix(project_path)
+        .map(|relative| relative.to_string_lossy().replace('\\', "/"))
+        .map_err(|_| AppError::InvalidPath(file.display().to_string()))
+}
+
+/// 内容与该文章最新的修订相同时不插入，返回 None
+fn insert_revision(conn: &Connection, path: &str, content: &str, kind: RevisionKind) -> Result<Option<i64>> {
+    let hash = fingerprint(content);
+    let latest: Option<String> = conn
+        .query_row(
+            "SELECT hash FROM revisions WHERE path = ?1 ORDER BY id DESC LIMIT 1",
+            params![path],
+            |row| row.get(0),
+        )
+        .optional()?;
+    if latest.as_deref() == Some(hash.as_str()) {
+        return Ok(None);
+    }
+
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(content.as_bytes())?;
+    let compressed = encoder.finish()?;
+
+    conn.execute(
+        "INSERT INTO revisions (path, created_at, kind, hash, word_count, size, content)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            path,
+            Utc::now().timestamp_millis(),
+            kind.as_str(),
+            hash,
+            count_words(content) as i64,
+            content.len() as i64,
+            compressed,
+        ],
+    )?;
+    Ok(Some(conn.last_insert_rowid()))
+}
+
+fn load_revision(conn: &Connection, id: i64) -> Result<(String, String)> {
+    let (path, compressed): (String, Vec<u8>) = conn
+        .query_row(
+            "SELECT path, content FROM revisions WHERE id = ?1",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?
+        .ok_or_else(|| AppError::PathNotFound(format!("revision {}", id)))?;
+
+    let mut content = String::new();
+    ZlibDecoder::new(compressed.as_slice()).read_to_string(&mut content)?;
+    Ok((path, content))
+}
+
+/// 按项目设置删除多余和过期的修订，最新的一个总是保留
+fn prune(conn: &Connection, project_path: &Path, path: &str) -> Result<()> {
+    let settings = load_project_settings(project_path)?.revisions;
+    if settings.max_per_post > 0 {
+        conn.execute(
+            "DELETE FROM revisions WHERE path = ?1 AND id NOT IN (
+                SELECT id FROM revisions WHERE path = ?1 ORDER BY id DESC LIMIT ?2
+            )",
+            params![path, settings.max_per_post as i64],
+        )?;
+    }
+    if settings.max_age_days > 0 {
+        let oldest = (Utc::now() - Duration::days(settings.max_age_days)).timestamp_millis();
+        conn.execute(
+            "DELETE FROM revisions WHERE path = ?1 AND created_at < ?2
+             AND id <> (SELECT MAX(id) FROM revisions WHERE path = ?1)",
+            params![path, oldest],
+        )?;
+    }
+    Ok(())
+}
+
+/// 正文字数：中日韩文字每字计一，其余按连续的字母数字计词
+fn count_words(content: &str) -> usize {
+    let body = split_front_matter(content).body;
+    let mut count = 0;
+    let mut in_word = false;
+    for c in body.chars() {
+        if is_cjk(c) {
+            count += 1;
+            in_word = false;
+        } else if c.is_alphanumeric() {
+            if !in_word {
+                count += 1;
+            }
+            in_word = true;
+        } else if c == '\'' && in_word {
+            // don't、it's 算一个词
+        } else {
+            in_word = false;
+        }
+    }
+    count
+}
+
+/// 逐行比较：先去掉相同的开头和结尾，中间部分求最长公共子序列
+fn diff_lines(old: &str, new: &str) -> Vec<DiffHunk> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    let mut ops: Vec<DiffLineKind> = vec![DiffLineKind::Equal; prefix];
+    if old_mid.len() * new_mid.len() > DIFF_MAX_CELLS {
+        ops.extend(std::iter::repeat_n(DiffLineKind::Delete, old_mid.len()));
+        ops.extend(std::iter::repeat_n(DiffLineKind::Insert, new_mid.len()));
+    } else {
+        ops.extend(lcs_ops(old_mid, new_mid));
+    }
+    ops.extend(std::iter::repeat_n(DiffLineKind::Equal, suffix));
+
+    let mut lines = Vec::with_capacity(ops.len());
+    let (mut i, mut j) = (0, 0);
+    for kind in ops {
+        let line = match kind {
+            DiffLineKind::Equal => {
+                i += 1;
+                j += 1;
+                DiffLine {
+                    kind,
+                    old_line: Some(i),
+                    new_line: Some(j),
+                    text: old[i - 1].to_string(),
+                }
+            }
+            DiffLineKind::Delete => {
+                i += 1;
+                DiffLine {
+                    kind,
+                    old_line: Some(i),
+                    new_line: None,
+                    text: old[i - 1].to_string(),
+                }
+            }
+            DiffLineKind::Insert => {
+                j += 1;
+                DiffLine {
+                    kind,
+                    old_line: None,
+                    new_line: Some(j),
+                    text: new[j - 1].to_string(),
+                }
+            }
+        };
+        lines.push(line);
+    }
+    group_hunks(lines)
+}
+
+fn lcs_ops(old: &[&str], new: &[&str]) -> Vec<DiffLineKind> {
+    let (n, m) = (old.len(), new.len());
+    // lengths[i][j]：old[i..] 与 new[j..] 的最长公共子序列长度
+    let mut lengths = vec![0u32; (n + 1) * (m + 1)];
+    let at = |i: usize, j: usize| i * (m + 1) + j;
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lengths[at(i, j)] = if old[i] == new[j] {
+                lengths[at(i + 1, j + 1)] + 1
+            } else {
+                lengths[at(i + 1, j)].max(lengths[at(i, j + 1)])
+            };
+        }
+    }
+
+    let mut ops = Vec::with_capacity(n + m);
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && old[i] == new[j] {
+            ops.push(DiffLineKind::Equal);
+            i += 1;
+            j += 1;
+        } else if i < n && (j == m || lengths[at(i + 1, j)] >= lengths[at(i, j + 1)]) {
+            ops.push(DiffLineKind::Delete);
+            i += 1;
+        } else {
+            ops.push(DiffLineKind::Insert);
+            j += 1;
+        }
+    }
+    ops
+}
+
+/// 只保留改动行及其上下文，相距不远的改动合并为一段
+fn group_hunks(lines: Vec<DiffLine>) -> Vec<DiffHunk> {
+    let changed: Vec<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| line.kind != DiffLineKind::Equal)
+        .map(|(index, _)| index)
+        .collect();
+
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for index in changed {
+        let start = index.saturating_sub(DIFF_CONTEXT);
+        let end = (index + DIFF_CONTEXT + 1).min(lines.len());
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+
+    ranges
+        .into_iter()
+        .map(|(start, end)| DiffHunk {
+            lines: lines[start..end].to_vec(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::{save_project_settings, ProjectSettings};
+
+    fn kinds(project_path: &Path) -> Vec<RevisionKind> {
+        list_revisions(project_path, "content/post.md")
+            .unwrap()
+            .into_iter()
+            .map(|revision| revision.kind)
+            .collect()
+    }
+
+    #[test]
+    fn save_prune_and_restore() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path();
+        let mut settings = ProjectSettings::default();
+        settings.revisions.max_per_post = 4;
+        save_project_settings(project, &settings).unwrap();
+        let file = project.join("content/post.md");
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(&file, "written elsewhere").unwrap();
+
+        // 第一次保存前磁盘上的内容记为 external
+        save_with_revision(project, &file, "one").unwrap();
+        assert_eq!(kinds(project), [RevisionKind::Save, RevisionKind::External]);
+        assert_eq!(fs::read_to_string(&file).unwrap(), "one");
+
+        let autosaved = autosave(project, &file, "one two").unwrap();
+        assert!(autosaved.is_some());
+        assert_eq!(autosave(project, &file, "one two").unwrap(), None);
+        assert_eq!(fs::read_to_string(&file).unwrap(), "one");
+
+        // 超过 max_per_post 时删除最早的修订
+        save_with_revision(project, &file, "one two three").unwrap();
+        save_with_revision(project, &file, "one two three four").unwrap();
+        let revisions = list_revisions(project, "content/post.md").unwrap();
+        assert_eq!(revisions.len(), 4);
+        assert_eq!(revisions[0].word_count, 4);
+        assert!(revisions.iter().all(|revision| revision.kind != RevisionKind::External));
+
+        let restored = restore_revision(project, autosaved.unwrap()).unwrap();
+        assert_eq!(restored, "one two");
+        assert_eq!(fs::read_to_string(&file).unwrap(), "one two");
+        // 恢复前的内容与最新修订相同，不再重复记录
+        let revisions = list_revisions(project, "content/post.md").unwrap();
+        assert_eq!(revisions.len(), 4);
+        assert_eq!(read_revision(project, revisions[1].id).unwrap(), "one two three four");
+        assert_eq!(diff_revisions(project, revisions[1].id, None).unwrap().word_delta, -2);
+
+        // 磁盘上的内容在别处改过时，恢复前先记为 restore
+        fs::write(&file, "edited elsewhere").unwrap();
+        restore_revision(project, revisions[1].id).unwrap();
+        assert_eq!(kinds(project)[..2], [RevisionKind::Save, RevisionKind::Restore]);
+        assert_eq!(fs::read_to_string(&file).unwrap(), "one two three four");
+    }
+
+    #[test]
+    fn rejects_files_outside_the_project() {
+        let dir = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        let file = outside.path().join("post.md");
+        assert!(matches!(
+            save_with_revision(dir.path(), &file, "x"),
+            Err(AppError::InvalidPath(_))
+        ));
+        assert!(!file.exists());
+    }
+}
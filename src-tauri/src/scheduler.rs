@@ -0,0 +1,659 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Manager};
+use crate::db::open_project_db;
+use crate::deploy::gate::CheckLevel;
+use crate::deploy::{self, DeployResult, Progress};
+use crate::error::Result;
+use crate::front_matter::edit::{set_field, FieldValue};
+use crate::front_matter::{get_field, parse_front_matter, split_front_matter};
+use crate::hooks::{self, Hook};
+use crate::models::{BuildOptions, EngineType};
+use crate::preview::PreviewServers;
+use crate::settings::{load_project_settings, ProjectSettings};
+use crate::utils::{execute_command, resolve_project_file, walk_files, write_all_atomic};
+
+/// 后台检查定时发布的间隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 发布完成（或发现错过的定时发布）时发给前端的事件
+pub const PUBLISH_EVENT: &str = "scheduled-publish";
+pub const PUBLISH_ERROR_EVENT: &str = "scheduled-publish-error";
+
+/// 应用关闭期间错过的定时发布如何处理
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MissedPolicy {
+    /// 只报告，由用户决定是否发布
+    #[default]
+    Report,
+    /// 启动时立即发布
+    Publish,
+}
+
+/// 定时发布设置，保存在项目设置中
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PublishSettings {
+    pub enabled: bool,
+    /// 发布后使用的构建配置，为空时不构建；设置了部署目标时使用目标的构建配置
+    pub build_profile: Option<String>,
+    /// 发布后部署到的部署目标名称，经过部署前检查、钩子并记入部署历史
+    pub deploy_target: Option<String>,
+    /// 没有设置部署目标时，构建成功后在站点根目录执行的部署命令，为空时不部署
+    pub deploy_command: Option<String>,
+    pub missed: MissedPolicy,
+}
+
+/// 等待发布的草稿
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduledPost {
+    /// 相对于站点根目录，使用 `/` 分隔
+    pub path: String,
+    pub publish_at: DateTime<Utc>,
+    /// 发布时间已过但应用当时没有运行
+    pub missed: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StepOutcome {
+    pub success: bool,
+    pub output: String,
+}
+
+/// 一次定时发布的记录，保存在 `publish_log` 表
+#[derive(Debug, Clone, Serialize)]
+pub struct PublishRecord {
+    pub id: i64,
+    pub path: String,
+    pub scheduled_at: DateTime<Utc>,
+    pub published_at: DateTime<Utc>,
+    pub missed: bool,
+    /// 没有配置构建或部署时为 None
+    pub build_success: Option<bool>,
+    pub deploy_success: Option<bool>,
+    pub message: Option<String>,
+}
+
+/// 一次检查的结果
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ScheduleCheck {
+    pub project_path: PathBuf,
+    pub published: Vec<PublishRecord>,
+    pub missed: Vec<ScheduledPost>,
+    pub build: Option<StepOutcome>,
+    pub deploy: Option<StepOutcome>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct WatchedProject {
+    path: PathBuf,
+    engine: EngineType,
+}
+
+/// 后台定时发布器：登记的项目保存在应用数据目录中，重启后继续检查
+pub struct PublishScheduler {
+    registry: PathBuf,
+    projects: Mutex<BTreeMap<PathBuf, EngineType>>,
+    /// 本次运行中已经做过启动检查的项目
+    started: Mutex<HashSet<PathBuf>>,
+    /// 同一时间只运行一次检查，避免后台任务和命令重复发布同一篇文章
+    running: tokio::sync::Mutex<()>,
+}
+
+impl PublishScheduler {
+    pub fn load(registry: PathBuf) -> Self {
+        let projects = fs::read_to_string(&registry)
+            .ok()
+            .and_then(|content| serde_json::from_str::<Vec<WatchedProject>>(&content).ok())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|project| (project.path, project.engine))
+            .collect();
+
+        Self {
+            registry,
+            projects: Mutex::new(projects),
+            started: Mutex::new(HashSet::new()),
+            running: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    pub fn watch(&self, project_path: PathBuf, engine: EngineType) -> Result<()> {
+        let mut projects = self.projects.lock().unwrap();
+        projects.insert(project_path, engine);
+        self.save(&projects)
+    }
+
+    pub fn unwatch(&self, project_path: &Path) -> Result<bool> {
+        let mut projects = self.projects.lock().unwrap();
+        let removed = projects.remove(project_path).is_some();
+        self.save(&projects)?;
+        Ok(removed)
+    }
+
+    pub fn projects(&self) -> Vec<(PathBuf, EngineType)> {
+        let projects = self.projects.lock().unwrap();
+        projects.iter().map(|(path, engine)| (path.clone(), engine.clone())).collect()
+    }
+
+    /// 每个项目在本次运行中第一次成功的检查视为启动检查，已过期的定时发布按项目的 `missed` 策略处理
+    pub async fn check(&self, project_path: &Path, engine: &EngineType) -> Result<ScheduleCheck> {
+        let _running = self.running.lock().await;
+        let startup = !self.started.lock().unwrap().contains(project_path);
+        let check = check_project(project_path, engine, startup).await?;
+        self.started.lock().unwrap().insert(project_path.to_path_buf());
+        Ok(check)
+    }
+
+    /// 发布所有错过的定时发布
+    pub async fn publish_missed(&self, project_path: &Path, engine: &EngineType) -> Result<ScheduleCheck> {
+        let _running = self.running.lock().await;
+        let settings = load_project_settings(project_path)?;
+        let missed: Vec<ScheduledPost> = list_scheduled(project_path, engine)?
+            .into_iter()
+            .filter(|post| post.missed)
+            .collect();
+        publish(project_path, engine, &settings, missed).await
+    }
+
+    fn save(&self, projects: &BTreeMap<PathBuf, EngineType>) -> Result<()> {
+        let list: Vec<WatchedProject> = projects
+            .iter()
+            .map(|(path, engine)| WatchedProject {
+                path: path.clone(),
+                engine: engine.clone(),
+            })
+            .collect();
+        if let Some(dir) = self.registry.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.registry, serde_json::to_string_pretty(&list)?)?;
+        Ok(())
+    }
+}
+
+/// 启动后台任务，定期检查所有登记的项目
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let scheduler = app.state::<PublishScheduler>();
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+
+        loop {
+            interval.tick().await;
+            for (path, engine) in scheduler.projects() {
+                match scheduler.check(&path, &engine).await {
+                    Ok(check) => notify(&app, &check),
+                    Err(e) => {
+                        let _ = app.emit(PUBLISH_ERROR_EVENT, format!("{}: {}", path.display(), e));
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// 有文章发布或发现错过的发布时通知前端；构建成功后刷新预览
+pub fn notify(app: &AppHandle, check: &ScheduleCheck) {
+    if check.published.is_empty() && check.missed.is_empty() {
+        return;
+    }
+    if check.build.as_ref().is_some_and(|build| build.success) {
+        app.state::<PreviewServers>().notify_reload(&check.project_path);
+    }
+    let _ = app.emit(PUBLISH_EVENT, check.clone());
+}
+
+/// 同步定时发布列表，并发布已到时间的草稿
+pub async fn check_project(project_path: &Path, engine: &EngineType, startup: bool) -> Result<ScheduleCheck> {
+    let settings = load_project_settings(project_path)?;
+    let mut check = ScheduleCheck {
+        project_path: project_path.to_path_buf(),
+        ..Default::default()
+    };
+    if !settings.publishing.enabled {
+        return Ok(check);
+    }
+
+    let due = {
+        let drafts = scheduled_drafts(project_path, engine);
+        let conn = schedule_db(project_path)?;
+        let due = sync_schedule(&conn, &drafts, Utc::now())?;
+        if startup && settings.publishing.missed == MissedPolicy::Report {
+            for post in &due {
+                conn.execute(
+                    "UPDATE publish_schedule SET status = 'missed' WHERE path = ?1",
+                    params![post.path],
+                )?;
+            }
+            check.missed = due
+                .into_iter()
+                .map(|post| ScheduledPost { missed: true, ..post })
+                .collect();
+            return Ok(check);
+        }
+        due
+    };
+
+    let due = due
+        .into_iter()
+        .map(|post| ScheduledPost { missed: startup, ..post })
+        .collect();
+    publish(project_path, engine, &settings, due).await
+}
+
+/// 当前的定时发布列表（包括错过的）
+pub fn list_scheduled(project_path: &Path, engine: &EngineType) -> Result<Vec<ScheduledPost>> {
+    let drafts = scheduled_drafts(project_path, engine);
+    let conn = schedule_db(project_path)?;
+    sync_schedule(&conn, &drafts, Utc::now())?;
+    list_schedule(&conn)
+}
+
+/// 最近的发布记录，新的在前
+pub fn list_history(project_path: &Path, limit: usize) -> Result<Vec<PublishRecord>> {
+    let conn = schedule_db(project_path)?;
+    let mut stmt = conn.prepare(
+        "SELECT id, path, scheduled_at, published_at, missed, build_success, deploy_success, message
+         FROM publish_log ORDER BY id DESC LIMIT ?1",
+    )?;
+    let records = stmt
+        .query_map(params![limit as i64], |row| {
+            Ok(PublishRecord {
+                id: row.get(0)?,
+                path: row.get(1)?,
+                scheduled_at: timestamp(row.get(2)?),
+                published_at: timestamp(row.get(3)?),
+                missed: row.get(4)?,
+                build_success: row.get(5)?,
+                deploy_success: row.get(6)?,
+                message: row.get(7)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(records)
+}
+
+/// 把草稿改为已发布，然后按设置构建和部署，每篇文章记录一条发布记录
+async fn publish(
+    project_path: &Path,
+    engine: &EngineType,
+    settings: &ProjectSettings,
+    posts: Vec<ScheduledPost>,
+) -> Result<ScheduleCheck> {
+    let mut check = ScheduleCheck {
+        project_path: project_path.to_path_buf(),
+        ..Default::default()
+    };
+    if posts.is_empty() {
+        return Ok(check);
+    }
+
+    let mut writes = Vec::new();
+    for post in &posts {
+        let file = resolve_project_file(project_path, &post.path)?;
+        let content = fs::read_to_string(&file)?;
+        writes.push((file, set_field(&content, &["draft"], &FieldValue::Bool(false))?));
+    }
+    write_all_atomic(&writes)?;
+    {
+        let conn = schedule_db(project_path)?;
+        for post in &posts {
+            conn.execute("DELETE FROM publish_schedule WHERE path = ?1", params![post.path])?;
+        }
+    }
+
+    let publishing = &settings.publishing;
+    if let Some(target) = publishing.deploy_target.as_deref().filter(|t| !t.trim().is_empty()) {
+        // 部署会按目标的构建配置重新构建，不再单独构建
+        check.deploy = Some(deploy_to_target(project_path, engine, settings, target).await);
+    } else {
+        if let Some(profile) = &publishing.build_profile {
+            check.build = Some(match settings.profile(profile, engine) {
+                Ok(profile) => build(project_path, engine, &profile.options, &settings.hooks).await,
+                Err(e) => StepOutcome {
+                    success: false,
+                    output: e.to_string(),
+                },
+            });
+        }
+        let built = check.build.as_ref().is_none_or(|build| build.success);
+        if let Some(command) = publishing.deploy_command.as_deref().filter(|c| !c.trim().is_empty()) {
+            if built {
+                check.deploy = Some(run_shell(command, project_path).await);
+            }
+        }
+    }
+
+    let message = [("Build", &check.build), ("Deploy", &check.deploy)]
+        .into_iter()
+        .find_map(|(step, outcome)| match outcome {
+            Some(outcome) if !outcome.success => Some(format!("{} failed: {}", step, outcome.output.trim())),
+            _ => None,
+        });
+    let published_at = Utc::now();
+    let conn = schedule_db(project_path)?;
+    for post in posts {
+        conn.execute(
+            "INSERT INTO publish_log (path, scheduled_at, published_at, missed, build_success, deploy_success, message)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                post.path,
+                post.publish_at.timestamp(),
+                published_at.timestamp(),
+                post.missed,
+                check.build.as_ref().map(|b| b.success),
+                check.deploy.as_ref().map(|d| d.success),
+                message,
+            ],
+        )?;
+        check.published.push(PublishRecord {
+            id: conn.last_insert_rowid(),
+            path: post.path,
+            scheduled_at: post.publish_at,
+            published_at,
+            missed: post.missed,
+            build_success: check.build.as_ref().map(|b| b.success),
+            deploy_success: check.deploy.as_ref().map(|d| d.success),
+            message: message.clone(),
+        });
+    }
+
+    Ok(check)
+}
+
+async fn build(project_path: &Path, engine: &EngineType, options: &BuildOptions, hooks: &[Hook]) -> StepOutcome {
+    match hooks::build(project_path, engine, options, hooks, &|_| {}).await {
+        Ok(result) => StepOutcome {
+            success: result.success,
+            output: if result.errors.is_empty() {
+                result.output
+            } else {
+                format!("{}\n{}", result.output, result.errors.join("\n"))
+            },
+        },
+        Err(e) => StepOutcome {
+            success: false,
+            output: e.to_string(),
+        },
+    }
+}
+
+async fn deploy_to_target(project_path: &Path, engine: &EngineType, settings: &ProjectSettings, target: &str) -> StepOutcome {
+    let config = match settings.deploy_target(target) {
+        Ok(config) => config,
+        Err(e) => {
+            return StepOutcome {
+                success: false,
+                output: e.to_string(),
+            }
+        }
+    };
+    let progress = Progress::new(&config.name, &|_| {});
+    match deploy::deploy(project_path, engine, config, &progress, &|_| {}).await {
+        Ok(DeployResult::Deployed(outcome)) => StepOutcome {
+            success: true,
+            output: outcome.url.unwrap_or_default(),
+        },
+        Ok(DeployResult::Blocked(report)) => StepOutcome {
+            success: false,
+            output: report
+                .checks
+                .iter()
+                .filter(|check| !check.passed && check.level == CheckLevel::Blocking)
+                .flat_map(|check| &check.issues)
+                .map(|issue| match (&issue.path, issue.line) {
+                    (Some(path), Some(line)) => format!("{}:{}: {}", path, line, issue.message),
+                    (Some(path), None) => format!("{}: {}", path, issue.message),
+                    _ => issue.message.clone(),
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        },
+        Err(e) => StepOutcome {
+            success: false,
+            output: e.to_string(),
+        },
+    }
+}
+
+async fn run_shell(command: &str, working_dir: &Path) -> StepOutcome {
+    let output = if cfg!(windows) {
+        execute_command(Path::new("cmd"), &["/C", command], working_dir).await
+    } else {
+        execute_command(Path::new("sh"), &["-c", command], working_dir).await
+    };
+    match output {
+        Ok(output) => StepOutcome {
+            success: output.status.success(),
+            output: format!(
+                "{}{}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            ),
+        },
+        Err(e) => StepOutcome {
+            success: false,
+            output: e.to_string(),
+        },
+    }
+}
+
+/// 内容目录中 `draft: true` 且有发布时间的文章。Hugo 优先使用 `publishDate`，其次 `date`
+fn scheduled_drafts(project_path: &Path, engine: &EngineType) -> BTreeMap<String, DateTime<Utc>> {
+    let date_fields: &[&str] = match engine {
+        EngineType::Hugo => &["publishDate", "date"],
+        EngineType::Zola => &["date"],
+    };
+
+    let mut drafts = BTreeMap::new();
+    for file in walk_files(&project_path.join("content")) {
+        if !matches!(file.extension().and_then(|e| e.to_str()), Some("md") | Some("markdown")) {
+            continue;
+        }
+        let Ok(content) = fs::read_to_string(&file) else {
+            continue;
+        };
+        let Some(front_matter) = split_front_matter(&content)
+            .front_matter
+            .and_then(|block| parse_front_matter(&block).ok())
+        else {
+            continue;
+        };
+
+        let draft = match get_field(&front_matter, "draft") {
+            Some(Value::Bool(draft)) => *draft,
+            Some(Value::String(draft)) => draft.eq_ignore_ascii_case("true"),
+            _ => false,
+        };
+        let publish_at = date_fields
+            .iter()
+            .find_map(|field| get_field(&front_matter, field).and_then(Value::as_str).and_then(parse_date));
+        if let (true, Some(publish_at)) = (draft, publish_at) {
+            let relative = file
+                .strip_prefix(project_path)
+                .unwrap_or(&file)
+                .to_string_lossy()
+                .replace('\\', "/");
+            drafts.insert(relative, publish_at);
+        }
+    }
+    drafts
+}
+
+/// 没有时区的时间按本机时区解释，只有日期时取当天零点
+pub(crate) fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Some(date.with_timezone(&Utc));
+    }
+    if let Ok(date) = DateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%:z") {
+        return Some(date.with_timezone(&Utc));
+    }
+
+    let naive = ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?.and_hms_opt(0, 0, 0))?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|date| date.with_timezone(&Utc))
+}
+
+fn schedule_db(project_path: &Path) -> Result<Connection> {
+    let conn = open_project_db(project_path)?;
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS publish_schedule (
+            path TEXT PRIMARY KEY,
+            publish_at INTEGER NOT NULL,
+            status TEXT NOT NULL DEFAULT 'pending'
+        );
+        CREATE TABLE IF NOT EXISTS publish_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            path TEXT NOT NULL,
+            scheduled_at INTEGER NOT NULL,
+            published_at INTEGER NOT NULL,
+            missed INTEGER NOT NULL,
+            build_success INTEGER,
+            deploy_success INTEGER,
+            message TEXT
+        );",
+    )?;
+    Ok(conn)
+}
+
+/// 与内容目录同步：只有在发布时间之前就被看到的草稿才会登记为定时发布，
+/// 这样日期早已过去的普通草稿不会被自动发布。修改了时间或已不是草稿的条目会被移除。
+/// 返回已到发布时间、尚未处理的条目
+fn sync_schedule(
+    conn: &Connection,
+    drafts: &BTreeMap<String, DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> Result<Vec<ScheduledPost>> {
+    let existing: Vec<(String, i64, String)> = conn
+        .prepare("SELECT path, publish_at, status FROM publish_schedule")?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut due = Vec::new();
+    for (path, publish_at, status) in &existing {
+        if drafts.get(path).map(DateTime::timestamp) != Some(*publish_at) {
+            conn.execute("DELETE FROM publish_schedule WHERE path = ?1", params![path])?;
+        } else if status == "pending" && *publish_at <= now.timestamp() {
+            due.push(ScheduledPost {
+                path: path.clone(),
+                publish_at: timestamp(*publish_at),
+                missed: false,
+            });
+        }
+    }
+
+    for (path, publish_at) in drafts {
+        if *publish_at > now {
+            conn.execute(
+                "INSERT OR IGNORE INTO publish_schedule (path, publish_at) VALUES (?1, ?2)",
+                params![path, publish_at.timestamp()],
+            )?;
+        }
+    }
+
+    Ok(due)
+}
+
+fn list_schedule(conn: &Connection) -> Result<Vec<ScheduledPost>> {
+    let posts = conn
+        .prepare("SELECT path, publish_at, status FROM publish_schedule ORDER BY publish_at")?
+        .query_map([], |row| {
+            Ok(ScheduledPost {
+                path: row.get(0)?,
+                publish_at: timestamp(row.get(1)?),
+                missed: row.get::<_, String>(2)? == "missed",
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(posts)
+}
+
+fn timestamp(seconds: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp(seconds, 0).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::save_project_settings;
+
+    fn schedule(project_path: &Path, relative: &str) {
+        let file = project_path.join(relative);
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(&file, "---\ntitle: Post\ndate: 2000-01-01T00:00:00Z\ndraft: true\n---\n").unwrap();
+        // 登记时还未到发布时间，之后应用没有运行
+        schedule_db(project_path)
+            .unwrap()
+            .execute(
+                "INSERT INTO publish_schedule (path, publish_at) VALUES (?1, ?2)",
+                params![relative, parse_date("2000-01-01T00:00:00Z").unwrap().timestamp()],
+            )
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn startup_check_runs_once_per_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("site");
+        let mut settings = ProjectSettings::default();
+        settings.publishing.enabled = true;
+        save_project_settings(&project, &settings).unwrap();
+        let scheduler = PublishScheduler::load(dir.path().join("scheduler.json"));
+
+        schedule(&project, "content/a.md");
+        let check = scheduler.check(&project, &EngineType::Hugo).await.unwrap();
+        assert_eq!(check.missed.len(), 1);
+        assert!(check.published.is_empty());
+
+        // 再次登记同一项目不会再当作启动检查：新到期的文章直接发布
+        scheduler.watch(project.clone(), EngineType::Hugo).unwrap();
+        schedule(&project, "content/b.md");
+        let check = scheduler.check(&project, &EngineType::Hugo).await.unwrap();
+        assert!(check.missed.is_empty());
+        assert_eq!(check.published.len(), 1);
+        assert_eq!(check.published[0].path, "content/b.md");
+        assert!(!check.published[0].missed);
+
+        // 新的会话重新做启动检查
+        let scheduler = PublishScheduler::load(dir.path().join("scheduler.json"));
+        schedule(&project, "content/c.md");
+        let check = scheduler.check(&project, &EngineType::Hugo).await.unwrap();
+        assert_eq!(check.missed.len(), 1);
+        assert_eq!(check.missed[0].path, "content/c.md");
+    }
+
+    #[tokio::test]
+    async fn deploy_target_takes_precedence_over_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path();
+        let mut settings = ProjectSettings::default();
+        settings.publishing.enabled = true;
+        settings.publishing.missed = MissedPolicy::Publish;
+        settings.publishing.deploy_target = Some("live".to_string());
+        settings.publishing.deploy_command = Some("echo deployed > deployed.txt".to_string());
+        save_project_settings(project, &settings).unwrap();
+        let scheduler = PublishScheduler::load(dir.path().join("scheduler.json"));
+
+        schedule(project, "content/a.md");
+        let check = scheduler.check(project, &EngineType::Hugo).await.unwrap();
+        assert_eq!(check.published.len(), 1);
+        // 目标不存在时部署失败，不会退回到部署命令
+        assert_eq!(check.published[0].deploy_success, Some(false));
+        assert!(check.published[0].message.as_deref().unwrap().contains("Deploy target live"));
+        assert!(!project.join("deployed.txt").exists());
+    }
+}
@@ -0,0 +1,456 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use serde_json::Value;
+use crate::error::Result;
+use crate::front_matter::{get_field, parse_front_matter, split_front_matter};
+use crate::markdown::shortcodes::escape_html;
+use crate::utils::walk_files;
+
+const MAX_LINE_MATCHES: usize = 20;
+const SNIPPET_CONTEXT: usize = 40;
+/// 标签列中各标签之间的分隔符，unicode61 分词器把它当作空白，读取时可以按它还原含空格的标签
+const TAG_SEPARATOR: &str = "\n";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchLineMatch {
+    /// 行号，从 1 开始
+    pub line: usize,
+    /// 匹配行附近的文字，命中的词用 `<mark>` 标出
+    pub snippet: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    pub path: String,
+    pub title: String,
+    pub tags: Vec<String>,
+    /// bm25 得分，越小越相关
+    pub score: f64,
+    pub matches: Vec<SearchLineMatch>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct IndexStats {
+    pub indexed: usize,
+    pub removed: usize,
+    pub total: usize,
+}
+
+pub fn init_schema(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS search_files (
+            path TEXT PRIMARY KEY,
+            mtime INTEGER NOT NULL,
+            size INTEGER NOT NULL
+        );
+        CREATE VIRTUAL TABLE IF NOT EXISTS search_index USING fts5(
+            path UNINDEXED,
+            title,
+            tags,
+            front_matter,
+            body,
+            tokenize = 'unicode61 remove_diacritics 2'
+        );",
+    )?;
+    Ok(())
+}
+
+/// 增量更新索引：只重新索引修改时间或大小变化的文件，并移除已删除的文件
+pub fn refresh_index(conn: &mut Connection, project_path: &Path) -> Result<IndexStats> {
+    init_schema(conn)?;
+    let mut stats = IndexStats::default();
+
+    let files: Vec<_> = walk_files(&project_path.join("content"))
+        .into_iter()
+        .filter(|p| is_markdown(p))
+        .collect();
+    let current: HashSet<String> = files.iter().map(|p| p.to_string_lossy().to_string()).collect();
+
+    let tx = conn.transaction()?;
+
+    let indexed: HashMap<String, (i64, i64)> = {
+        let mut stmt = tx.prepare("SELECT path, mtime, size FROM search_files")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?))))?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+
+    for path in indexed.keys().filter(|path| !current.contains(*path)) {
+        remove_file(&tx, path)?;
+        stats.removed += 1;
+    }
+
+    for file in &files {
+        let path = file.to_string_lossy().to_string();
+        let Ok(metadata) = fs::metadata(file) else {
+            continue;
+        };
+        let stamp = file_stamp(&metadata);
+        if indexed.get(&path) == Some(&stamp) {
+            continue;
+        }
+        if index_file(&tx, file, stamp)? {
+            stats.indexed += 1;
+        }
+    }
+
+    tx.commit()?;
+    stats.total = current.len();
+    Ok(stats)
+}
+
+/// 文件保存、新建或删除后更新它在索引中的条目；内容目录之外的文件和非 Markdown 文件会被忽略
+pub fn update_file(conn: &Connection, project_path: &Path, file: &Path) -> Result<()> {
+    if !file.starts_with(project_path.join("content")) || !is_markdown(file) {
+        return Ok(());
+    }
+    init_schema(conn)?;
+    match fs::metadata(file) {
+        Ok(metadata) => {
+            index_file(conn, file, file_stamp(&metadata))?;
+        }
+        Err(_) => remove_file(conn, &file.to_string_lossy())?,
+    }
+    Ok(())
+}
+
+/// 修改时间（毫秒）和大小
+fn file_stamp(metadata: &fs::Metadata) -> (i64, i64) {
+    let mtime = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default();
+    (mtime, metadata.len() as i64)
+}
+
+/// 读取失败时跳过，返回 false
+fn index_file(conn: &Connection, file: &Path, (mtime, size): (i64, i64)) -> Result<bool> {
+    let Ok(content) = fs::read_to_string(file) else {
+        return Ok(false);
+    };
+    let path = file.to_string_lossy();
+    index_document(conn, &path, &content)?;
+    conn.execute(
+        "INSERT OR REPLACE INTO search_files (path, mtime, size) VALUES (?1, ?2, ?3)",
+        params![path, mtime, size],
+    )?;
+    Ok(true)
+}
+
+fn remove_file(conn: &Connection, path: &str) -> Result<()> {
+    conn.execute("DELETE FROM search_index WHERE path = ?1", params![path])?;
+    conn.execute("DELETE FROM search_files WHERE path = ?1", params![path])?;
+    Ok(())
+}
+
+fn index_document(conn: &Connection, path: &str, content: &str) -> Result<()> {
+    let document = split_front_matter(content);
+    let front_matter = document
+        .front_matter
+        .and_then(|block| parse_front_matter(&block).ok())
+        .unwrap_or(Value::Null);
+
+    let title = get_field(&front_matter, "title")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .unwrap_or_else(|| title_from_path(path));
+    let tags = front_matter_terms(&front_matter).join(TAG_SEPARATOR);
+    let mut values = Vec::new();
+    flatten_values(&front_matter, &mut values);
+
+    conn.execute("DELETE FROM search_index WHERE path = ?1", params![path])?;
+    conn.execute(
+        "INSERT INTO search_index (path, title, tags, front_matter, body) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            path,
+            segment_cjk(&title),
+            segment_cjk(&tags),
+            segment_cjk(&values.join(" ")),
+            segment_cjk(document.body),
+        ],
+    )?;
+    Ok(())
+}
+
+/// 按相关度返回匹配的文章，以及每篇文章中命中的行
+pub fn search(conn: &Connection, query: &str, limit: usize) -> Result<Vec<SearchHit>> {
+    let terms = query_terms(query);
+    if terms.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let fts_query = terms
+        .iter()
+        .map(|term| {
+            let phrase = format!("\"{}\"", segment_cjk(term).trim().replace('"', "\"\""));
+            // 非中日韩文字的词按前缀匹配
+            if term.chars().any(is_cjk) {
+                phrase
+            } else {
+                format!("{}*", phrase)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let mut stmt = conn.prepare(
+        "SELECT path, title, tags, bm25(search_index, 0.0, 10.0, 5.0, 2.0, 1.0) AS score
+         FROM search_index
+         WHERE search_index MATCH ?1
+         ORDER BY score
+         LIMIT ?2",
+    )?;
+    let rows = stmt.query_map(params![fts_query, limit as i64], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, f64>(3)?,
+        ))
+    })?;
+
+    let mut hits = Vec::new();
+    for row in rows {
+        let (path, title, tags, score) = row?;
+        let content = fs::read_to_string(&path).unwrap_or_default();
+        hits.push(SearchHit {
+            matches: line_matches(&content, &terms),
+            title: desegment_cjk(&title),
+            tags: tags
+                .split(TAG_SEPARATOR)
+                .map(desegment_cjk)
+                .filter(|tag| !tag.is_empty())
+                .collect(),
+            path,
+            score,
+        });
+    }
+
+    Ok(hits)
+}
+
+fn query_terms(query: &str) -> Vec<String> {
+    query
+        .split_whitespace()
+        .map(|t| t.trim_matches('"').to_lowercase())
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+fn line_matches(content: &str, terms: &[String]) -> Vec<SearchLineMatch> {
+    content
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let lower = line.to_lowercase();
+            let (start, term) = terms
+                .iter()
+                .filter_map(|term| lower.find(term.as_str()).map(|pos| (pos, term)))
+                .min_by_key(|(pos, _)| *pos)?;
+            // 小写化可能改变字节长度，这种情况下直接返回整行
+            if lower.len() != line.len() {
+                return Some(SearchLineMatch {
+                    line: i + 1,
+                    snippet: escape_html(line.trim()),
+                });
+            }
+            Some(SearchLineMatch {
+                line: i + 1,
+                snippet: highlight(line, start, start + term.len()),
+            })
+        })
+        .take(MAX_LINE_MATCHES)
+        .collect()
+}
+
+/// 截取命中位置附近的文字并转义，再用 `<mark>` 标出命中的词
+fn highlight(line: &str, start: usize, end: usize) -> String {
+    let mut from = start.saturating_sub(SNIPPET_CONTEXT);
+    while !line.is_char_boundary(from) {
+        from -= 1;
+    }
+    let mut to = (end + SNIPPET_CONTEXT).min(line.len());
+    while !line.is_char_boundary(to) {
+        to += 1;
+    }
+
+    format!(
+        "{}{}<mark>{}</mark>{}{}",
+        if from > 0 { "…" } else { "" },
+        escape_html(&line[from..start]),
+        escape_html(&line[start..end]),
+        escape_html(&line[end..to]),
+        if to < line.len() { "…" } else { "" },
+    )
+}
+
+/// 标签、分类和关键词
+fn front_matter_terms(front_matter: &Value) -> Vec<String> {
+    let mut terms = Vec::new();
+    for key in ["tags", "categories", "keywords", "series"] {
+        match get_field(front_matter, key) {
+            Some(Value::Array(items)) => {
+                terms.extend(items.iter().filter_map(Value::as_str).map(str::to_string))
+            }
+            Some(Value::String(s)) => terms.push(s.clone()),
+            _ => {}
+        }
+    }
+    // Zola 把分类法放在 [taxonomies] 下
+    if let Some(Value::Object(taxonomies)) = get_field(front_matter, "taxonomies") {
+        for items in taxonomies.values().filter_map(Value::as_array) {
+            terms.extend(items.iter().filter_map(Value::as_str).map(str::to_string));
+        }
+    }
+    terms
+}
+
+fn flatten_values(value: &Value, out: &mut Vec<String>) {
+    match value {
+        Value::String(s) => out.push(s.clone()),
+        Value::Number(n) => out.push(n.to_string()),
+        Value::Array(items) => items.iter().for_each(|v| flatten_values(v, out)),
+        Value::Object(map) => map.values().for_each(|v| flatten_values(v, out)),
+        _ => {}
+    }
+}
+
+fn title_from_path(path: &str) -> String {
+    Path::new(path)
+        .file_stem()
+        .map(|s| s.to_string_lossy().replace(['-', '_'], " "))
+        .unwrap_or_default()
+}
+
+fn is_markdown(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|e| e.to_str()),
+        Some("md") | Some("markdown")
+    )
+}
+
+/// unicode61 分词器不会切分连续的中日韩文字，这里在每个字两侧加空格，按单字索引
+fn segment_cjk(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + text.len() / 2);
+    for c in text.chars() {
+        if is_cjk(c) {
+            out.push(' ');
+            out.push(c);
+            out.push(' ');
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn desegment_cjk(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    for (i, &c) in chars.iter().enumerate() {
+        if c == ' ' {
+            let prev = i.checked_sub(1).map(|j| chars[j]);
+            let next = chars.get(i + 1).copied();
+            if prev.is_some_and(is_cjk) || next.is_some_and(is_cjk) || prev == Some(' ') {
+                continue;
+            }
+        }
+        out.push(c);
+    }
+    out.trim().to_string()
+}
+
+pub(crate) fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF      // 平假名、片假名
+        | 0x3400..=0x4DBF    // CJK 扩展 A
+        | 0x4E00..=0x9FFF    // CJK 统一汉字
+        | 0xAC00..=0xD7AF    // 韩文音节
+        | 0xF900..=0xFAFF    // CJK 兼容汉字
+        | 0x20000..=0x2FA1F  // CJK 扩展 B 及以后
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(project: &Path, relative: &str, content: &str) -> std::path::PathBuf {
+        let file = project.join(relative);
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(&file, content).unwrap();
+        file
+    }
+
+    #[test]
+    fn refresh_only_reindexes_changed_files() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "content/a.md", "---\ntitle: A\n---\nalpha\n");
+        write(dir.path(), "content/b.md", "---\ntitle: B\n---\nbeta\n");
+        let mut conn = Connection::open_in_memory().unwrap();
+
+        let stats = refresh_index(&mut conn, dir.path()).unwrap();
+        assert_eq!((stats.indexed, stats.removed, stats.total), (2, 0, 2));
+        let stats = refresh_index(&mut conn, dir.path()).unwrap();
+        assert_eq!((stats.indexed, stats.removed, stats.total), (0, 0, 2));
+
+        write(dir.path(), "content/a.md", "---\ntitle: A\n---\nalpha gamma\n");
+        fs::remove_file(dir.path().join("content/b.md")).unwrap();
+        let stats = refresh_index(&mut conn, dir.path()).unwrap();
+        assert_eq!((stats.indexed, stats.removed, stats.total), (1, 1, 1));
+        assert_eq!(search(&conn, "gamma", 10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn tags_with_spaces_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        write(
+            dir.path(),
+            "content/post.md",
+            "---\ntitle: Post\ntags: [machine learning, 静态网站]\ncategories: [rust]\n---\nbody\n",
+        );
+        let mut conn = Connection::open_in_memory().unwrap();
+        refresh_index(&mut conn, dir.path()).unwrap();
+
+        let hits = search(&conn, "learning", 10).unwrap();
+        assert_eq!(hits[0].tags, vec!["machine learning", "静态网站", "rust"]);
+        assert_eq!(search(&conn, "网站", 10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn snippets_escape_html_around_mark() {
+        let hits = line_matches("Use <script>alert(1)</script> & more", &["alert".to_string()]);
+        assert_eq!(hits[0].snippet, "Use &lt;script&gt;<mark>alert</mark>(1)&lt;/script&gt; &amp; more");
+
+        // 小写化改变长度时返回整行，同样需要转义
+        let hits = line_matches("İ <b>x</b>", &["x".to_string()]);
+        assert_eq!(hits[0].snippet, "İ &lt;b&gt;x&lt;/b&gt;");
+    }
+
+    #[test]
+    fn update_file_follows_saves_and_deletes() {
+        let dir = tempfile::tempdir().unwrap();
+        let conn = Connection::open_in_memory().unwrap();
+        let file = write(dir.path(), "content/post.md", "---\ntitle: Post\n---\nfirst draft\n");
+        update_file(&conn, dir.path(), &file).unwrap();
+        assert_eq!(search(&conn, "draft", 10).unwrap().len(), 1);
+
+        fs::write(&file, "---\ntitle: Post\n---\nfinal version\n").unwrap();
+        update_file(&conn, dir.path(), &file).unwrap();
+        assert!(search(&conn, "draft", 10).unwrap().is_empty());
+        assert_eq!(search(&conn, "final", 10).unwrap().len(), 1);
+
+        fs::remove_file(&file).unwrap();
+        update_file(&conn, dir.path(), &file).unwrap();
+        assert!(search(&conn, "final", 10).unwrap().is_empty());
+
+        let outside = write(dir.path(), "README.md", "final");
+        update_file(&conn, dir.path(), &outside).unwrap();
+        assert!(search(&conn, "final", 10).unwrap().is_empty());
+    }
+}
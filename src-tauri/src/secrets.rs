@@ -0,0 +1,61 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use keyring::Entry;
+use crate::error::{AppError, Result};
+
+/// 系统钥匙串中的服务名
+const SERVICE: &str = "com.pineappleblog.editor";
+
+/// 部署目标的凭据，例如 `access_key_id`、`secret_access_key`。
+/// 保存在系统钥匙串（macOS Keychain、Windows 凭据管理器、Secret Service）中，不写入项目设置
+pub type Credentials = BTreeMap<String, String>;
+
+pub async fn load_credentials(project_path: &Path, target: &str) -> Result<Credentials> {
+    let account = account(project_path, target);
+    blocking(move || match Entry::new(SERVICE, &account)?.get_password() {
+        Ok(secret) => Ok(serde_json::from_str(&secret)?),
+        Err(keyring::Error::NoEntry) => Ok(Credentials::new()),
+        Err(e) => Err(e.into()),
+    })
+    .await
+}
+
+pub async fn save_credentials(project_path: &Path, target: &str, credentials: &Credentials) -> Result<()> {
+    let account = account(project_path, target);
+    let secret = serde_json::to_string(credentials)?;
+    blocking(move || Ok(Entry::new(SERVICE, &account)?.set_password(&secret)?)).await
+}
+
+pub async fn delete_credentials(project_path: &Path, target: &str) -> Result<()> {
+    let account = account(project_path, target);
+    blocking(move || match Entry::new(SERVICE, &account)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(e.into()),
+    })
+    .await
+}
+
+/// 只返回保存了哪些字段，不返回内容
+pub async fn credential_keys(project_path: &Path, target: &str) -> Result<Vec<String>> {
+    Ok(load_credentials(project_path, target).await?.into_keys().collect())
+}
+
+/// 取出必需的字段
+pub fn required<'a>(credentials: &'a Credentials, key: &str) -> Result<&'a str> {
+    credentials
+        .get(key)
+        .map(String::as_str)
+        .filter(|value| !value.is_empty())
+        .ok_or_else(|| AppError::CredentialError(format!("Missing credential: {}", key)))
+}
+
+fn account(project_path: &Path, target: &str) -> String {
+    format!("{}#{}", project_path.display(), target)
+}
+
+/// 钥匙串的调用是阻塞的
+async fn blocking<T: Send + 'static>(f: impl FnOnce() -> Result<T> + Send + 'static) -> Result<T> {
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| AppError::CredentialError(e.to_string()))?
+}
@@ -0,0 +1,132 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::deploy::{DeployGateSettings, DeployTargetConfig};
+use crate::error::{AppError, Result};
+use crate::git::GitSettings;
+use crate::hooks::Hook;
+use crate::models::{BuildProfile, EngineType};
+use crate::preview::PortRange;
+use crate::revisions::RevisionSettings;
+use crate::scheduler::PublishSettings;
+
+/// 编辑器在站点根目录下使用的数据目录
+pub const PROJECT_DATA_DIR: &str = ".pineapple";
+const SETTINGS_FILE: &str = "settings.json";
+
+/// 按项目保存的编辑器设置，存放在 `.pineapple/settings.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProjectSettings {
+    /// 用户保存的构建配置，与内置配置同名时覆盖内置配置
+    pub build_profiles: Vec<BuildProfile>,
+    pub preview_ports: PortRange,
+    pub publishing: PublishSettings,
+    pub revisions: RevisionSettings,
+    pub git: GitSettings,
+    pub deploy_targets: Vec<DeployTargetConfig>,
+    pub deploy_gate: DeployGateSettings,
+    pub hooks: Vec<Hook>,
+}
+
+impl ProjectSettings {
+    /// 保存的构建配置加上没有被覆盖的内置配置
+    pub fn profiles(&self, engine: &EngineType) -> Vec<BuildProfile> {
+        let mut profiles = self.build_profiles.clone();
+        for builtin in BuildProfile::builtin(engine) {
+            if !profiles.iter().any(|p| p.name == builtin.name) {
+                profiles.push(builtin);
+            }
+        }
+        profiles
+    }
+
+    pub fn profile(&self, name: &str, engine: &EngineType) -> Result<BuildProfile> {
+        self.profiles(engine)
+            .into_iter()
+            .find(|p| p.name == name)
+            .ok_or_else(|| AppError::NotFound(format!("Build profile {}", name)))
+    }
+
+    pub fn deploy_target(&self, name: &str) -> Result<&DeployTargetConfig> {
+        self.deploy_targets
+            .iter()
+            .find(|t| t.name == name)
+            .ok_or_else(|| AppError::NotFound(format!("Deploy target {}", name)))
+    }
+}
+
+pub fn project_data_dir(project_path: &Path) -> PathBuf {
+    project_path.join(PROJECT_DATA_DIR)
+}
+
+/// 创建数据目录，并在其中放一个忽略所有内容的 `.gitignore`，
+/// 数据库、部署缓存和撤销记录都不会被提交到站点仓库
+pub fn ensure_project_data_dir(project_path: &Path) -> Result<PathBuf> {
+    let dir = project_data_dir(project_path);
+    fs::create_dir_all(&dir)?;
+    let gitignore = dir.join(".gitignore");
+    if !gitignore.exists() {
+        fs::write(gitignore, "*\n")?;
+    }
+    Ok(dir)
+}
+
+pub fn load_project_settings(project_path: &Path) -> Result<ProjectSettings> {
+    let path = project_data_dir(project_path).join(SETTINGS_FILE);
+    if !path.exists() {
+        return Ok(ProjectSettings::default());
+    }
+
+    let content = fs::read_to_string(&path)?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+pub fn save_project_settings(project_path: &Path, settings: &ProjectSettings) -> Result<()> {
+    let dir = ensure_project_data_dir(project_path)?;
+    fs::write(dir.join(SETTINGS_FILE), serde_json::to_string_pretty(settings)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::zola_adapter::unsupported_options;
+    use crate::models::BuildOptions;
+
+    #[test]
+    fn builtin_profiles_only_use_options_zola_supports() {
+        let settings = ProjectSettings::default();
+        for profile in settings.profiles(&EngineType::Zola) {
+            assert!(unsupported_options(&profile.options).is_empty(), "{}", profile.name);
+        }
+        let preview = settings.profile("preview", &EngineType::Hugo).unwrap();
+        assert!(preview.options.build_future);
+        // 默认选项两种引擎都能用，minify 只由 Hugo 的 production 打开
+        assert!(unsupported_options(&BuildOptions::default()).is_empty());
+        assert!(settings.profile("production", &EngineType::Hugo).unwrap().options.minify);
+        assert!(!preview.options.minify);
+    }
+
+    #[test]
+    fn saved_profile_overrides_builtin() {
+        let settings = ProjectSettings {
+            build_profiles: vec![BuildProfile {
+                name: "production".to_string(),
+                options: BuildOptions {
+                    base_url: Some("https://example.com/".to_string()),
+                    ..BuildOptions::default()
+                },
+            }],
+            ..ProjectSettings::default()
+        };
+        let profiles = settings.profiles(&EngineType::Zola);
+        assert_eq!(profiles.len(), 2);
+        let production = settings.profile("production", &EngineType::Zola).unwrap();
+        assert_eq!(production.options.base_url.as_deref(), Some("https://example.com/"));
+        assert!(matches!(
+            settings.profile("staging", &EngineType::Zola),
+            Err(AppError::NotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,256 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use regex::Regex;
+use serde::Serialize;
+use crate::models::EngineType;
+use crate::utils::walk_files;
+
+/// 短代码参数：命名参数（`.Get "src"`）或位置参数（`.Get 0`）
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(tag = "kind", content = "name", rename_all = "lowercase")]
+pub enum ShortcodeParam {
+    Named(String),
+    Positional(usize),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ShortcodeInfo {
+    pub name: String,
+    /// "project" 或 "theme:<主题名>"
+    pub source: String,
+    pub path: String,
+    pub params: Vec<ShortcodeParam>,
+    /// 是否使用了内部内容（Hugo 的 `.Inner`，Zola 的 `body`）
+    pub has_inner: bool,
+}
+
+/// 扫描项目和主题中的短代码模板；同名短代码以项目自身的为准
+pub fn discover_shortcodes(project_path: &Path, engine: &EngineType) -> Vec<ShortcodeInfo> {
+    let relative_dirs: &[&str] = match engine {
+        EngineType::Hugo => &["layouts/shortcodes", "layouts/_shortcodes"],
+        EngineType::Zola => &["templates/shortcodes"],
+    };
+
+    let mut roots: Vec<(String, PathBuf)> = vec![("project".to_string(), project_path.to_path_buf())];
+    if let Ok(entries) = fs::read_dir(project_path.join("themes")) {
+        let mut themes: Vec<PathBuf> = entries.flatten().map(|e| e.path()).filter(|p| p.is_dir()).collect();
+        themes.sort();
+        for theme in themes {
+            let name = theme.file_name().unwrap_or_default().to_string_lossy().to_string();
+            roots.push((format!("theme:{}", name), theme));
+        }
+    }
+
+    let mut seen = BTreeSet::new();
+    let mut shortcodes = Vec::new();
+
+    for (source, root) in roots {
+        for relative in relative_dirs {
+            let dir = root.join(relative);
+            for file in walk_files(&dir) {
+                let Some(name) = shortcode_name(&dir, &file) else {
+                    continue;
+                };
+                if !seen.insert(name.clone()) {
+                    continue;
+                }
+                let Ok(template) = fs::read_to_string(&file) else {
+                    continue;
+                };
+
+                let (params, has_inner) = match engine {
+                    EngineType::Hugo => hugo_params(&template),
+                    EngineType::Zola => zola_params(&template),
+                };
+
+                shortcodes.push(ShortcodeInfo {
+                    name,
+                    source: source.clone(),
+                    path: file.to_string_lossy().to_string(),
+                    params,
+                    has_inner,
+                });
+            }
+        }
+    }
+
+    shortcodes.sort_by(|a, b| a.name.cmp(&b.name));
+    shortcodes
+}
+
+/// `figure.html`、`figure.amp.html` 都是 "figure"；子目录中的为 "dir/name"
+fn shortcode_name(dir: &Path, file: &Path) -> Option<String> {
+    let extension = file.extension()?.to_str()?;
+    if !matches!(extension, "html" | "md" | "xml" | "json" | "txt") {
+        return None;
+    }
+
+    let relative = file.strip_prefix(dir).ok()?;
+    let stem = relative.file_name()?.to_str()?.split('.').next()?.to_string();
+    let name = match relative.parent().filter(|p| !p.as_os_str().is_empty()) {
+        Some(parent) => format!("{}/{}", parent.to_string_lossy().replace('\\', "/"), stem),
+        None => stem,
+    };
+    Some(name)
+}
+
+fn hugo_params(template: &str) -> (Vec<ShortcodeParam>, bool) {
+    static PARAM: OnceLock<Regex> = OnceLock::new();
+    let re = PARAM.get_or_init(|| {
+        Regex::new(r#"(?:\.Get|index\s+\.Params)\s+(?:"([^"]+)"|`([^`]+)`|(\d+))"#).unwrap()
+    });
+
+    let params: BTreeSet<ShortcodeParam> = re
+        .captures_iter(template)
+        .filter_map(|caps| {
+            if let Some(name) = caps.get(1).or_else(|| caps.get(2)) {
+                Some(ShortcodeParam::Named(name.as_str().to_string()))
+            } else {
+                caps.get(3)?.as_str().parse().ok().map(ShortcodeParam::Positional)
+            }
+        })
+        .collect();
+
+    let has_inner = template.contains(".Inner");
+    (params.into_iter().collect(), has_inner)
+}
+
+/// Tera 模板里没有显式的参数声明，这里把模板中引用、但不是内置变量
+/// 也不是模板内部定义的变量都当作参数
+fn zola_params(template: &str) -> (Vec<ShortcodeParam>, bool) {
+    static EXPR: OnceLock<Regex> = OnceLock::new();
+    static IDENT: OnceLock<Regex> = OnceLock::new();
+    static LOCAL: OnceLock<Regex> = OnceLock::new();
+    static STRING: OnceLock<Regex> = OnceLock::new();
+    let expr_re = EXPR.get_or_init(|| Regex::new(r"(?s)\{\{-?(.*?)-?\}\}|\{%-?(.*?)-?%\}").unwrap());
+    let ident_re = IDENT.get_or_init(|| Regex::new(r"[A-Za-z_][A-Za-z0-9_]*").unwrap());
+    let local_re = LOCAL.get_or_init(|| {
+        Regex::new(r"\b(?:set|set_global|for)\s+([A-Za-z_][A-Za-z0-9_]*)(?:\s*,\s*([A-Za-z_][A-Za-z0-9_]*))?").unwrap()
+    });
+    let string_re = STRING.get_or_init(|| Regex::new(r#""[^"]*"|'[^']*'|`[^`]*`"#).unwrap());
+
+    const RESERVED: &[&str] = &[
+        "if", "elif", "else", "endif", "for", "in", "endfor", "set", "set_global", "and", "or",
+        "not", "is", "true", "false", "True", "False", "none", "macro", "endmacro", "block",
+        "endblock", "filter", "endfilter", "raw", "endraw", "break", "continue", "include",
+        "import", "as", "with", "config", "page", "section", "lang", "nth", "body",
+        "current_url", "current_path", "now", "loop", "defined", "undefined", "odd", "even",
+        "starting_with", "ending_with", "containing", "matching", "number", "string",
+    ];
+
+    let locals: BTreeSet<String> = local_re
+        .captures_iter(template)
+        .flat_map(|caps| [caps.get(1), caps.get(2)])
+        .flatten()
+        .map(|m| m.as_str().to_string())
+        .collect();
+
+    let mut params = BTreeSet::new();
+    let mut has_inner = false;
+    for caps in expr_re.captures_iter(template) {
+        let Some(expr) = caps.get(1).or_else(|| caps.get(2)) else {
+            continue;
+        };
+        let expr = string_re.replace_all(expr.as_str(), "\"\"");
+        for ident in ident_re.find_iter(&expr) {
+            let before = expr[..ident.start()].trim_end().chars().last();
+            let after = expr[ident.end()..].trim_start();
+            // 属性访问、过滤器、函数调用和关键字参数都不是短代码参数
+            let is_kwarg = after.starts_with('=') && !after.starts_with("==");
+            if matches!(before, Some('.') | Some('|')) || after.starts_with('(') || is_kwarg {
+                continue;
+            }
+            let name = ident.as_str();
+            if name == "body" {
+                has_inner = true;
+            }
+            if RESERVED.contains(&name) || locals.contains(name) {
+                continue;
+            }
+            params.insert(ShortcodeParam::Named(name.to_string()));
+        }
+    }
+
+    (params.into_iter().collect(), has_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn write(path: PathBuf, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn discovers_hugo_shortcodes_with_theme_overrides() {
+        let dir = tempdir().unwrap();
+        let site = dir.path();
+        write(
+            site.join("layouts/shortcodes/figure.html"),
+            r#"<img src="{{ .Get "src" }}" alt="{{ .Get 1 }}">{{ index .Params `title` }}"#,
+        );
+        write(site.join("layouts/shortcodes/docs/callout.md"), "{{ .Inner }}");
+        write(site.join("layouts/shortcodes/notes.txt.bak"), "");
+        write(site.join("themes/base/layouts/shortcodes/figure.html"), "{{ .Get \"theme\" }}");
+        write(site.join("themes/base/layouts/_shortcodes/notice.html"), "{{ .Get 0 }}{{ .Inner }}");
+
+        let found = discover_shortcodes(site, &EngineType::Hugo);
+        let names: Vec<&str> = found.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["docs/callout", "figure", "notice"]);
+
+        // 项目自身的 figure 覆盖主题里的同名模板
+        let figure = &found[1];
+        assert_eq!(figure.source, "project");
+        assert_eq!(
+            figure.params,
+            [
+                ShortcodeParam::Named("src".into()),
+                ShortcodeParam::Named("title".into()),
+                ShortcodeParam::Positional(1),
+            ]
+        );
+        assert!(!figure.has_inner);
+
+        assert!(found[0].has_inner);
+        let notice = &found[2];
+        assert_eq!(notice.source, "theme:base");
+        assert_eq!(notice.params, [ShortcodeParam::Positional(0)]);
+        assert!(notice.has_inner);
+    }
+
+    #[test]
+    fn discovers_zola_shortcodes() {
+        let dir = tempdir().unwrap();
+        let site = dir.path();
+        write(
+            site.join("templates/shortcodes/youtube.html"),
+            "{% set w = width | default(value=640) %}<iframe src=\"{{ id }}\" width=\"{{ w }}\" \
+             class=\"{{ class | default(value='') }}\"></iframe>{% if page.title %}{% endif %}",
+        );
+        write(site.join("templates/shortcodes/quote.md"), "> {{ body }}\n>\n> {{ author }}");
+        // Hugo 的目录不属于 Zola 站点
+        write(site.join("layouts/shortcodes/figure.html"), "");
+
+        let found = discover_shortcodes(site, &EngineType::Zola);
+        let names: Vec<&str> = found.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["quote", "youtube"]);
+
+        assert_eq!(found[0].params, [ShortcodeParam::Named("author".into())]);
+        assert!(found[0].has_inner);
+
+        assert_eq!(
+            found[1].params,
+            [
+                ShortcodeParam::Named("class".into()),
+                ShortcodeParam::Named("id".into()),
+                ShortcodeParam::Named("width".into()),
+            ]
+        );
+        assert!(!found[1].has_inner);
+    }
+}
@@ -0,0 +1,5 @@
+pub mod catalogue;
+pub mod parse;
+
+pub use catalogue::{discover_shortcodes, ShortcodeInfo, ShortcodeParam};
+pub use parse::{parse_shortcodes, ShortcodeArgs, ShortcodeCall, ShortcodeSegment, ShortcodeSyntax};
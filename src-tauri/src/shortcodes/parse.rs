@@ -0,0 +1,446 @@
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::sync::OnceLock;
+use regex::Regex;
+use serde::Serialize;
+use crate::models::EngineType;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ShortcodeSyntax {
+    /// `{{< name >}}`：输出直接作为 HTML
+    HugoHtml,
+    /// `{{% name %}}`：输出继续按 Markdown 处理
+    HugoMarkdown,
+    /// `{{ name(...) }}` 或 `{% name(...) %}...{% end %}`
+    Zola,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ShortcodeArgs {
+    pub positional: Vec<String>,
+    pub named: BTreeMap<String, String>,
+}
+
+impl ShortcodeArgs {
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.named.get(name).map(String::as_str)
+    }
+
+    pub fn get_at(&self, index: usize) -> Option<&str> {
+        self.positional.get(index).map(String::as_str)
+    }
+
+    /// 先按名称取，再按位置取（很多内置短代码两种写法都支持）
+    pub fn get_either(&self, name: &str, index: usize) -> Option<&str> {
+        self.get(name).or_else(|| self.get_at(index))
+    }
+}
+
+/// 文章中的一个顶层片段：短代码调用，或被转义的短代码（按原文显示）
+#[derive(Debug, Clone)]
+pub enum ShortcodeSegment {
+    Call(ShortcodeCall),
+    Literal { range: Range<usize>, text: String },
+}
+
+impl ShortcodeSegment {
+    pub fn range(&self) -> &Range<usize> {
+        match self {
+            ShortcodeSegment::Call(call) => &call.range,
+            ShortcodeSegment::Literal { range, .. } => range,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ShortcodeCall {
+    pub name: String,
+    pub args: ShortcodeArgs,
+    pub syntax: ShortcodeSyntax,
+    /// 包含开始和结束标签的完整范围
+    pub range: Range<usize>,
+    /// 开始标签和结束标签之间的内容，可能包含嵌套的短代码
+    pub inner: Option<Range<usize>>,
+}
+
+/// 找出顶层的短代码；嵌套在内部内容里的短代码由调用方对 `inner` 递归解析
+pub fn parse_shortcodes(source: &str, engine: &EngineType) -> Vec<ShortcodeSegment> {
+    match engine {
+        EngineType::Hugo => parse_hugo(source),
+        EngineType::Zola => parse_zola(source),
+    }
+}
+
+#[derive(Debug)]
+enum TagKind {
+    Open,
+    Close,
+    SelfClosing,
+}
+
+#[derive(Debug)]
+struct HugoTag {
+    kind: TagKind,
+    name: String,
+    args: ShortcodeArgs,
+    syntax: ShortcodeSyntax,
+    range: Range<usize>,
+}
+
+fn parse_hugo(source: &str) -> Vec<ShortcodeSegment> {
+    let mut tags = Vec::new();
+    let mut literals = Vec::new();
+    let mut pos = 0;
+
+    while let Some(found) = source[pos..].find("{{") {
+        let start = pos + found;
+        let rest = &source[start + 2..];
+        let (syntax, closing) = if rest.starts_with('<') {
+            (ShortcodeSyntax::HugoHtml, ">}}")
+        } else if rest.starts_with('%') {
+            (ShortcodeSyntax::HugoMarkdown, "%}}")
+        } else {
+            pos = start + 2;
+            continue;
+        };
+
+        let body_start = start + 3;
+        // `{{</* figure */>}}` 是被转义的短代码，预览时原样显示
+        if source[body_start..].trim_start().starts_with("/*") {
+            let end_marker = format!("*/{}", closing);
+            if let Some(end) = source[body_start..].find(&end_marker) {
+                let end = body_start + end + end_marker.len();
+                let inner = source[body_start..end - end_marker.len()].trim();
+                let inner = inner.trim_start_matches("/*").trim();
+                let open = &source[start..start + 3];
+                literals.push(ShortcodeSegment::Literal {
+                    range: start..end,
+                    text: format!("{} {} {}", open, inner, closing),
+                });
+                pos = end;
+                continue;
+            }
+        }
+
+        let Some(body_len) = find_unquoted(&source[body_start..], closing) else {
+            pos = body_start;
+            continue;
+        };
+        let end = body_start + body_len + closing.len();
+        let body = source[body_start..body_start + body_len].trim();
+
+        let (kind, body) = if let Some(name) = body.strip_prefix('/') {
+            (TagKind::Close, name.trim())
+        } else if let Some(body) = body.strip_suffix('/') {
+            (TagKind::SelfClosing, body.trim())
+        } else {
+            (TagKind::Open, body)
+        };
+
+        let mut tokens = tokenize_args(body).into_iter();
+        let Some(name) = tokens.next() else {
+            pos = end;
+            continue;
+        };
+
+        let mut args = ShortcodeArgs::default();
+        for token in tokens {
+            match token.split_once('=') {
+                Some((key, value)) if is_identifier(key) => {
+                    args.named.insert(key.to_string(), unquote(value));
+                }
+                _ => args.positional.push(unquote(&token)),
+            }
+        }
+
+        tags.push(HugoTag {
+            kind,
+            name: unquote(&name),
+            args,
+            syntax,
+            range: start..end,
+        });
+        pos = end;
+    }
+
+    // 用栈把开始标签和结束标签配对，找不到结束标签的视为没有内部内容
+    let mut closers: Vec<Option<usize>> = vec![None; tags.len()];
+    let mut stack: Vec<usize> = Vec::new();
+    for (i, tag) in tags.iter().enumerate() {
+        match tag.kind {
+            TagKind::Open => stack.push(i),
+            TagKind::SelfClosing => {}
+            TagKind::Close => {
+                if let Some(depth) = stack.iter().rposition(|&j| tags[j].name == tag.name) {
+                    closers[stack[depth]] = Some(i);
+                    stack.truncate(depth);
+                }
+            }
+        }
+    }
+
+    let mut segments = Vec::new();
+    let mut covered_until = 0;
+    for (i, tag) in tags.iter().enumerate() {
+        if tag.range.start < covered_until || matches!(tag.kind, TagKind::Close) {
+            continue;
+        }
+        let (range, inner) = match closers[i] {
+            Some(close) => (
+                tag.range.start..tags[close].range.end,
+                Some(tag.range.end..tags[close].range.start),
+            ),
+            None => (tag.range.clone(), None),
+        };
+        covered_until = range.end;
+        segments.push(ShortcodeSegment::Call(ShortcodeCall {
+            name: tag.name.clone(),
+            args: tag.args.clone(),
+            syntax: tag.syntax,
+            range,
+            inner,
+        }));
+    }
+
+    // 位于短代码内部内容中的转义短代码留给递归解析处理
+    let literals: Vec<ShortcodeSegment> = literals
+        .into_iter()
+        .filter(|l| {
+            !segments
+                .iter()
+                .any(|s| s.range().start <= l.range().start && l.range().end <= s.range().end)
+        })
+        .collect();
+    segments.extend(literals);
+    segments.sort_by_key(|s| s.range().start);
+    segments
+}
+
+fn parse_zola(source: &str) -> Vec<ShortcodeSegment> {
+    static CALL: OnceLock<Regex> = OnceLock::new();
+    static END: OnceLock<Regex> = OnceLock::new();
+    let call_re = CALL.get_or_init(|| {
+        Regex::new(r"(?s)\{\{\s*([A-Za-z_][A-Za-z0-9_]*)\((.*?)\)\s*\}\}|\{%\s*([A-Za-z_][A-Za-z0-9_]*)\((.*?)\)\s*%\}").unwrap()
+    });
+    let end_re = END.get_or_init(|| Regex::new(r"\{%\s*end\s*%\}").unwrap());
+
+    let mut segments = Vec::new();
+    let mut pos = 0;
+    while let Some(caps) = call_re.captures_at(source, pos) {
+        let whole = caps.get(0).unwrap();
+        let is_block = caps.get(3).is_some();
+        let name = caps.get(1).or_else(|| caps.get(3)).unwrap().as_str().to_string();
+        let args = parse_zola_args(caps.get(2).or_else(|| caps.get(4)).unwrap().as_str());
+
+        let (range, inner) = if is_block {
+            match end_re.find_at(source, whole.end()) {
+                Some(end) => (whole.start()..end.end(), Some(whole.end()..end.start())),
+                None => (whole.range(), None),
+            }
+        } else {
+            (whole.range(), None)
+        };
+
+        pos = range.end;
+        segments.push(ShortcodeSegment::Call(ShortcodeCall {
+            name,
+            args,
+            syntax: ShortcodeSyntax::Zola,
+            range,
+            inner,
+        }));
+    }
+
+    segments
+}
+
+fn parse_zola_args(args: &str) -> ShortcodeArgs {
+    let mut parsed = ShortcodeArgs::default();
+    let mut rest = args.trim();
+
+    while !rest.is_empty() {
+        let Some((key, value)) = rest.split_once('=') else {
+            break;
+        };
+        let key = key.trim().trim_start_matches(',').trim().to_string();
+        let value = value.trim_start();
+
+        let value_len = match value.chars().next() {
+            Some(quote @ ('"' | '\'' | '`')) => value[1..].find(quote).map(|i| i + 2).unwrap_or(value.len()),
+            Some('[') => value.find(']').map(|i| i + 1).unwrap_or(value.len()),
+            _ => value.find(',').unwrap_or(value.len()),
+        };
+
+        parsed.named.insert(key, unquote(value[..value_len].trim()));
+        rest = value[value_len..].trim_start().trim_start_matches(',').trim_start();
+    }
+
+    parsed
+}
+
+/// 在引号之外查找结束标记
+fn find_unquoted(haystack: &str, needle: &str) -> Option<usize> {
+    let mut quote: Option<char> = None;
+    for (i, c) in haystack.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '`' => quote = Some(c),
+            None if haystack[i..].starts_with(needle) => return Some(i),
+            None => {}
+        }
+    }
+    None
+}
+
+/// 按空白切分参数，引号内的空白保留
+fn tokenize_args(body: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+
+    for c in body.chars() {
+        match quote {
+            Some(q) => {
+                current.push(c);
+                if c == q {
+                    quote = None;
+                }
+            }
+            None if c == '"' || c == '`' => {
+                current.push(c);
+                quote = Some(c);
+            }
+            None if c.is_whitespace() => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            None => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    for quote in ['"', '\'', '`'] {
+        if value.len() >= 2 && value.starts_with(quote) && value.ends_with(quote) {
+            let inner = &value[1..value.len() - 1];
+            return if quote == '"' {
+                inner.replace("\\\"", "\"")
+            } else {
+                inner.to_string()
+            };
+        }
+    }
+    value.to_string()
+}
+
+fn is_identifier(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calls(source: &str, engine: &EngineType) -> Vec<ShortcodeCall> {
+        parse_shortcodes(source, engine)
+            .into_iter()
+            .filter_map(|segment| match segment {
+                ShortcodeSegment::Call(call) => Some(call),
+                ShortcodeSegment::Literal { .. } => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parses_positional_named_and_quoted_args() {
+        let source = r#"{{< figure "a b.png" src="x.png" title=`raw "q"` 3 >}}"#;
+        let calls = calls(source, &EngineType::Hugo);
+        assert_eq!(calls.len(), 1);
+        let call = &calls[0];
+        assert_eq!(call.name, "figure");
+        assert_eq!(call.syntax, ShortcodeSyntax::HugoHtml);
+        assert_eq!(call.args.positional, vec!["a b.png", "3"]);
+        assert_eq!(call.args.get("src"), Some("x.png"));
+        assert_eq!(call.args.get("title"), Some("raw \"q\""));
+        assert_eq!(call.args.get_either("alt", 1), Some("3"));
+        assert_eq!(call.range, 0..source.len());
+        assert!(call.inner.is_none());
+    }
+
+    #[test]
+    fn closing_marker_inside_quotes_does_not_end_the_tag() {
+        let source = r#"{{< note title="a >}} b" >}}"#;
+        let calls = calls(source, &EngineType::Hugo);
+        assert_eq!(calls[0].args.get("title"), Some("a >}} b"));
+        assert_eq!(calls[0].range.end, source.len());
+    }
+
+    #[test]
+    fn pairs_open_and_close_tags() {
+        let source = "{{% note %}}\nouter {{< note >}}inner{{< /note >}}\n{{% /note %}} {{< br />}}";
+        let calls = calls(source, &EngineType::Hugo);
+        // 嵌套的同名短代码留在外层的 inner 里，只返回顶层调用
+        assert_eq!(calls.len(), 2);
+
+        let outer = &calls[0];
+        assert_eq!(outer.syntax, ShortcodeSyntax::HugoMarkdown);
+        assert_eq!(&source[outer.range.clone()], &source[..source.find(" {{< br").unwrap()]);
+        assert_eq!(
+            &source[outer.inner.clone().unwrap()],
+            "\nouter {{< note >}}inner{{< /note >}}\n"
+        );
+
+        let br = &calls[1];
+        assert_eq!(br.name, "br");
+        assert!(br.inner.is_none());
+        assert_eq!(br.range.end, source.len());
+    }
+
+    #[test]
+    fn unclosed_open_tag_has_no_inner() {
+        let calls = calls("{{< youtube abc >}} text", &EngineType::Hugo);
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].args.positional, vec!["abc"]);
+        assert!(calls[0].inner.is_none());
+    }
+
+    #[test]
+    fn escaped_shortcodes_are_literals() {
+        let segments = parse_shortcodes("{{</* figure src=\"a\" */>}}", &EngineType::Hugo);
+        assert_eq!(segments.len(), 1);
+        match &segments[0] {
+            ShortcodeSegment::Literal { text, .. } => assert_eq!(text, "{{< figure src=\"a\" >}}"),
+            other => panic!("unexpected segment: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_zola_inline_and_block_calls() {
+        let source = "{{ youtube(id=\"abc\", autoplay=true, tags=[\"a\", \"b\"]) }}\n\
+                      {% quote(author='Me, myself') %}\nBody\n{% end %}";
+        let calls = calls(source, &EngineType::Zola);
+        assert_eq!(calls.len(), 2);
+
+        let inline = &calls[0];
+        assert_eq!(inline.name, "youtube");
+        assert_eq!(inline.syntax, ShortcodeSyntax::Zola);
+        assert_eq!(inline.args.get("id"), Some("abc"));
+        assert_eq!(inline.args.get("autoplay"), Some("true"));
+        assert_eq!(inline.args.get("tags"), Some("[\"a\", \"b\"]"));
+        assert!(inline.inner.is_none());
+
+        let block = &calls[1];
+        assert_eq!(block.name, "quote");
+        assert_eq!(block.args.get("author"), Some("Me, myself"));
+        assert_eq!(&source[block.inner.clone().unwrap()], "\nBody\n");
+        assert_eq!(block.range.end, source.len());
+    }
+}
@@ -0,0 +1,78 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde_json::Value;
+use crate::error::{AppError, Result};
+use crate::front_matter::{get_field, toml_to_json};
+use crate::models::EngineType;
+use crate::utils::find_site_config;
+
+/// 读取站点配置（TOML / YAML / JSON），统一转换为 JSON 值；没有配置文件时返回空对象
+pub fn load_site_config(project_path: &Path) -> Result<Value> {
+    let Some(config_path) = find_site_config(project_path) else {
+        return Ok(Value::Object(Default::default()));
+    };
+
+    let content = fs::read_to_string(&config_path)?;
+    let extension = config_path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default();
+
+    match extension {
+        "toml" => {
+            let value: toml::Value =
+                toml::from_str(&content).map_err(|e| AppError::ParseError(e.to_string()))?;
+            Ok(toml_to_json(value))
+        }
+        "yaml" | "yml" => {
+            serde_yaml::from_str(&content).map_err(|e| AppError::ParseError(e.to_string()))
+        }
+        _ => Ok(serde_json::from_str(&content)?),
+    }
+}
+
+/// 按路径读取嵌套配置，例如 `["markup", "goldmark", "renderer"]`
+pub fn config_path<'a>(config: &'a Value, path: &[&str]) -> Option<&'a Value> {
+    path.iter()
+        .try_fold(config, |value, key| get_field(value, key))
+}
+
+/// 构建输出目录：构建选项中的 `destination` 优先，其次是站点配置中的
+/// Hugo `publishDir` / Zola `output_dir`，都没有时为 `public`
+pub fn output_dir(project_path: &Path, engine: &EngineType, destination: Option<&str>) -> PathBuf {
+    let key = match engine {
+        EngineType::Hugo => "publishDir",
+        EngineType::Zola => "output_dir",
+    };
+    let configured = load_site_config(project_path)
+        .ok()
+        .and_then(|config| get_field(&config, key).and_then(Value::as_str).map(str::to_string));
+    let dir = destination
+        .map(str::to_string)
+        .or(configured)
+        .filter(|dir| !dir.trim().is_empty())
+        .unwrap_or_else(|| "public".to_string());
+    project_path.join(dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_dir_follows_options_then_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path();
+        assert_eq!(output_dir(project, &EngineType::Hugo, None), project.join("public"));
+
+        fs::write(project.join("hugo.toml"), "publishdir = \"dist\"\noutput_dir = \"out\"\n").unwrap();
+        // Hugo 的键名不区分大小写
+        assert_eq!(output_dir(project, &EngineType::Hugo, None), project.join("dist"));
+        assert_eq!(output_dir(project, &EngineType::Hugo, Some("tmp")), project.join("tmp"));
+
+        fs::remove_file(project.join("hugo.toml")).unwrap();
+        fs::write(project.join("config.toml"), "output_dir = \"out\"\n").unwrap();
+        assert_eq!(output_dir(project, &EngineType::Zola, None), project.join("out"));
+        assert_eq!(output_dir(project, &EngineType::Hugo, None), project.join("public"));
+    }
+}
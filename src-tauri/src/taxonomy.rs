@@ -0,0 +1,414 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::error::{AppError, Result};
+use crate::front_matter::edit::{read_list, update_list};
+use crate::front_matter::{get_field, parse_front_matter, split_front_matter};
+use crate::models::EngineType;
+use crate::site_config::{config_path, load_site_config};
+use crate::utils::{walk_files, write_all_atomic};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TermUsage {
+    pub term: String,
+    pub count: usize,
+    /// 使用该词条的文章，相对于站点根目录
+    pub posts: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TaxonomyTerms {
+    /// 分类法名称（复数形式），例如 `tags`
+    pub taxonomy: String,
+    pub terms: Vec<TermUsage>,
+}
+
+/// 对某个分类法中词条的批量修改
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "action", rename_all = "lowercase")]
+pub enum TaxonomyEdit {
+    Rename { taxonomy: String, from: String, to: String },
+    /// 把多个词条合并成一个，例如 `Rust`、`rust-lang` → `rust`
+    Merge { taxonomy: String, from: Vec<String>, into: String },
+    Delete { taxonomy: String, term: String },
+}
+
+impl TaxonomyEdit {
+    fn taxonomy(&self) -> &str {
+        match self {
+            TaxonomyEdit::Rename { taxonomy, .. }
+            | TaxonomyEdit::Merge { taxonomy, .. }
+            | TaxonomyEdit::Delete { taxonomy, .. } => taxonomy,
+        }
+    }
+
+    /// 修改一篇文章的词条列表；重命名后出现的重复词条只保留第一个
+    fn apply(&self, terms: &mut Vec<String>) {
+        let (sources, replacement): (Vec<&str>, Option<&str>) = match self {
+            TaxonomyEdit::Rename { from, to, .. } => (vec![from.as_str()], Some(to.as_str())),
+            TaxonomyEdit::Merge { from, into, .. } => (from.iter().map(String::as_str).collect(), Some(into.as_str())),
+            TaxonomyEdit::Delete { term, .. } => (vec![term.as_str()], None),
+        };
+        if !terms.iter().any(|t| sources.contains(&t.as_str())) {
+            return;
+        }
+
+        let mut updated: Vec<String> = Vec::with_capacity(terms.len());
+        for term in terms.drain(..) {
+            let term = match (sources.contains(&term.as_str()), replacement) {
+                (true, Some(replacement)) => replacement.to_string(),
+                (true, None) => continue,
+                (false, _) => term,
+            };
+            if !updated.contains(&term) {
+                updated.push(term);
+            }
+        }
+        *terms = updated;
+    }
+}
+
+/// 一篇文章的词条变化，用于预览
+#[derive(Debug, Clone, Serialize)]
+pub struct TaxonomyChange {
+    pub path: String,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+}
+
+/// 站点使用的分类法（复数形式）：Hugo 默认 tags 和 categories，Zola 在配置中声明
+pub fn taxonomy_names(engine: &EngineType, site_config: &Value) -> Vec<String> {
+    match engine {
+        EngineType::Hugo => match config_path(site_config, &["taxonomies"]) {
+            Some(Value::Object(map)) => map.values().filter_map(Value::as_str).map(str::to_string).collect(),
+            _ => vec!["tags".to_string(), "categories".to_string()],
+        },
+        EngineType::Zola => match config_path(site_config, &["taxonomies"]) {
+            Some(Value::Array(items)) => items
+                .iter()
+                .filter_map(|item| get_field(item, "name").and_then(Value::as_str))
+                .map(str::to_string)
+                .collect(),
+            _ => Vec::new(),
+        },
+    }
+}
+
+/// 文章 Front Matter 中每个分类法的词条
+pub fn page_terms(engine: &EngineType, front_matter: &Value, taxonomies: &[String]) -> Vec<(String, Vec<String>)> {
+    let source = match engine {
+        EngineType::Hugo => Some(front_matter),
+        EngineType::Zola => get_field(front_matter, "taxonomies"),
+    };
+    let Some(source) = source else {
+        return Vec::new();
+    };
+
+    taxonomies
+        .iter()
+        .filter_map(|taxonomy| {
+            let terms = match get_field(source, taxonomy)? {
+                Value::Array(items) => items.iter().filter_map(Value::as_str).map(str::to_string).collect(),
+                Value::String(term) => vec![term.clone()],
+                _ => return None,
+            };
+            Some((taxonomy.clone(), terms))
+        })
+        .collect()
+}
+
+/// 词条在 Front Matter 中的位置：Hugo 在顶层，Zola 在 `[taxonomies]` 下
+fn field_path<'a>(engine: &EngineType, taxonomy: &'a str) -> Vec<&'a str> {
+    match engine {
+        EngineType::Hugo => vec![taxonomy],
+        EngineType::Zola => vec!["taxonomies", taxonomy],
+    }
+}
+
+/// 列出所有分类法的词条及使用次数，包括配置中声明但还没有文章使用的分类法
+pub fn list_terms(project_path: &Path, engine: &EngineType) -> Result<Vec<TaxonomyTerms>> {
+    let site_config = load_site_config(project_path)?;
+    let taxonomies = taxonomy_names(engine, &site_config);
+    let mut usage: BTreeMap<String, BTreeMap<String, Vec<String>>> = taxonomies
+        .iter()
+        .map(|taxonomy| (taxonomy.clone(), BTreeMap::new()))
+        .collect();
+
+    for (file, relative) in content_files(project_path) {
+        let Ok(content) = fs::read_to_string(&file) else {
+            continue;
+        };
+        let Some(front_matter) = split_front_matter(&content)
+            .front_matter
+            .and_then(|block| parse_front_matter(&block).ok())
+        else {
+            continue;
+        };
+
+        for (taxonomy, terms) in page_terms(engine, &front_matter, &taxonomies) {
+            let by_term = usage.entry(taxonomy).or_default();
+            for term in terms {
+                let posts = by_term.entry(term).or_default();
+                if !posts.contains(&relative) {
+                    posts.push(relative.clone());
+                }
+            }
+        }
+    }
+
+    Ok(taxonomies
+        .into_iter()
+        .map(|taxonomy| {
+            let mut terms: Vec<TermUsage> = usage
+                .remove(&taxonomy)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(term, posts)| TermUsage {
+                    term,
+                    count: posts.len(),
+                    posts,
+                })
+                .collect();
+            terms.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.term.cmp(&b.term)));
+            TaxonomyTerms { taxonomy, terms }
+        })
+        .collect())
+}
+
+/// 预览：列出受影响的文章及修改前后的词条，不修改文件
+pub fn preview_edit(project_path: &Path, engine: &EngineType, edit: &TaxonomyEdit) -> Result<Vec<TaxonomyChange>> {
+    Ok(prepare_edit(project_path, engine, edit)?
+        .into_iter()
+        .map(|(_, change, _)| change)
+        .collect())
+}
+
+/// 按行改写每篇受影响文章的 Front Matter，所有文件一起写入
+pub fn apply_edit(project_path: &Path, engine: &EngineType, edit: &TaxonomyEdit) -> Result<Vec<TaxonomyChange>> {
+    let prepared = prepare_edit(project_path, engine, edit)?;
+    let writes: Vec<(PathBuf, String)> = prepared
+        .iter()
+        .map(|(file, _, content)| (file.clone(), content.clone()))
+        .collect();
+    write_all_atomic(&writes)?;
+    Ok(prepared.into_iter().map(|(_, change, _)| change).collect())
+}
+
+fn prepare_edit(
+    project_path: &Path,
+    engine: &EngineType,
+    edit: &TaxonomyEdit,
+) -> Result<Vec<(PathBuf, TaxonomyChange, String)>> {
+    let target = match edit {
+        TaxonomyEdit::Rename { to, .. } => Some(to),
+        TaxonomyEdit::Merge { into, .. } => Some(into),
+        TaxonomyEdit::Delete { .. } => None,
+    };
+    if target.is_some_and(|t| t.trim().is_empty()) {
+        return Err(AppError::ParseError("Term name is empty".to_string()));
+    }
+
+    let path = field_path(engine, edit.taxonomy());
+    let mut prepared = Vec::new();
+    for (file, relative) in content_files(project_path) {
+        let Ok(content) = fs::read_to_string(&file) else {
+            continue;
+        };
+        let before = read_list(&content, &path);
+        let mut after = before.clone();
+        edit.apply(&mut after);
+        if after == before {
+            continue;
+        }
+
+        let updated = update_list(&content, &path, |terms| *terms = after.clone())?;
+        prepared.push((
+            file,
+            TaxonomyChange {
+                path: relative,
+                before,
+                after,
+            },
+            updated,
+        ));
+    }
+
+    Ok(prepared)
+}
+
+fn content_files(project_path: &Path) -> Vec<(PathBuf, String)> {
+    walk_files(&project_path.join("content"))
+        .into_iter()
+        .filter(|file| matches!(file.extension().and_then(|e| e.to_str()), Some("md") | Some("markdown")))
+        .map(|file| {
+            let relative = file
+                .strip_prefix(project_path)
+                .unwrap_or(&file)
+                .to_string_lossy()
+                .replace('\\', "/");
+            (file, relative)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn site(config: &str, files: &[(&str, &str)]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("config.toml"), config).unwrap();
+        for (path, content) in files {
+            let file = dir.path().join(path);
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+            fs::write(file, content).unwrap();
+        }
+        dir
+    }
+
+    fn read(dir: &tempfile::TempDir, path: &str) -> String {
+        fs::read_to_string(dir.path().join(path)).unwrap()
+    }
+
+    fn hugo_site() -> tempfile::TempDir {
+        site(
+            "baseURL = \"https://example.com/\"\n",
+            &[
+                (
+                    "content/posts/yaml.md",
+                    "---\ntitle: YAML\ntags:\n  - Rust\n  - rust-lang\n  - web\ncategories: [dev]\n---\nBody\n",
+                ),
+                (
+                    "content/posts/toml.md",
+                    "+++\ntitle = \"TOML\"\ntags = [\"Rust\", \"cli\"] # 注释\n+++\nBody\n",
+                ),
+                ("content/posts/inline.md", "---\ntitle: Inline\ntags: Rust\n---\nBody\n"),
+                ("content/posts/scalar.md", "+++\ntitle = \"Scalar\"\ntags = \"Rust\"\n+++\nBody\n"),
+                ("content/posts/flow.md", "---\ntitle: Flow\ntags: [rust, Rust]\n---\nBody\n"),
+            ],
+        )
+    }
+
+    fn rename(from: &str, to: &str) -> TaxonomyEdit {
+        TaxonomyEdit::Rename {
+            taxonomy: "tags".to_string(),
+            from: from.to_string(),
+            to: to.to_string(),
+        }
+    }
+
+    #[test]
+    fn lists_terms_by_usage() {
+        let dir = hugo_site();
+        let terms = list_terms(dir.path(), &EngineType::Hugo).unwrap();
+        assert_eq!(terms[0].taxonomy, "tags");
+        let tags: Vec<(&str, usize)> = terms[0].terms.iter().map(|t| (t.term.as_str(), t.count)).collect();
+        assert_eq!(tags, [("Rust", 5), ("cli", 1), ("rust", 1), ("rust-lang", 1), ("web", 1)]);
+        assert_eq!(terms[1].taxonomy, "categories");
+        assert_eq!(terms[1].terms[0].posts, ["content/posts/yaml.md"]);
+    }
+
+    #[test]
+    fn rename_keeps_each_front_matter_format() {
+        let dir = hugo_site();
+        let changes = apply_edit(dir.path(), &EngineType::Hugo, &rename("Rust", "Rust Lang")).unwrap();
+        assert_eq!(changes.len(), 5);
+
+        assert_eq!(
+            read(&dir, "content/posts/yaml.md"),
+            "---\ntitle: YAML\ntags:\n  - Rust Lang\n  - rust-lang\n  - web\ncategories: [dev]\n---\nBody\n"
+        );
+        assert_eq!(
+            read(&dir, "content/posts/toml.md"),
+            "+++\ntitle = \"TOML\"\ntags = [\"Rust Lang\", \"cli\"] # 注释\n+++\nBody\n"
+        );
+        // 单个字符串的写法保持为字符串
+        assert_eq!(read(&dir, "content/posts/inline.md"), "---\ntitle: Inline\ntags: Rust Lang\n---\nBody\n");
+        assert_eq!(
+            read(&dir, "content/posts/scalar.md"),
+            "+++\ntitle = \"Scalar\"\ntags = \"Rust Lang\"\n+++\nBody\n"
+        );
+        assert_eq!(read(&dir, "content/posts/flow.md"), "---\ntitle: Flow\ntags: [rust, Rust Lang]\n---\nBody\n");
+    }
+
+    #[test]
+    fn merge_removes_duplicates_of_the_target() {
+        let dir = hugo_site();
+        let edit = TaxonomyEdit::Merge {
+            taxonomy: "tags".to_string(),
+            from: vec!["Rust".to_string(), "rust-lang".to_string()],
+            into: "rust".to_string(),
+        };
+
+        let preview = preview_edit(dir.path(), &EngineType::Hugo, &edit).unwrap();
+        let flow = preview.iter().find(|c| c.path == "content/posts/flow.md").unwrap();
+        assert_eq!(flow.before, ["rust", "Rust"]);
+        assert_eq!(flow.after, ["rust"]);
+        // 预览不修改文件
+        assert!(read(&dir, "content/posts/flow.md").contains("[rust, Rust]"));
+
+        apply_edit(dir.path(), &EngineType::Hugo, &edit).unwrap();
+        assert_eq!(
+            read(&dir, "content/posts/yaml.md"),
+            "---\ntitle: YAML\ntags:\n  - rust\n  - web\ncategories: [dev]\n---\nBody\n"
+        );
+        assert_eq!(read(&dir, "content/posts/flow.md"), "---\ntitle: Flow\ntags: [rust]\n---\nBody\n");
+        assert_eq!(read(&dir, "content/posts/inline.md"), "---\ntitle: Inline\ntags: rust\n---\nBody\n");
+    }
+
+    #[test]
+    fn delete_removes_the_term() {
+        let dir = hugo_site();
+        let edit = TaxonomyEdit::Delete {
+            taxonomy: "tags".to_string(),
+            term: "Rust".to_string(),
+        };
+        let changes = apply_edit(dir.path(), &EngineType::Hugo, &edit).unwrap();
+        assert_eq!(changes.len(), 5);
+
+        assert_eq!(
+            read(&dir, "content/posts/toml.md"),
+            "+++\ntitle = \"TOML\"\ntags = [\"cli\"] # 注释\n+++\nBody\n"
+        );
+        assert_eq!(read(&dir, "content/posts/inline.md"), "---\ntitle: Inline\ntags: []\n---\nBody\n");
+        assert!(list_terms(dir.path(), &EngineType::Hugo).unwrap()[0]
+            .terms
+            .iter()
+            .all(|t| t.term != "Rust"));
+    }
+
+    #[test]
+    fn edits_zola_taxonomies_table() {
+        let dir = site(
+            "base_url = \"https://example.com\"\ntaxonomies = [{ name = \"tags\" }]\n",
+            &[
+                (
+                    "content/a.md",
+                    "+++\ntitle = \"A\"\n\n[taxonomies]\ntags = [\"Rust\", \"zola\"]\n+++\nBody\n",
+                ),
+                // Zola 只认 `[taxonomies]` 下的词条
+                ("content/b.md", "+++\ntitle = \"B\"\ntags = [\"Rust\"]\n+++\n"),
+            ],
+        );
+
+        let terms = list_terms(dir.path(), &EngineType::Zola).unwrap();
+        assert_eq!(terms.len(), 1);
+        assert_eq!(terms[0].terms.iter().find(|t| t.term == "Rust").unwrap().count, 1);
+
+        let changes = apply_edit(dir.path(), &EngineType::Zola, &rename("Rust", "rust")).unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(
+            read(&dir, "content/a.md"),
+            "+++\ntitle = \"A\"\n\n[taxonomies]\ntags = [\"rust\", \"zola\"]\n+++\nBody\n"
+        );
+        assert_eq!(read(&dir, "content/b.md"), "+++\ntitle = \"B\"\ntags = [\"Rust\"]\n+++\n");
+    }
+
+    #[test]
+    fn rejects_empty_target() {
+        let dir = hugo_site();
+        assert!(preview_edit(dir.path(), &EngineType::Hugo, &rename("Rust", "  ")).is_err());
+    }
+}
@@ -1,6 +1,13 @@
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Output, Stdio};
+use std::time::Duration;
 use std::env;
+use serde::Serialize;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::process::Child;
 use crate::error::{AppError, Result};
 
 pub fn get_hugo_binary_path() -> Result<PathBuf> {
@@ -183,19 +190,370 @@ pub fn ensure_directory_exists(path: &Path) -> Result<()> {
     Ok(())
 }
 
+/// 子进程的输出流
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
 pub async fn execute_command(
     program: &Path,
     args: &[&str],
     working_dir: &Path,
 ) -> Result<Output> {
-    let output = Command::new(program)
+    execute_command_streaming(program, args, working_dir, &BTreeMap::new(), |_, _| {}).await
+}
+
+/// 与 `execute_command` 相同，另外设置环境变量，并在子进程每输出一行时回调（行尾不含换行）
+pub async fn execute_command_streaming(
+    program: &Path,
+    args: &[&str],
+    working_dir: &Path,
+    envs: &BTreeMap<String, String>,
+    mut on_line: impl FnMut(OutputStream, &str),
+) -> Result<Output> {
+    let mut child = tokio::process::Command::new(program)
+        .args(args)
+        .envs(envs)
+        .current_dir(working_dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| AppError::EngineError(e.to_string()))?;
+
+    let mut stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+    let mut stderr = BufReader::new(child.stderr.take().expect("stderr is piped"));
+    let (mut stdout_all, mut stderr_all) = (Vec::new(), Vec::new());
+    // 读到一半被另一个分支打断时，已读的内容留在行缓冲中，下次继续
+    let (mut stdout_line, mut stderr_line) = (Vec::new(), Vec::new());
+    let (mut stdout_done, mut stderr_done) = (false, false);
+    let line = |buffer: &[u8]| String::from_utf8_lossy(buffer).trim_end_matches(['\r', '\n']).to_string();
+
+    while !(stdout_done && stderr_done) {
+        tokio::select! {
+            read = stdout.read_until(b'\n', &mut stdout_line), if !stdout_done => {
+                stdout_done = read? == 0;
+                // 到达结尾时缓冲中可能还有被打断的读取留下的、没有换行结尾的最后一行
+                if !stdout_line.is_empty() {
+                    on_line(OutputStream::Stdout, &line(&stdout_line));
+                    stdout_all.append(&mut stdout_line);
+                }
+            }
+            read = stderr.read_until(b'\n', &mut stderr_line), if !stderr_done => {
+                stderr_done = read? == 0;
+                // 到达结尾时缓冲中可能还有被打断的读取留下的、没有换行结尾的最后一行
+                if !stderr_line.is_empty() {
+                    on_line(OutputStream::Stderr, &line(&stderr_line));
+                    stderr_all.append(&mut stderr_line);
+                }
+            }
+        }
+    }
+
+    Ok(Output {
+        status: child.wait().await?,
+        stdout: stdout_all,
+        stderr: stderr_all,
+    })
+}
+
+/// 启动一个长期运行的服务器进程（hugo server / zola serve），
+/// 直到它输出包含 `ready` 和端口号的一行、并且 `addr` 接受连接才返回；
+/// 只看端口是否可连接会把抢先占用该端口的其他进程误认成预览服务器。
+/// 进程提前退出时带回它的错误输出。返回的进程在被丢弃时会被终止
+pub async fn spawn_server(
+    program: &Path,
+    args: &[&str],
+    working_dir: &Path,
+    addr: SocketAddr,
+    ready: &str,
+    timeout: Duration,
+) -> Result<Child> {
+    let mut child = tokio::process::Command::new(program)
         .args(args)
         .current_dir(working_dir)
         .stdin(Stdio::null())
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
-        .output()
+        .kill_on_drop(true)
+        .spawn()
         .map_err(|e| AppError::EngineError(e.to_string()))?;
 
-    Ok(output)
+    // 两个输出在整个运行期间都要持续读取，避免管道写满后阻塞服务器进程；
+    // 启动完成后接收端被丢弃，读取任务继续读到进程退出
+    let (lines, mut received) = tokio::sync::mpsc::unbounded_channel();
+    if let Some(stdout) = child.stdout.take() {
+        forward_lines(OutputStream::Stdout, stdout, lines.clone());
+    }
+    if let Some(stderr) = child.stderr.take() {
+        forward_lines(OutputStream::Stderr, stderr, lines.clone());
+    }
+    drop(lines);
+
+    let ready = ready.to_lowercase();
+    let port = format!(":{}", addr.port());
+    let mut errors = Vec::new();
+    let mut announced = false;
+    let started = tokio::time::timeout(timeout, async {
+        loop {
+            while let Ok((stream, line)) = received.try_recv() {
+                let lower = line.to_lowercase();
+                if lower.contains(&ready) && lower.contains(&port) {
+                    announced = true;
+                }
+                if stream == OutputStream::Stderr {
+                    errors.push(line);
+                }
+            }
+            if let Some(status) = child.try_wait()? {
+                return Ok(Some(status));
+            }
+            if announced {
+                let connect = tokio::time::timeout(Duration::from_millis(200), TcpStream::connect(addr)).await;
+                // 连接成功后再确认一次进程仍在运行
+                if matches!(connect, Ok(Ok(_))) {
+                    if let Some(status) = child.try_wait()? {
+                        return Ok(Some(status));
+                    }
+                    return Ok(None);
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(150)).await;
+        }
+    })
+    .await;
+
+    match started {
+        Ok(Ok(None)) => Ok(child),
+        Ok(Ok(Some(status))) => {
+            // 进程已退出，读完剩下的错误输出
+            let _ = tokio::time::timeout(Duration::from_secs(1), async {
+                while let Some((stream, line)) = received.recv().await {
+                    if stream == OutputStream::Stderr {
+                        errors.push(line);
+                    }
+                }
+            })
+            .await;
+            let message = errors.join("\n");
+            let message = message.trim();
+            Err(AppError::ServerStartFailed(if message.is_empty() {
+                format!("server exited with {}", status)
+            } else {
+                message.to_string()
+            }))
+        }
+        Ok(Err(e)) => Err(AppError::Io(e)),
+        Err(_) => {
+            let _ = child.kill().await;
+            Err(AppError::ServerStartFailed(format!(
+                "server did not start listening on {} within {}s",
+                addr,
+                timeout.as_secs()
+            )))
+        }
+    }
+}
+
+fn forward_lines(
+    stream: OutputStream,
+    reader: impl tokio::io::AsyncRead + Send + Unpin + 'static,
+    lines: tokio::sync::mpsc::UnboundedSender<(OutputStream, String)>,
+) {
+    tokio::spawn(async move {
+        let mut reader = BufReader::new(reader).lines();
+        while let Ok(Some(line)) = reader.next_line().await {
+            let _ = lines.send((stream, line));
+        }
+    });
+}
+
+/// 递归列出目录下的所有文件，跳过以 `.` 开头的隐藏目录
+pub fn walk_files(dir: &Path) -> Vec<PathBuf> {
+    fn walk(dir: &Path, files: &mut Vec<PathBuf>) {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                let hidden = path
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .map(|n| n.starts_with('.'))
+                    .unwrap_or(false);
+                if !hidden {
+                    walk(&path, files);
+                }
+            } else {
+                files.push(path);
+            }
+        }
+    }
+
+    let mut files = Vec::new();
+    walk(dir, &mut files);
+    files.sort();
+    files
+}
+
+/// 站点配置文件可能的文件名（Hugo 与 Zola）
+pub const SITE_CONFIG_FILES: [&str; 8] = [
+    "hugo.toml",
+    "hugo.yaml",
+    "hugo.yml",
+    "hugo.json",
+    "config.toml",
+    "config.yaml",
+    "config.yml",
+    "config.json",
+];
+
+pub fn find_site_config(project_path: &Path) -> Option<PathBuf> {
+    SITE_CONFIG_FILES
+        .iter()
+        .map(|name| project_path.join(name))
+        .find(|p| p.exists())
+}
+
+/// 文件所在的站点根目录：最近的一个含有站点配置文件的上级目录
+pub fn find_project_root(file: &Path) -> Option<PathBuf> {
+    file.ancestors()
+        .skip(1)
+        .find(|dir| find_site_config(dir).is_some())
+        .map(Path::to_path_buf)
+}
+
+/// 从站点配置中读取 baseURL（Hugo）或 base_url（Zola），只解析顶层的简单赋值
+pub fn read_site_base_url(project_path: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(find_site_config(project_path)?).ok()?;
+
+    content.lines().find_map(|line| {
+        let line = line.trim().trim_end_matches(',');
+        let (key, value) = line.split_once('=').or_else(|| line.split_once(':'))?;
+        let key = key.trim().trim_matches('"');
+        if !key.eq_ignore_ascii_case("baseurl") && key != "base_url" {
+            return None;
+        }
+        let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+        if value.is_empty() {
+            None
+        } else {
+            Some(value.to_string())
+        }
+    })
+}
+
+/// 解码 URL 中的 `%XX`；编码不合法或解码结果不是 UTF-8 时返回 None
+pub fn percent_decode(input: &str) -> Option<String> {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = input.get(i + 1..i + 3)?;
+            out.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+/// 先把所有内容写入同目录下的临时文件，全部成功后再重命名覆盖原文件
+pub fn write_all_atomic(writes: &[(PathBuf, String)]) -> Result<()> {
+    let mut temps = Vec::with_capacity(writes.len());
+    for (target, content) in writes {
+        let file_name = target.file_name().unwrap_or_default().to_string_lossy();
+        let temp = target.with_file_name(format!(".{}.pineapple-tmp", file_name));
+        if let Err(e) = std::fs::write(&temp, content) {
+            for temp in &temps {
+                let _ = std::fs::remove_file(temp);
+            }
+            let _ = std::fs::remove_file(&temp);
+            return Err(e.into());
+        }
+        temps.push(temp);
+    }
+
+    for (temp, (target, _)) in temps.iter().zip(writes) {
+        std::fs::rename(temp, target)?;
+    }
+    Ok(())
+}
+
+/// 把前端传来的相对路径（如 `content/posts/a.md`）解析到站点目录下，拒绝 `..` 和绝对路径
+pub fn resolve_project_file(project_path: &Path, relative: &str) -> Result<PathBuf> {
+    let relative_path = Path::new(relative);
+    let is_safe = relative_path
+        .components()
+        .all(|c| matches!(c, std::path::Component::Normal(_)));
+    if relative.is_empty() || !is_safe {
+        return Err(AppError::InvalidPath(relative.to_string()));
+    }
+    Ok(project_path.join(relative_path))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    async fn spawn(script: &str, addr: SocketAddr, timeout: Duration) -> Result<Child> {
+        spawn_server(Path::new("sh"), &["-c", script], Path::new("."), addr, "is available at", timeout).await
+    }
+
+    #[test]
+    fn finds_project_root_from_post() {
+        let dir = tempfile::tempdir().unwrap();
+        let post = dir.path().join("content/posts/hello.md");
+        std::fs::create_dir_all(post.parent().unwrap()).unwrap();
+        std::fs::write(dir.path().join("hugo.toml"), "title = 'x'").unwrap();
+        assert_eq!(find_project_root(&post).as_deref(), Some(dir.path()));
+        assert_eq!(find_project_root(Path::new("/no/site/here.md")), None);
+    }
+
+    #[tokio::test]
+    async fn spawn_server_waits_for_listening_line() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let script = format!("sleep 0.5; echo 'Web Server is available at http://{}/'; sleep 30", addr);
+        let mut child = spawn(&script, addr, Duration::from_secs(5)).await.unwrap();
+        assert!(child.try_wait().unwrap().is_none());
+        child.kill().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn spawn_server_ignores_port_owned_by_another_process() {
+        // 端口能连上，但启动的进程从没报告过监听，说明端口被别的进程占用
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let err = spawn("sleep 30", addr, Duration::from_millis(800)).await.unwrap_err();
+        assert!(err.to_string().contains("did not start listening"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn spawn_server_reports_early_exit() {
+        let addr = "127.0.0.1:9".parse().unwrap();
+        let err = spawn("echo starting; echo 'port in use' >&2; exit 3", addr, Duration::from_secs(5)).await.unwrap_err();
+        assert!(matches!(err, AppError::ServerStartFailed(ref message) if message == "port in use"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn spawn_server_drains_output_while_waiting() {
+        // 超过管道缓冲区的输出不会让进程阻塞在写入上
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let script = format!(
+            "head -c 1000000 /dev/zero | tr '\\0' x >&2; echo; echo 'Web server is available at http://{}'; sleep 30",
+            addr
+        );
+        let mut child = spawn(&script, addr, Duration::from_secs(10)).await.unwrap();
+        child.kill().await.unwrap();
+    }
 }
@@ -9,7 +9,8 @@ export interface EditorState {
   saving: boolean
 }
 
-export function useEditor(initialPath?: string) {
+// projectPath 为站点根目录，保存时据此记录修订
+export function useEditor(initialPath?: string, projectPath?: string) {
   const content = ref('')
   const originalContent = ref('')
   const filePath = ref(initialPath || '')
@@ -47,6 +48,7 @@ export function useEditor(initialPath?: string) {
       await invoke('save_file', {
         filePath: filePath.value,
         content: content.value,
+        projectPath,
       })
       originalContent.value = content.value
       return true
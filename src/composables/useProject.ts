@@ -1,6 +1,6 @@
 import { ref } from 'vue'
 import { invoke } from '@tauri-apps/api/core'
-import type { Project, BuildOptions, BuildResult, PostInfo } from '@/types'
+import type { Project, BuildOptions, BuildResult, PostInfo, ServeInfo } from '@/types'
 
 export function useProject() {
   const loading = ref(false)
@@ -71,20 +71,20 @@ export function useProject() {
     projectPath: string,
     engine: 'Hugo',
     port: number = 1313
-  ): Promise<boolean> => {
+  ): Promise<string | null> => {
     loading.value = true
     error.value = null
 
     try {
-      await invoke('serve_project', {
+      const info = await invoke<ServeInfo>('serve_project', {
         projectPath,
         engine,
         port,
       })
-      return true
+      return info.url
     } catch (e) {
       error.value = String(e)
-      return false
+      return null
     } finally {
       loading.value = false
     }
@@ -2,7 +2,7 @@ export interface Project {
   id: string
   name: string
   path: string
-  engine: 'Hugo'
+  engine: 'Hugo' | 'Zola'
   theme?: string
   config: ProjectConfig
   created_at: string
@@ -20,6 +20,18 @@ export interface BuildOptions {
   minify: boolean
   clean: boolean
   draft: boolean
+  environment?: string
+  base_url?: string
+  build_future?: boolean
+  build_expired?: boolean
+  destination?: string
+  cache_dir?: string
+  template_metrics?: boolean
+}
+
+export interface BuildProfile {
+  name: string
+  options: BuildOptions
 }
 
 export interface BuildResult {
@@ -29,6 +41,12 @@ export interface BuildResult {
   errors: string[]
 }
 
+export interface ServeInfo {
+  url: string
+  urls: string[]
+  lan: boolean
+}
+
 export interface PostInfo {
   title: string
   path: string